@@ -0,0 +1,645 @@
+//! Offline configuration file backend
+//!
+//! Writes credentials into supplicant configuration files instead of talking
+//! to a running daemon, e.g. during factory provisioning. Connections are only
+//! stored; they are activated when the supplicant starts.
+
+use std::{
+    fs,
+    io::{self, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tracing::debug;
+
+use crate::{
+    backend::{
+        WifiBackend, WpactrlBackend,
+        iwd_backend::{self, parse_profile_name, profile_name, psk_profile},
+    },
+    core::{
+        error::{WifiError, WifiResult},
+        types::{
+            BssDetails, Capabilities, ConnectOptions, ConnectionState, ConnectionStatus, DppConfig,
+            ScanOptions, Ssid, WifiNetwork, WpsMethod, normalize_bssid,
+        },
+    },
+};
+
+/// Header of a newly created wpa_supplicant configuration
+const WPA_CONFIG_HEADER: &str = "ctrl_interface=DIR=/run/wpa_supplicant GROUP=netdev\n\
+                                 update_config=1\n";
+
+/// Configuration file format to provision
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// `wpa_supplicant-<iface>.conf` network blocks (also used with systemd-networkd)
+    #[value(name = "wpa_supplicant")]
+    WpaSupplicant,
+    /// iwd known network profiles (`<ssid>.psk`)
+    Iwd,
+}
+
+impl ConfigFormat {
+    /// Default location: the configuration file (wpa_supplicant) or profile
+    /// directory (iwd)
+    pub fn default_path(self, interface: &str) -> PathBuf {
+        match self {
+            ConfigFormat::WpaSupplicant => PathBuf::from(format!(
+                "/etc/wpa_supplicant/wpa_supplicant-{}.conf",
+                interface
+            )),
+            ConfigFormat::Iwd => PathBuf::from(iwd_backend::DEFAULT_PROFILE_DIR),
+        }
+    }
+}
+
+/// Replace `path` with `contents` without ever exposing a partial file
+///
+/// The file is created with mode 0600 since it holds credentials.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No file name"))?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let write = || -> io::Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp_path)?;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    };
+
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })
+}
+
+/// Value of an SSID in a wpa_supplicant configuration
+///
+/// Printable SSIDs are quoted, anything that would need escaping is written
+/// as hex, which wpa_supplicant accepts without quotes.
+fn wpa_ssid_value(ssid: &Ssid) -> String {
+    let printable = ssid
+        .as_bytes()
+        .iter()
+        .all(|b| (0x20..0x7f).contains(b) && *b != b'"' && *b != b'\\');
+    if printable {
+        format!("\"{}\"", String::from_utf8_lossy(ssid.as_bytes()))
+    } else {
+        hex::encode(ssid.as_bytes())
+    }
+}
+
+/// Parse the SSID value of a wpa_supplicant network block
+fn parse_wpa_ssid(value: &str) -> Option<Vec<u8>> {
+    match value.strip_prefix('"') {
+        Some(quoted) => Some(quoted.rsplit_once('"')?.0.as_bytes().to_vec()),
+        None => hex::decode(value).ok(),
+    }
+}
+
+/// A wpa_supplicant configuration split into global lines and network blocks
+#[derive(Debug, Default)]
+struct WpaConfig {
+    globals: Vec<String>,
+    /// Network blocks with the SSID they configure
+    networks: Vec<(Option<Vec<u8>>, Vec<String>)>,
+}
+
+impl WpaConfig {
+    fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        let mut block: Option<(Option<Vec<u8>>, Vec<String>)> = None;
+        // Comments between blocks belong to the block that follows them
+        let mut leading = Vec::new();
+
+        for line in text.lines() {
+            let trimmed = line.trim();
+            match block.as_mut() {
+                Some((ssid, lines)) => {
+                    lines.push(line.to_string());
+                    if let Some(value) = trimmed.strip_prefix("ssid=") {
+                        *ssid = parse_wpa_ssid(value);
+                    }
+                    if trimmed == "}" {
+                        config.networks.extend(block.take());
+                    }
+                }
+                None if trimmed.starts_with("network={") => {
+                    leading.push(line.to_string());
+                    block = Some((None, std::mem::take(&mut leading)));
+                }
+                // Blank lines are regenerated by `render`
+                None if trimmed.is_empty() => {}
+                None if config.networks.is_empty() => config.globals.push(line.to_string()),
+                None => leading.push(line.to_string()),
+            }
+        }
+        // Keep an unterminated block rather than silently dropping it
+        config.networks.extend(block);
+        config.globals.append(&mut leading);
+
+        config
+    }
+
+    fn country(&self) -> Option<String> {
+        self.globals
+            .iter()
+            .find_map(|line| line.trim().strip_prefix("country="))
+            .map(str::to_string)
+    }
+
+    fn set_country(&mut self, country: &str) {
+        let line = format!("country={}", country);
+        match self
+            .globals
+            .iter_mut()
+            .find(|l| l.trim().starts_with("country="))
+        {
+            Some(existing) => *existing = line,
+            None => self.globals.push(line),
+        }
+    }
+
+    /// SSIDs of the network blocks, in file order
+    fn ssids(&self) -> Vec<Ssid> {
+        let mut ssids: Vec<Ssid> = Vec::new();
+        for ssid in self.networks.iter().filter_map(|(ssid, _)| ssid.clone()) {
+            let ssid = Ssid::from(ssid);
+            if !ssids.contains(&ssid) {
+                ssids.push(ssid);
+            }
+        }
+        ssids
+    }
+
+    /// Replace all networks for `ssid` with `block`
+    fn set_network(&mut self, ssid: &Ssid, block: Vec<String>) {
+        self.networks
+            .retain(|(existing, _)| existing.as_deref() != Some(ssid.as_bytes()));
+        self.networks.push((Some(ssid.as_bytes().to_vec()), block));
+    }
+
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for line in &self.globals {
+            text.push_str(line);
+            text.push('\n');
+        }
+        for (_, block) in &self.networks {
+            text.push('\n');
+            for line in block {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+/// Network block for a PSK network
+fn wpa_network_block(
+    ssid: &Ssid,
+    psk: &[u8; 32],
+    options: &ConnectOptions,
+) -> WifiResult<Vec<String>> {
+    let mut block = vec![
+        "network={".to_string(),
+        format!("\tssid={}", wpa_ssid_value(ssid)),
+        "\tkey_mgmt=WPA-PSK".to_string(),
+        // An unquoted 64 hex digit PSK is used as is
+        format!("\tpsk={}", hex::encode(psk)),
+    ];
+    if let Some(bssid) = &options.bssid {
+        let bssid = normalize_bssid(bssid)
+            .ok_or_else(|| WifiError::ConnectionFailed(format!("Invalid BSSID: {}", bssid)))?;
+        block.push(format!("\tbssid={}", bssid));
+    }
+    if let Some(freq_list) = options.freq_list.as_ref().filter(|f| !f.is_empty()) {
+        let freqs: Vec<String> = freq_list.iter().map(u32::to_string).collect();
+        block.push(format!("\tfreq_list={}", freqs.join(" ")));
+    }
+    block.push("}".to_string());
+
+    Ok(block)
+}
+
+/// Backend writing credentials to configuration files for later activation
+pub struct ConfigFileBackend {
+    format: ConfigFormat,
+    /// Configuration file (wpa_supplicant) or profile directory (iwd)
+    path: PathBuf,
+    /// Saved `wpa_cli scan_results` output served as scan results
+    scan_cache: Option<PathBuf>,
+    /// SSID of the last stored network
+    stored: Mutex<Option<Ssid>>,
+}
+
+impl ConfigFileBackend {
+    /// Create a new config file backend writing to `path`
+    pub fn new(format: ConfigFormat, path: PathBuf, scan_cache: Option<PathBuf>) -> Self {
+        Self {
+            format,
+            path,
+            scan_cache,
+            stored: Mutex::new(None),
+        }
+    }
+
+    fn config_error(&self, action: &str, e: io::Error) -> WifiError {
+        WifiError::BackendUnavailable(format!(
+            "Failed to {} {}: {}",
+            action,
+            self.path.display(),
+            e
+        ))
+    }
+
+    fn read_wpa_config(&self) -> WifiResult<WpaConfig> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => Ok(WpaConfig::parse(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(WpaConfig::parse(WPA_CONFIG_HEADER))
+            }
+            Err(e) => Err(self.config_error("read", e)),
+        }
+    }
+
+    fn write_wpa_config(&self, config: &WpaConfig) -> WifiResult<()> {
+        write_atomic(&self.path, config.render().as_bytes())
+            .map_err(|e| self.config_error("write", e))
+    }
+
+    fn read_scan_cache(&self) -> WifiResult<String> {
+        match &self.scan_cache {
+            Some(path) => fs::read_to_string(path).map_err(|e| {
+                WifiError::ScanFailed(format!("Failed to read {}: {}", path.display(), e))
+            }),
+            None => Ok(String::new()),
+        }
+    }
+}
+
+impl WifiBackend for ConfigFileBackend {
+    async fn scan(&self, _options: &ScanOptions) -> WifiResult<Vec<WifiNetwork>> {
+        // Without a radio the cached results are all there is
+        let networks = WpactrlBackend::parse_scan_results(&self.read_scan_cache()?);
+        debug!("Loaded {} cached networks", networks.len());
+        Ok(networks)
+    }
+
+    async fn abort_scan(&self) -> WifiResult<()> {
+        // Scans only read the cache, there is nothing to abort
+        Ok(())
+    }
+
+    async fn network_details(&self, bssid: &str) -> WifiResult<BssDetails> {
+        let cache = self.read_scan_cache()?;
+        let line = cache
+            .lines()
+            .skip(1)
+            .map(|line| line.split('\t').collect::<Vec<_>>())
+            .find(|parts| parts.len() >= 5 && parts[0] == bssid)
+            .ok_or_else(|| WifiError::BssNotFound(bssid.to_string()))?;
+
+        let flags = line[3]
+            .split(['[', ']'])
+            .filter(|flag| !flag.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        // The cache only has the scan results table, no IEs
+        Ok(BssDetails {
+            bssid: bssid.to_string(),
+            ssid: WpactrlBackend::decode_ssid(line[4]),
+            frequency: line[1].parse().unwrap_or(0),
+            channel: WpactrlBackend::frequency_to_channel(line[1]),
+            rssi: line[2].parse().unwrap_or(0),
+            noise: None,
+            beacon_interval: 0,
+            capabilities: 0,
+            supported_rates: vec![],
+            ht: false,
+            vht: false,
+            he: false,
+            wps: None,
+            flags,
+            age_secs: 0,
+            ies: String::new(),
+        })
+    }
+
+    async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> WifiResult<()> {
+        if ssid.is_empty() || ssid.len() > Ssid::MAX_LEN {
+            return Err(WifiError::InvalidSsid(format!(
+                "SSID must be 1 to {} octets, got {}",
+                Ssid::MAX_LEN,
+                ssid.len()
+            )));
+        }
+
+        match self.format {
+            ConfigFormat::WpaSupplicant => {
+                let block = wpa_network_block(ssid, psk, options)?;
+                let mut config = self.read_wpa_config()?;
+                config.set_network(ssid, block);
+                self.write_wpa_config(&config)?;
+            }
+            ConfigFormat::Iwd => {
+                if *options != ConnectOptions::default() {
+                    debug!("iwd profiles cannot pin BSSID or frequencies, ignoring options");
+                }
+                let path = self.path.join(profile_name(ssid));
+                write_atomic(&path, psk_profile(psk).as_bytes())
+                    .map_err(|e| self.config_error("write profile in", e))?;
+            }
+        }
+
+        debug!("Stored credentials for {}, pending activation", ssid);
+        *self.stored.lock().unwrap() = Some(ssid.clone());
+        Ok(())
+    }
+
+    async fn start_wps(
+        &self,
+        _method: &WpsMethod,
+        _bssid: Option<&str>,
+    ) -> WifiResult<Option<String>> {
+        Err(WifiError::NotSupported("WPS".into()))
+    }
+
+    async fn wps_result(&self) -> WifiResult<bool> {
+        Ok(false)
+    }
+
+    async fn cancel_wps(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("WPS".into()))
+    }
+
+    async fn start_dpp(&self) -> WifiResult<String> {
+        Err(WifiError::NotSupported("DPP".into()))
+    }
+
+    async fn dpp_config(&self) -> WifiResult<Option<DppConfig>> {
+        Ok(None)
+    }
+
+    async fn stop_dpp(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("DPP".into()))
+    }
+
+    async fn get_country(&self) -> WifiResult<Option<String>> {
+        match self.format {
+            ConfigFormat::WpaSupplicant => Ok(self.read_wpa_config()?.country()),
+            ConfigFormat::Iwd => Ok(None),
+        }
+    }
+
+    async fn set_country(&self, country: &str, _persist: bool) -> WifiResult<()> {
+        // The configuration file is the only place to keep it, so it always persists
+        match self.format {
+            ConfigFormat::WpaSupplicant => {
+                let mut config = self.read_wpa_config()?;
+                config.set_country(country);
+                self.write_wpa_config(&config)
+            }
+            ConfigFormat::Iwd => Err(WifiError::NotSupported("Setting the country code".into())),
+        }
+    }
+
+    async fn capabilities(&self) -> WifiResult<Capabilities> {
+        // Only WPA-PSK networks are written
+        Ok(Capabilities {
+            persistence: true,
+            set_country: self.format == ConfigFormat::WpaSupplicant,
+            ..Default::default()
+        })
+    }
+
+    async fn disconnect(&self) -> WifiResult<()> {
+        // Stored credentials stay in place, only the pending state is dropped
+        *self.stored.lock().unwrap() = None;
+        Ok(())
+    }
+
+    async fn status(&self) -> WifiResult<ConnectionStatus> {
+        let stored = self.stored.lock().unwrap().clone();
+        Ok(ConnectionStatus {
+            state: if stored.is_some() {
+                ConnectionState::Stored
+            } else {
+                ConnectionState::Idle
+            },
+            ssid: stored,
+            ip_address: None,
+            // The country code is added by the connection service
+            country: None,
+        })
+    }
+
+    async fn saved_networks(&self) -> WifiResult<Vec<Ssid>> {
+        match self.format {
+            ConfigFormat::WpaSupplicant => Ok(self.read_wpa_config()?.ssids()),
+            ConfigFormat::Iwd => {
+                let entries = match fs::read_dir(&self.path) {
+                    Ok(entries) => entries,
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => return Err(self.config_error("list profiles in", e)),
+                };
+                let mut ssids: Vec<Ssid> = entries
+                    .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                    .filter_map(|name| parse_profile_name(&name))
+                    .collect();
+                ssids.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+                Ok(ssids)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::Band;
+
+    const SCAN_CACHE: &str = "bssid / frequency / signal level / flags / ssid\n\
+                              aa:bb:cc:dd:ee:01\t2437\t-48\t[WPA2-PSK-CCMP][ESS]\tHomeNet\n\
+                              aa:bb:cc:dd:ee:02\t5180\t-70\t[WPA2-PSK-CCMP][WPS][ESS]\tCaf\\xc3\\xa9\n";
+
+    fn backend(dir: &tempfile::TempDir, format: ConfigFormat) -> ConfigFileBackend {
+        let cache = dir.path().join("scan_results");
+        fs::write(&cache, SCAN_CACHE).unwrap();
+        let path = match format {
+            ConfigFormat::WpaSupplicant => dir.path().join("wpa_supplicant-wlan0.conf"),
+            ConfigFormat::Iwd => dir.path().to_path_buf(),
+        };
+        ConfigFileBackend::new(format, path, Some(cache))
+    }
+
+    #[test]
+    fn test_wpa_ssid_value() {
+        assert_eq!(wpa_ssid_value(&"Home Net".into()), "\"Home Net\"");
+        assert_eq!(wpa_ssid_value(&"a\"b".into()), "612262");
+        assert_eq!(wpa_ssid_value(&"Café".into()), "436166c3a9");
+        assert_eq!(
+            wpa_ssid_value(&"line\nbreak".into()),
+            "6c696e650a627265616b"
+        );
+        assert_eq!(parse_wpa_ssid("\"Home Net\""), Some(b"Home Net".to_vec()));
+        assert_eq!(
+            parse_wpa_ssid("436166c3a9"),
+            Some("Café".as_bytes().to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_writes_wpa_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = backend(&dir, ConfigFormat::WpaSupplicant);
+
+        let options = ConnectOptions {
+            bssid: Some("AA:BB:CC:DD:EE:01".to_string()),
+            band: Some(Band::Band2_4Ghz),
+            freq_list: Some(vec![2412, 2437]),
+        };
+        backend
+            .connect(&"HomeNet".into(), &[0x11; 32], &options)
+            .await
+            .unwrap();
+
+        let config = fs::read_to_string(&backend.path).unwrap();
+        assert_eq!(
+            config,
+            format!(
+                "{}\nnetwork={{\n\tssid=\"HomeNet\"\n\tkey_mgmt=WPA-PSK\n\tpsk={}\n\
+                 \tbssid=aa:bb:cc:dd:ee:01\n\tfreq_list=2412 2437\n}}\n",
+                WPA_CONFIG_HEADER,
+                "11".repeat(32)
+            )
+        );
+        let mode = fs::metadata(&backend.path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Stored);
+        assert_eq!(status.ssid, Some("HomeNet".into()));
+    }
+
+    #[tokio::test]
+    async fn test_connect_replaces_existing_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = backend(&dir, ConfigFormat::WpaSupplicant);
+        fs::write(
+            &backend.path,
+            "ctrl_interface=/run/wpa_supplicant\ncountry=DE\n\n\
+             network={\n\tssid=\"Other\"\n\tpsk=\"other-pass\"\n}\n\n\
+             network={\n\tssid=486f6d654e6574\n\tpsk=\"old-pass\"\n}\n",
+        )
+        .unwrap();
+
+        backend
+            .connect(&"HomeNet".into(), &[0x22; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
+
+        let config = fs::read_to_string(&backend.path).unwrap();
+        assert!(config.starts_with("ctrl_interface=/run/wpa_supplicant\ncountry=DE\n"));
+        assert!(config.contains("ssid=\"Other\""));
+        assert!(!config.contains("old-pass"));
+        assert_eq!(config.matches("network={").count(), 2);
+        assert!(config.contains(&format!("psk={}", "22".repeat(32))));
+        // No temporary file is left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+        assert_eq!(
+            backend.saved_networks().await.unwrap(),
+            [Ssid::from("Other"), Ssid::from("HomeNet")]
+        );
+
+        assert_eq!(backend.get_country().await.unwrap(), Some("DE".to_string()));
+        backend.set_country("US", true).await.unwrap();
+        let config = fs::read_to_string(&backend.path).unwrap();
+        assert!(config.contains("country=US\n"));
+        assert!(!config.contains("country=DE"));
+    }
+
+    #[tokio::test]
+    async fn test_connect_writes_iwd_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = backend(&dir, ConfigFormat::Iwd);
+
+        backend
+            .connect(&"Café".into(), &[0x33; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
+
+        let profile = fs::read_to_string(dir.path().join("=436166c3a9.psk")).unwrap();
+        assert_eq!(
+            profile,
+            format!("[Security]\nPreSharedKey={}\n", "33".repeat(32))
+        );
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Stored
+        );
+
+        backend.disconnect().await.unwrap();
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+
+        // Stored profiles stay saved, temporary files are not profiles
+        fs::write(dir.path().join(".Home.psk.tmp"), "").unwrap();
+        backend
+            .connect(&"Home".into(), &[0x44; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.saved_networks().await.unwrap(),
+            [Ssid::from("Café"), Ssid::from("Home")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = backend(&dir, ConfigFormat::WpaSupplicant);
+
+        let networks = backend.scan(&ScanOptions::default()).await.unwrap();
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[1].ssid, "Café");
+        assert_eq!(networks[1].channel, 36);
+
+        let details = backend.network_details("aa:bb:cc:dd:ee:02").await.unwrap();
+        assert_eq!(details.frequency, 5180);
+        assert_eq!(details.flags, vec!["WPA2-PSK-CCMP", "WPS", "ESS"]);
+        assert!(matches!(
+            backend.network_details("aa:bb:cc:dd:ee:ff").await,
+            Err(WifiError::BssNotFound(_))
+        ));
+
+        // Without a cache there is nothing to report
+        let backend = ConfigFileBackend::new(ConfigFormat::Iwd, dir.path().to_path_buf(), None);
+        assert!(
+            backend
+                .scan(&ScanOptions::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
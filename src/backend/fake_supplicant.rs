@@ -0,0 +1,548 @@
+//! In-process wpa_supplicant stand-in for backend tests
+//!
+//! Serves the control interface protocol on a Unix datagram socket with a
+//! scriptable set of access points, so `WpactrlBackend` can be exercised end
+//! to end without hardware.
+
+use std::{
+    collections::BTreeMap,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::core::types::{Ssid, passphrase_to_psk};
+
+/// Access point visible to the fake supplicant
+#[derive(Debug, Clone)]
+pub struct FakeAp {
+    pub bssid: String,
+    pub ssid: Ssid,
+    pub freq: u32,
+    pub signal: i16,
+    pub flags: String,
+    pub passphrase: String,
+}
+
+impl FakeAp {
+    /// WPA2-PSK access point with a good signal
+    pub fn new(bssid: &str, ssid: impl Into<Ssid>, freq: u32, passphrase: &str) -> Self {
+        Self {
+            bssid: bssid.to_string(),
+            ssid: ssid.into(),
+            freq,
+            signal: -50,
+            flags: "[WPA2-PSK-CCMP][ESS]".to_string(),
+            passphrase: passphrase.to_string(),
+        }
+    }
+
+    fn psk_hex(&self) -> String {
+        hex::encode(passphrase_to_psk(&self.passphrase, &self.ssid))
+    }
+}
+
+/// Network configured with `ADD_NETWORK`/`SET_NETWORK`
+#[derive(Debug, Default)]
+struct Network {
+    ssid: Option<Ssid>,
+    psk: Option<String>,
+    bssid: Option<String>,
+    freq_list: Vec<u32>,
+}
+
+#[derive(Debug)]
+struct State {
+    aps: Vec<FakeAp>,
+    /// Results of the last `SCAN`
+    scan_results: Vec<FakeAp>,
+    networks: BTreeMap<u32, Network>,
+    next_network_id: u32,
+    wpa_state: &'static str,
+    /// Network id and BSSID of the current association
+    current: Option<(u32, String)>,
+    attached: Vec<PathBuf>,
+    country: Option<String>,
+    update_config: bool,
+    /// `GET_CAPABILITY` replies by field
+    capabilities: BTreeMap<String, String>,
+    dpp_bootstrap: Option<u32>,
+    dpp_config_processing: u8,
+    /// Commands answered with `FAIL`
+    rejected: Vec<String>,
+    commands: Vec<String>,
+}
+
+/// Fake wpa_supplicant serving one control socket
+pub struct FakeSupplicant {
+    path: PathBuf,
+    socket: Arc<UnixDatagram>,
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Escape an SSID the way wpa_supplicant prints it (`printf_encode`)
+fn printf_encode(ssid: &Ssid) -> String {
+    let mut text = String::new();
+    for &b in ssid.as_bytes() {
+        match b {
+            b'"' => text.push_str("\\\""),
+            b'\\' => text.push_str("\\\\"),
+            0x1b => text.push_str("\\e"),
+            b'\n' => text.push_str("\\n"),
+            b'\r' => text.push_str("\\r"),
+            b'\t' => text.push_str("\\t"),
+            0x20..=0x7e => text.push(b as char),
+            _ => text.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    text
+}
+
+/// Parse a `SET_NETWORK ssid` value (hex or quoted)
+fn parse_ssid(value: &str) -> Option<Ssid> {
+    match value.strip_prefix('"') {
+        Some(quoted) => Some(quoted.strip_suffix('"')?.into()),
+        None => hex::decode(value).ok().map(Ssid::from),
+    }
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            aps: Vec::new(),
+            scan_results: Vec::new(),
+            networks: BTreeMap::new(),
+            next_network_id: 0,
+            wpa_state: "DISCONNECTED",
+            current: None,
+            attached: Vec::new(),
+            country: None,
+            update_config: true,
+            capabilities: [
+                ("key_mgmt", "NONE WPA-PSK WPA-EAP SAE DPP"),
+                ("eap", "MD5 TLS PEAP MSCHAPV2 WSC"),
+                (
+                    "freq",
+                    "Mode[G] Channels: 2412 2437 2462\nMode[A] Channels: 5180 5745 5955\n",
+                ),
+            ]
+            .into_iter()
+            .map(|(field, reply)| (field.to_string(), reply.to_string()))
+            .collect(),
+            dpp_bootstrap: None,
+            // Supplicant set up to add received networks itself
+            dpp_config_processing: 2,
+            rejected: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// Handle one command; returns the reply and events for attached clients
+    fn handle(&mut self, command: &str, sender: Option<&Path>) -> (String, Vec<String>) {
+        self.commands.push(command.to_string());
+        let mut events = Vec::new();
+        let (name, args) = command.split_once(' ').unwrap_or((command, ""));
+
+        let reply = match name {
+            _ if self.rejected.iter().any(|rejected| rejected == name) => "FAIL\n".to_string(),
+            "PING" => "PONG\n".to_string(),
+            "ATTACH" => match sender {
+                Some(sender) => {
+                    self.attached.push(sender.to_path_buf());
+                    "OK\n".to_string()
+                }
+                None => "FAIL\n".to_string(),
+            },
+            "DETACH" => {
+                self.attached
+                    .retain(|client| Some(client.as_path()) != sender);
+                "OK\n".to_string()
+            }
+            "SCAN" => {
+                let freqs: Option<Vec<u32>> = args
+                    .split(' ')
+                    .find_map(|arg| arg.strip_prefix("freq="))
+                    .map(|list| list.split(',').filter_map(|f| f.parse().ok()).collect());
+                self.scan_results = self
+                    .aps
+                    .iter()
+                    .filter(|ap| freqs.as_ref().is_none_or(|freqs| freqs.contains(&ap.freq)))
+                    .cloned()
+                    .collect();
+                events.push("CTRL-EVENT-SCAN-RESULTS ".to_string());
+                "OK\n".to_string()
+            }
+            "ABORT_SCAN" => "OK\n".to_string(),
+            "SCAN_RESULTS" => {
+                let mut reply = "bssid / frequency / signal level / flags / ssid\n".to_string();
+                for ap in &self.scan_results {
+                    reply.push_str(&format!(
+                        "{}\t{}\t{}\t{}\t{}\n",
+                        ap.bssid,
+                        ap.freq,
+                        ap.signal,
+                        ap.flags,
+                        printf_encode(&ap.ssid)
+                    ));
+                }
+                reply
+            }
+            "BSS" => match self.scan_results.iter().find(|ap| ap.bssid == args) {
+                Some(ap) => {
+                    let mut ies = vec![0, ap.ssid.len() as u8];
+                    ies.extend_from_slice(ap.ssid.as_bytes());
+                    // Supported rates: 6 (basic), 12, 24 Mbit/s
+                    ies.extend_from_slice(&[1, 3, 0x8c, 0x18, 0x30]);
+                    format!(
+                        "bssid={}\nfreq={}\nbeacon_int=100\ncapabilities=0x0411\n\
+                         noise=-92\nlevel={}\nage=1\nie={}\nflags={}\nssid={}\n",
+                        ap.bssid,
+                        ap.freq,
+                        ap.signal,
+                        hex::encode(&ies),
+                        ap.flags,
+                        printf_encode(&ap.ssid)
+                    )
+                }
+                None => String::new(),
+            },
+            "ADD_NETWORK" => {
+                let id = self.next_network_id;
+                self.next_network_id += 1;
+                self.networks.insert(id, Network::default());
+                format!("{}\n", id)
+            }
+            "SET_NETWORK" => self.set_network(args),
+            "LIST_NETWORKS" => {
+                let mut reply = "network id / ssid / bssid / flags\n".to_string();
+                for (id, network) in &self.networks {
+                    let current = self.current.as_ref().is_some_and(|(c, _)| c == id);
+                    reply.push_str(&format!(
+                        "{}\t{}\t{}\t{}\n",
+                        id,
+                        network.ssid.as_ref().map(printf_encode).unwrap_or_default(),
+                        network.bssid.as_deref().unwrap_or("any"),
+                        if current { "[CURRENT]" } else { "" }
+                    ));
+                }
+                reply
+            }
+            "ENABLE_NETWORK" => "OK\n".to_string(),
+            "SELECT_NETWORK" => match args.parse() {
+                Ok(id) if self.networks.contains_key(&id) => {
+                    events.extend(self.associate(id));
+                    "OK\n".to_string()
+                }
+                _ => "FAIL\n".to_string(),
+            },
+            "DISCONNECT" => {
+                if let Some((id, bssid)) = self.current.take() {
+                    events.push(format!(
+                        "CTRL-EVENT-DISCONNECTED bssid={} reason=3 locally_generated=1 id={}",
+                        bssid, id
+                    ));
+                }
+                self.wpa_state = "DISCONNECTED";
+                "OK\n".to_string()
+            }
+            "STATUS" => self.status(),
+            "GET" if args == "country" => match &self.country {
+                Some(country) => country.clone(),
+                None => "FAIL\n".to_string(),
+            },
+            "GET" if args == "dpp_config_processing" => {
+                format!("{}", self.dpp_config_processing)
+            }
+            "GET" if args == "update_config" => {
+                format!("{}", u8::from(self.update_config))
+            }
+            "GET_CAPABILITY" => match self.capabilities.get(args) {
+                Some(reply) => reply.clone(),
+                None => "FAIL\n".to_string(),
+            },
+            "SET" => match args.split_once(' ') {
+                Some(("country", country)) => {
+                    self.country = Some(country.to_string());
+                    events.push(format!(
+                        "CTRL-EVENT-REGDOM-CHANGE init=USER type=COUNTRY alpha2={}",
+                        country
+                    ));
+                    "OK\n".to_string()
+                }
+                Some(("dpp_config_processing", value)) => match value.parse() {
+                    Ok(value) => {
+                        self.dpp_config_processing = value;
+                        "OK\n".to_string()
+                    }
+                    Err(_) => "FAIL\n".to_string(),
+                },
+                Some(_) => "OK\n".to_string(),
+                None => "FAIL\n".to_string(),
+            },
+            "SAVE_CONFIG" => "OK\n".to_string(),
+            "DPP_BOOTSTRAP_GEN" => {
+                self.dpp_bootstrap = Some(1);
+                "1\n".to_string()
+            }
+            "DPP_BOOTSTRAP_GET_URI" if self.dpp_bootstrap.is_some() => {
+                "DPP:C:81/6;M:020000000001;K:MDkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDIgAD;;\n".to_string()
+            }
+            "DPP_BOOTSTRAP_REMOVE" => {
+                self.dpp_bootstrap = None;
+                "OK\n".to_string()
+            }
+            "DPP_LISTEN" | "DPP_STOP_LISTEN" => "OK\n".to_string(),
+            "WPS_PBC" | "WPS_CANCEL" => "OK\n".to_string(),
+            "WPS_PIN" => match args.split_once(' ') {
+                Some((_, pin)) => format!("{}\n", pin),
+                None => "12345670\n".to_string(),
+            },
+            _ => "UNKNOWN COMMAND\n".to_string(),
+        };
+
+        (reply, events)
+    }
+
+    fn set_network(&mut self, args: &str) -> String {
+        let mut parts = args.splitn(3, ' ');
+        let (Some(id), Some(field), Some(value)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return "FAIL\n".to_string();
+        };
+        let Some(network) = id.parse().ok().and_then(|id| self.networks.get_mut(&id)) else {
+            return "FAIL\n".to_string();
+        };
+
+        match field {
+            "ssid" => match parse_ssid(value) {
+                Some(ssid) => network.ssid = Some(ssid),
+                None => return "FAIL\n".to_string(),
+            },
+            "psk" => network.psk = Some(value.to_string()),
+            "bssid" => network.bssid = Some(value.to_string()),
+            "freq_list" => {
+                match value
+                    .split(' ')
+                    .map(str::parse)
+                    .collect::<Result<Vec<u32>, _>>()
+                {
+                    Ok(freqs) => network.freq_list = freqs,
+                    Err(_) => return "FAIL\n".to_string(),
+                }
+            }
+            _ => {}
+        }
+        "OK\n".to_string()
+    }
+
+    /// Try to associate with network `id`, returning the resulting events
+    fn associate(&mut self, id: u32) -> Vec<String> {
+        let network = &self.networks[&id];
+        let candidate = self.aps.iter().find(|ap| {
+            Some(&ap.ssid) == network.ssid.as_ref()
+                && network
+                    .bssid
+                    .as_ref()
+                    .is_none_or(|bssid| *bssid == ap.bssid)
+                && (network.freq_list.is_empty() || network.freq_list.contains(&ap.freq))
+        });
+
+        let Some(ap) = candidate else {
+            self.current = None;
+            self.wpa_state = "SCANNING";
+            return vec!["CTRL-EVENT-NETWORK-NOT-FOUND".to_string()];
+        };
+
+        let psk = match network.psk.as_deref() {
+            Some(quoted) if quoted.starts_with('"') => {
+                hex::encode(passphrase_to_psk(quoted.trim_matches('"'), &ap.ssid))
+            }
+            Some(hex) => hex.to_string(),
+            None => String::new(),
+        };
+        if psk != ap.psk_hex() {
+            self.current = None;
+            self.wpa_state = "DISCONNECTED";
+            return vec![format!(
+                "CTRL-EVENT-SSID-TEMP-DISABLED id={} ssid=\"{}\" auth_failures=1 duration=10 reason=WRONG_KEY",
+                id,
+                printf_encode(&ap.ssid)
+            )];
+        }
+
+        let event = format!(
+            "CTRL-EVENT-CONNECTED - Connection to {} completed [id={} id_str=]",
+            ap.bssid, id
+        );
+        self.current = Some((id, ap.bssid.clone()));
+        self.wpa_state = "COMPLETED";
+        vec![event]
+    }
+
+    fn status(&self) -> String {
+        let mut status = String::new();
+        if let Some((id, bssid)) = &self.current {
+            if let Some(ap) = self.aps.iter().find(|ap| ap.bssid == *bssid) {
+                status.push_str(&format!(
+                    "bssid={}\nfreq={}\nssid={}\nid={}\nmode=station\nkey_mgmt=WPA2-PSK\n",
+                    bssid,
+                    ap.freq,
+                    printf_encode(&ap.ssid),
+                    id
+                ));
+            }
+        }
+        status.push_str(&format!("wpa_state={}\n", self.wpa_state));
+        status
+    }
+}
+
+impl FakeSupplicant {
+    /// Serve a control socket at `path`
+    pub fn start(path: &Path) -> Self {
+        let socket = Arc::new(UnixDatagram::bind(path).expect("Failed to bind control socket"));
+        socket
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let state = Arc::new(Mutex::new(State::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let (socket, state, stop) = (socket.clone(), state.clone(), stop.clone());
+            std::thread::spawn(move || {
+                let mut buffer = [0u8; 4096];
+                while !stop.load(Ordering::Relaxed) {
+                    let Ok((len, sender)) = socket.recv_from(&mut buffer) else {
+                        continue;
+                    };
+                    let command = String::from_utf8_lossy(&buffer[..len]).to_string();
+                    let sender = sender.as_pathname().map(Path::to_path_buf);
+
+                    let (reply, events) = state
+                        .lock()
+                        .unwrap()
+                        .handle(command.trim(), sender.as_deref());
+                    if let Some(sender) = &sender {
+                        let _ = socket.send_to(reply.as_bytes(), sender);
+                    }
+                    Self::broadcast(&socket, &state, &events);
+                }
+            })
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            socket,
+            state,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    fn broadcast(socket: &UnixDatagram, state: &Mutex<State>, events: &[String]) {
+        let attached = state.lock().unwrap().attached.clone();
+        for event in events {
+            let message = format!("<3>{}", event);
+            for client in &attached {
+                let _ = socket.send_to(message.as_bytes(), client);
+            }
+        }
+    }
+
+    /// Path of the control socket
+    pub fn path(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    /// Make an access point visible to subsequent scans and associations
+    pub fn add_ap(&self, ap: FakeAp) {
+        self.state.lock().unwrap().aps.push(ap);
+    }
+
+    /// Remove an access point, dropping the association if it is in use
+    pub fn remove_ap(&self, bssid: &str) {
+        let event = {
+            let mut state = self.state.lock().unwrap();
+            state.aps.retain(|ap| ap.bssid != bssid);
+            match state.current.take() {
+                Some((id, current)) if current == bssid => {
+                    state.wpa_state = "SCANNING";
+                    Some(format!(
+                        "CTRL-EVENT-DISCONNECTED bssid={} reason=4 id={}",
+                        bssid, id
+                    ))
+                }
+                current => {
+                    state.current = current;
+                    None
+                }
+            }
+        };
+        Self::broadcast(&self.socket, &self.state, &Vec::from_iter(event));
+    }
+
+    /// Set the reply to `GET_CAPABILITY <field>`
+    pub fn set_capability(&self, field: &str, reply: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .capabilities
+            .insert(field.to_string(), reply.to_string());
+    }
+
+    /// Set the `update_config` option reported by `GET`
+    pub fn set_update_config(&self, update_config: bool) {
+        self.state.lock().unwrap().update_config = update_config;
+    }
+
+    /// Answer `command` with `FAIL` from now on
+    pub fn reject(&self, command: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .rejected
+            .push(command.to_string());
+    }
+
+    /// `dpp_config_processing` as last set
+    pub fn dpp_config_processing(&self) -> u8 {
+        self.state.lock().unwrap().dpp_config_processing
+    }
+
+    /// Send an event to all attached clients
+    pub fn send_event(&self, event: &str) {
+        Self::broadcast(&self.socket, &self.state, &[event.to_string()]);
+    }
+
+    /// Number of attached event clients
+    pub fn attached(&self) -> usize {
+        self.state.lock().unwrap().attached.len()
+    }
+
+    /// All commands received so far
+    pub fn commands(&self) -> Vec<String> {
+        self.state.lock().unwrap().commands.clone()
+    }
+
+    /// Current `wpa_state`
+    pub fn wpa_state(&self) -> &'static str {
+        self.state.lock().unwrap().wpa_state
+    }
+}
+
+impl Drop for FakeSupplicant {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
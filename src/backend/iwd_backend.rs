@@ -0,0 +1,1114 @@
+//! iwd backend implementation (D-Bus)
+
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use dbus::{
+    Message, Path,
+    arg::{PropMap, prop_cast},
+    blocking::{
+        Connection,
+        stdintf::org_freedesktop_dbus::{ObjectManager, Properties},
+    },
+    channel::Channel,
+    message::MessageType,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    backend::{WifiBackend, WpactrlBackend, config_file_backend::write_atomic},
+    core::{
+        error::{WifiError, WifiResult},
+        types::{
+            BssDetails, Capabilities, ConnectOptions, ConnectionState, ConnectionStatus, DppConfig,
+            ScanOptions, Security, Ssid, WifiNetwork, WpsMethod,
+        },
+    },
+};
+
+pub(crate) const IWD_BUS_NAME: &str = "net.connman.iwd";
+pub(crate) const IWD_AGENT_MANAGER_PATH: &str = "/net/connman/iwd";
+pub(crate) const IWD_AGENT_MANAGER_IFACE: &str = "net.connman.iwd.AgentManager";
+pub(crate) const IWD_AGENT_IFACE: &str = "net.connman.iwd.Agent";
+pub(crate) const IWD_DEVICE_IFACE: &str = "net.connman.iwd.Device";
+pub(crate) const IWD_STATION_IFACE: &str = "net.connman.iwd.Station";
+pub(crate) const IWD_NETWORK_IFACE: &str = "net.connman.iwd.Network";
+pub(crate) const IWD_BSS_IFACE: &str = "net.connman.iwd.BasicServiceSet";
+pub(crate) const IWD_KNOWN_NETWORK_IFACE: &str = "net.connman.iwd.KnownNetwork";
+
+/// Object path of the agent exported while connecting
+const AGENT_PATH: &str = "/net/connman/iwd/commissioning_agent";
+
+/// Directory iwd loads known network profiles from
+pub const DEFAULT_PROFILE_DIR: &str = "/var/lib/iwd";
+
+/// Timeout for single D-Bus method calls
+const DBUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound for a scan requested with `Station.Scan`
+const SCAN_TIMEOUT: Duration = Duration::from_secs(15);
+const SCAN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound for `Network.Connect`, which returns once iwd is connected
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+type ManagedObjects = HashMap<Path<'static>, HashMap<String, PropMap>>;
+
+/// Network as listed by `Station.GetOrderedNetworks`
+#[derive(Debug, Clone)]
+struct IwdNetwork {
+    ssid: Ssid,
+    /// Addresses of the network's BSSs (empty on iwd < 2.0)
+    bssids: Vec<String>,
+    /// Signal strength in dBm
+    rssi: i16,
+    security: String,
+}
+
+impl IwdNetwork {
+    fn to_network(&self) -> WifiNetwork {
+        WifiNetwork {
+            ssid: self.ssid.clone(),
+            mac: self.bssids.first().cloned().unwrap_or_default(),
+            // iwd does not export BSS frequencies
+            channel: 0,
+            frequency: 0,
+            rssi: self.rssi,
+            security: Security::from_flags(&security_flags(&self.security)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Describe iwd network security the way wpa_supplicant flags do
+fn security_flags(security: &str) -> Vec<String> {
+    let mut flags: Vec<String> = match security {
+        "psk" => vec!["WPA2-PSK".to_string()],
+        "8021x" => vec!["WPA2-EAP".to_string()],
+        "wep" => vec!["WEP".to_string()],
+        _ => vec![],
+    };
+    flags.push("ESS".to_string());
+    flags
+}
+
+/// Map iwd station state to the service connection state
+fn station_state_to_connection_state(state: &str) -> ConnectionState {
+    match state {
+        "connected" => ConnectionState::Connected,
+        "connecting" | "roaming" => ConnectionState::Connecting,
+        _ => ConnectionState::Idle,
+    }
+}
+
+/// File name iwd expects for the PSK profile of `ssid`
+///
+/// SSIDs made of alphanumerics, space, `-` and `_` are used as is, all others
+/// are hex-encoded with a leading `=`.
+pub(crate) fn profile_name(ssid: &Ssid) -> String {
+    let plain = ssid
+        .as_bytes()
+        .iter()
+        .all(|b| b.is_ascii_alphanumeric() || b" -_".contains(b));
+    if plain {
+        format!("{}.psk", String::from_utf8_lossy(ssid.as_bytes()))
+    } else {
+        format!("={}.psk", hex::encode(ssid.as_bytes()))
+    }
+}
+
+/// SSID of the iwd profile named `file_name`, the inverse of `profile_name`
+///
+/// Open and 802.1X profiles are recognized as well.
+pub(crate) fn parse_profile_name(file_name: &str) -> Option<Ssid> {
+    let (name, _) = [".psk", ".open", ".8021x"]
+        .iter()
+        .find_map(|suffix| file_name.strip_suffix(suffix).map(|name| (name, suffix)))?;
+    let ssid: Ssid = match name.strip_prefix('=') {
+        Some(encoded) => hex::decode(encoded).ok()?.into(),
+        None => name.into(),
+    };
+    (!ssid.is_empty()).then_some(ssid)
+}
+
+/// Contents of an iwd profile for a PSK network
+pub(crate) fn psk_profile(psk: &[u8; 32]) -> String {
+    format!("[Security]\nPreSharedKey={}\n", hex::encode(psk))
+}
+
+fn iwd_error(e: dbus::Error) -> WifiError {
+    WifiError::IwdError(e.to_string())
+}
+
+/// iwd backend using the D-Bus API
+///
+/// iwd's agent API only accepts passphrases, while the service only knows the
+/// derived PSK. The PSK is therefore stored as known network profile before
+/// `Network.Connect`; the agent registered for the attempt declines any
+/// remaining passphrase requests so that iwd fails fast instead of waiting.
+pub struct IwdBackend {
+    interface: String,
+    /// Private bus address; the system bus is used if `None`
+    bus_address: Option<String>,
+    profile_dir: PathBuf,
+}
+
+impl IwdBackend {
+    /// Create a new iwd backend on the system bus
+    pub fn new(interface: String) -> Self {
+        Self {
+            interface,
+            bus_address: None,
+            profile_dir: PathBuf::from(DEFAULT_PROFILE_DIR),
+        }
+    }
+
+    /// Create a new iwd backend talking to the bus at `address`, storing
+    /// profiles in `profile_dir`
+    pub fn with_bus_address(interface: String, address: String, profile_dir: PathBuf) -> Self {
+        Self {
+            interface,
+            bus_address: Some(address),
+            profile_dir,
+        }
+    }
+
+    /// Run `f` with a bus connection, the managed objects and the station path
+    async fn with_station<T, F>(&self, f: F) -> WifiResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection, &ManagedObjects, &Path<'static>) -> WifiResult<T> + Send + 'static,
+    {
+        let interface = self.interface.clone();
+        let bus_address = self.bus_address.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = match bus_address {
+                Some(address) => Channel::open_private(&address)
+                    .and_then(|mut channel| channel.register().map(|()| channel))
+                    .map(Connection::from),
+                None => Connection::new_system(),
+            }
+            .map_err(|e| WifiError::BackendUnavailable(format!("D-Bus: {}", e)))?;
+
+            let objects = Self::managed_objects(&conn)?;
+            let station = objects
+                .iter()
+                .find(|(_, ifaces)| {
+                    ifaces.contains_key(IWD_STATION_IFACE)
+                        && ifaces
+                            .get(IWD_DEVICE_IFACE)
+                            .and_then(|device| prop_cast::<String>(device, "Name"))
+                            .is_some_and(|name| *name == interface)
+                })
+                .map(|(path, _)| path.clone())
+                .ok_or_else(|| {
+                    WifiError::InterfaceError(format!("{}: no iwd station", interface))
+                })?;
+
+            f(&conn, &objects, &station)
+        })
+        .await
+        .map_err(|e| WifiError::IwdError(format!("Task join error: {}", e)))?
+    }
+
+    fn managed_objects(conn: &Connection) -> WifiResult<ManagedObjects> {
+        conn.with_proxy(IWD_BUS_NAME, "/", DBUS_TIMEOUT)
+            .get_managed_objects()
+            .map_err(iwd_error)
+    }
+
+    fn ordered_networks(
+        conn: &Connection,
+        objects: &ManagedObjects,
+        station: &Path<'static>,
+    ) -> WifiResult<Vec<IwdNetwork>> {
+        let (ordered,): (Vec<(Path<'static>, i16)>,) = conn
+            .with_proxy(IWD_BUS_NAME, station, DBUS_TIMEOUT)
+            .method_call(IWD_STATION_IFACE, "GetOrderedNetworks", ())
+            .map_err(iwd_error)?;
+
+        Ok(ordered
+            .iter()
+            .filter_map(|(path, signal)| {
+                let network = objects.get(path)?.get(IWD_NETWORK_IFACE)?;
+                let bssids = prop_cast::<Vec<Path<'static>>>(network, "ExtendedServiceSet")
+                    .map(|ess| {
+                        ess.iter()
+                            .filter_map(|bss| objects.get(bss)?.get(IWD_BSS_IFACE))
+                            .filter_map(|bss| prop_cast::<String>(bss, "Address"))
+                            .map(|address| address.to_ascii_lowercase())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                Some(IwdNetwork {
+                    ssid: prop_cast::<String>(network, "Name")?.as_str().into(),
+                    bssids,
+                    // Signal strength is reported in 100 * dBm
+                    rssi: signal / 100,
+                    security: prop_cast::<String>(network, "Type")
+                        .cloned()
+                        .unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+
+    /// Find the network object of `ssid` on `station`
+    fn find_network(
+        objects: &ManagedObjects,
+        station: &Path<'static>,
+        ssid: &str,
+    ) -> Option<(Path<'static>, String)> {
+        objects.iter().find_map(|(path, ifaces)| {
+            let network = ifaces.get(IWD_NETWORK_IFACE)?;
+            let on_station = prop_cast::<Path<'static>>(network, "Device") == Some(station);
+            let name = prop_cast::<String>(network, "Name")?;
+            (on_station && name == ssid).then(|| {
+                let security = prop_cast::<String>(network, "Type").cloned();
+                (path.clone(), security.unwrap_or_default())
+            })
+        })
+    }
+
+    /// Trigger a scan and wait until iwd reports it finished
+    fn scan_blocking(conn: &Connection, station: &Path<'static>) -> WifiResult<()> {
+        let proxy = conn.with_proxy(IWD_BUS_NAME, station, DBUS_TIMEOUT);
+
+        match proxy.method_call::<(), _, _, _>(IWD_STATION_IFACE, "Scan", ()) {
+            Ok(()) => {}
+            // A scan is already running, its results are just as good
+            Err(e) if e.name() == Some("net.connman.iwd.Busy") => {}
+            Err(e) => return Err(WifiError::ScanFailed(e.to_string())),
+        }
+
+        let deadline = Instant::now() + SCAN_TIMEOUT;
+        while proxy
+            .get::<bool>(IWD_STATION_IFACE, "Scanning")
+            .map_err(iwd_error)?
+        {
+            if Instant::now() >= deadline {
+                return Err(WifiError::ScanFailed("Scan timed out".into()));
+            }
+            std::thread::sleep(SCAN_POLL_INTERVAL);
+        }
+
+        Ok(())
+    }
+
+    /// Store `psk` as iwd known network profile, atomically
+    fn write_profile(
+        profile_dir: &std::path::Path,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+    ) -> WifiResult<PathBuf> {
+        let path = profile_dir.join(profile_name(ssid));
+        write_atomic(&path, psk_profile(psk).as_bytes()).map_err(|e| {
+            WifiError::IwdError(format!("Failed to write profile {}: {}", path.display(), e))
+        })?;
+
+        Ok(path)
+    }
+
+    /// Answer a method call to the agent
+    fn agent_reply(call: &Message) -> Message {
+        match call.member().as_deref() {
+            Some("Release") | Some("Cancel") => call.method_return(),
+            request => {
+                warn!("Declining iwd agent request {:?}", request);
+                call.error(
+                    &"net.connman.iwd.Agent.Error.Canceled".into(),
+                    &CString::new("No passphrase available, only a PSK").unwrap(),
+                )
+            }
+        }
+    }
+
+    /// Call `Network.Connect` while serving the agent on the same connection
+    fn connect_blocking(conn: &Connection, network: &Path<'static>) -> WifiResult<()> {
+        let agent_path = Path::from(AGENT_PATH);
+        let manager = conn.with_proxy(IWD_BUS_NAME, IWD_AGENT_MANAGER_PATH, DBUS_TIMEOUT);
+        manager
+            .method_call::<(), _, _, _>(
+                IWD_AGENT_MANAGER_IFACE,
+                "RegisterAgent",
+                (agent_path.clone(),),
+            )
+            .map_err(iwd_error)?;
+
+        let result = (|| {
+            let call =
+                Message::new_method_call(IWD_BUS_NAME, network, IWD_NETWORK_IFACE, "Connect")
+                    .map_err(WifiError::IwdError)?;
+            let channel = conn.channel();
+            let serial = channel
+                .send(call)
+                .map_err(|()| WifiError::IwdError("Failed to send Connect".into()))?;
+
+            let deadline = Instant::now() + CONNECT_TIMEOUT;
+            while Instant::now() < deadline {
+                channel
+                    .read_write(Some(Duration::from_millis(100)))
+                    .map_err(|()| WifiError::IwdError("D-Bus connection lost".into()))?;
+
+                while let Some(mut message) = channel.pop_message() {
+                    match message.msg_type() {
+                        MessageType::MethodCall
+                            if message.path().as_ref() == Some(&agent_path)
+                                && message.interface().as_deref() == Some(IWD_AGENT_IFACE) =>
+                        {
+                            let _ = channel.send(Self::agent_reply(&message));
+                        }
+                        MessageType::MethodReturn | MessageType::Error
+                            if message.get_reply_serial() == Some(serial) =>
+                        {
+                            return match message.as_result() {
+                                Ok(_) => Ok(()),
+                                // Connecting to the current network is not an error
+                                Err(e) if e.name() == Some("net.connman.iwd.AlreadyConnected") => {
+                                    Ok(())
+                                }
+                                Err(e) => Err(WifiError::ConnectionFailed(e.to_string())),
+                            };
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            Err(WifiError::ConnectionFailed("Connect timed out".into()))
+        })();
+
+        if let Err(e) = manager.method_call::<(), _, _, _>(
+            IWD_AGENT_MANAGER_IFACE,
+            "UnregisterAgent",
+            (agent_path,),
+        ) {
+            debug!("Failed to unregister agent: {}", e);
+        }
+
+        result
+    }
+}
+
+impl WifiBackend for IwdBackend {
+    async fn scan(&self, options: &ScanOptions) -> WifiResult<Vec<WifiNetwork>> {
+        debug!("Starting iwd scan on {}", self.interface);
+
+        if *options != ScanOptions::default() {
+            // Station.Scan takes no parameters; hidden networks are listed by iwd itself
+            warn!("iwd backend ignores scan options");
+        }
+
+        let networks = self
+            .with_station(|conn, _, station| {
+                Self::scan_blocking(conn, station)?;
+                // Networks are added during the scan, refresh the object list
+                let objects = Self::managed_objects(conn)?;
+                Self::ordered_networks(conn, &objects, station)
+            })
+            .await?;
+
+        let networks: Vec<WifiNetwork> = networks.iter().map(IwdNetwork::to_network).collect();
+        debug!("Scan completed, found {} networks", networks.len());
+        Ok(networks)
+    }
+
+    async fn abort_scan(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("Aborting scans".into()))
+    }
+
+    async fn network_details(&self, bssid: &str) -> WifiResult<BssDetails> {
+        let bssid = bssid.to_string();
+        let networks = self.with_station(Self::ordered_networks).await?;
+
+        let network = networks
+            .into_iter()
+            .find(|network| network.bssids.contains(&bssid))
+            .ok_or(WifiError::BssNotFound(bssid.clone()))?;
+
+        // iwd exports neither frequencies nor IEs, so those details stay empty
+        Ok(BssDetails {
+            bssid,
+            ssid: network.ssid,
+            frequency: 0,
+            channel: 0,
+            rssi: network.rssi,
+            noise: None,
+            beacon_interval: 0,
+            capabilities: 0,
+            supported_rates: vec![],
+            ht: false,
+            vht: false,
+            he: false,
+            wps: None,
+            flags: security_flags(&network.security),
+            age_secs: 0,
+            ies: String::new(),
+        })
+    }
+
+    async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> WifiResult<()> {
+        let name = std::str::from_utf8(ssid.as_bytes())
+            .map_err(|_| WifiError::InvalidSsid("iwd only supports UTF-8 SSIDs".into()))?
+            .to_string();
+        if name.is_empty() || ssid.len() > Ssid::MAX_LEN {
+            return Err(WifiError::InvalidSsid(format!(
+                "SSID must be 1 to {} octets, got {}",
+                Ssid::MAX_LEN,
+                ssid.len()
+            )));
+        }
+        if *options != ConnectOptions::default() {
+            // iwd selects the BSS itself and offers no way to restrict it
+            warn!("iwd backend ignores BSSID, frequency and band options");
+        }
+
+        debug!("Connecting to network: {}", ssid);
+        let ssid = ssid.clone();
+        let psk = *psk;
+        let profile_dir = self.profile_dir.clone();
+
+        self.with_station(move |conn, objects, station| {
+            let network = match Self::find_network(objects, station, &name) {
+                Some(network) => network,
+                None => {
+                    // The network may not have been seen yet
+                    Self::scan_blocking(conn, station)?;
+                    let objects = Self::managed_objects(conn)?;
+                    Self::find_network(&objects, station, &name).ok_or_else(|| {
+                        WifiError::ConnectionFailed(format!("Network {} not found", name))
+                    })?
+                }
+            };
+            let (network, security) = network;
+            if security != "psk" {
+                return Err(WifiError::ConnectionFailed(format!(
+                    "Unsupported network security: {}",
+                    security
+                )));
+            }
+
+            // A known network keeps its working key if the new one fails
+            let previous = match fs::read(profile_dir.join(profile_name(&ssid))) {
+                Ok(contents) => Some(contents),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(WifiError::IwdError(format!(
+                        "Failed to read profile: {}",
+                        e
+                    )));
+                }
+            };
+            let profile = Self::write_profile(&profile_dir, &ssid, &psk)?;
+            let result = Self::connect_blocking(conn, &network);
+            if result.is_err() {
+                match previous {
+                    Some(previous) => {
+                        if let Err(e) = write_atomic(&profile, &previous) {
+                            warn!("Failed to restore profile {}: {}", profile.display(), e);
+                        }
+                    }
+                    // Keep iwd from retrying a profile with a wrong key
+                    None => {
+                        let _ = fs::remove_file(&profile);
+                    }
+                }
+            }
+            result
+        })
+        .await?;
+
+        debug!("Connected");
+        Ok(())
+    }
+
+    async fn start_wps(
+        &self,
+        _method: &WpsMethod,
+        _bssid: Option<&str>,
+    ) -> WifiResult<Option<String>> {
+        Err(WifiError::NotSupported("WPS".into()))
+    }
+
+    async fn wps_result(&self) -> WifiResult<bool> {
+        Ok(false)
+    }
+
+    async fn cancel_wps(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("WPS".into()))
+    }
+
+    async fn start_dpp(&self) -> WifiResult<String> {
+        Err(WifiError::NotSupported("DPP".into()))
+    }
+
+    async fn dpp_config(&self) -> WifiResult<Option<DppConfig>> {
+        Ok(None)
+    }
+
+    async fn stop_dpp(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("DPP".into()))
+    }
+
+    async fn get_country(&self) -> WifiResult<Option<String>> {
+        // The regulatory domain is not part of the iwd API
+        Ok(None)
+    }
+
+    async fn set_country(&self, _country: &str, _persist: bool) -> WifiResult<()> {
+        Err(WifiError::NotSupported("Setting the country code".into()))
+    }
+
+    async fn capabilities(&self) -> WifiResult<Capabilities> {
+        // connect only stores PSK profiles; bands are not exported
+        Ok(Capabilities {
+            sae: false,
+            enterprise: false,
+            wps: false,
+            dpp: false,
+            band_6ghz: false,
+            persistence: true,
+            set_country: false,
+        })
+    }
+
+    async fn disconnect(&self) -> WifiResult<()> {
+        debug!("Disconnecting from network");
+
+        self.with_station(|conn, _, station| {
+            let result: Result<(), dbus::Error> = conn
+                .with_proxy(IWD_BUS_NAME, station, DBUS_TIMEOUT)
+                .method_call(IWD_STATION_IFACE, "Disconnect", ());
+            match result {
+                Err(e) if e.name() != Some("net.connman.iwd.NotConnected") => Err(iwd_error(e)),
+                _ => Ok(()),
+            }
+        })
+        .await?;
+
+        debug!("Disconnected successfully");
+        Ok(())
+    }
+
+    async fn status(&self) -> WifiResult<ConnectionStatus> {
+        let (state, ssid) = self
+            .with_station(|conn, objects, station| {
+                let proxy = conn.with_proxy(IWD_BUS_NAME, station, DBUS_TIMEOUT);
+                let props = proxy.get_all(IWD_STATION_IFACE).map_err(iwd_error)?;
+                let state = prop_cast::<String>(&props, "State")
+                    .map(|s| station_state_to_connection_state(s))
+                    .unwrap_or(ConnectionState::Idle);
+
+                // ConnectedNetwork is only present while connected
+                let ssid = prop_cast::<Path<'static>>(&props, "ConnectedNetwork")
+                    .and_then(|network| objects.get(network)?.get(IWD_NETWORK_IFACE))
+                    .and_then(|network| prop_cast::<String>(network, "Name"))
+                    .map(|name| Ssid::from(name.as_str()));
+
+                Ok((state, ssid))
+            })
+            .await?;
+
+        let ip_address = if state == ConnectionState::Connected {
+            WpactrlBackend::get_ip_address(&self.interface).await
+        } else {
+            None
+        };
+
+        Ok(ConnectionStatus {
+            state,
+            ssid,
+            ip_address,
+            country: None,
+        })
+    }
+
+    async fn saved_networks(&self) -> WifiResult<Vec<Ssid>> {
+        self.with_station(|_, objects, _| {
+            let mut ssids: Vec<Ssid> = objects
+                .values()
+                .filter_map(|ifaces| ifaces.get(IWD_KNOWN_NETWORK_IFACE))
+                .filter_map(|known| prop_cast::<String>(known, "Name"))
+                .map(|name| Ssid::from(name.as_str()))
+                .collect();
+            // Managed objects come in no particular order
+            ssids.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+            ssids.dedup();
+            Ok(ssids)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_bus::TestBus;
+    use crate::core::types::passphrase_to_psk;
+    use dbus_crossroads::{Crossroads, IfaceBuilder, MethodErr};
+    use std::{
+        os::unix::fs::PermissionsExt,
+        sync::{Arc, Mutex},
+    };
+
+    const STATION_PATH: &str = "/net/connman/iwd/0/3";
+
+    /// Network served by the stand-in
+    struct FakeNetwork {
+        name: &'static str,
+        bssid: &'static str,
+        signal: i16,
+        passphrase: &'static str,
+    }
+
+    /// iwd state served by the stand-in
+    struct FakeIwd {
+        address: String,
+        profile_dir: PathBuf,
+        networks: Vec<FakeNetwork>,
+        state: &'static str,
+        connected: Option<usize>,
+        scans: u32,
+        /// Number of `Scanning` reads that still report a running scan
+        scanning_reads: u32,
+        /// Ask the agent even if a profile exists
+        ignore_profiles: bool,
+        agent: Option<(String, Path<'static>)>,
+        agent_registrations: u32,
+        /// Names of the known networks
+        known: Vec<&'static str>,
+    }
+
+    type Shared = Arc<Mutex<FakeIwd>>;
+
+    fn network_path(index: usize) -> Path<'static> {
+        Path::from(format!("{}/{}_psk", STATION_PATH, index))
+    }
+
+    fn bss_path(index: usize) -> Path<'static> {
+        Path::from(format!("{}/{}_psk/bss", STATION_PATH, index))
+    }
+
+    fn known_path(name: &str) -> Path<'static> {
+        Path::from(format!("/net/connman/iwd/{}_psk", hex::encode(name)))
+    }
+
+    fn network_index(path: &Path) -> Option<usize> {
+        let name = path.strip_prefix(STATION_PATH)?.strip_prefix('/')?;
+        name.split(['_', '/']).next()?.parse().ok()
+    }
+
+    /// Check the key stored for `network`, asking the agent if there is none
+    fn authenticate(iwd: &FakeIwd, network: &FakeNetwork) -> Result<(), MethodErr> {
+        let expected = passphrase_to_psk(network.passphrase, &network.name.into());
+        let profile = fs::read_to_string(iwd.profile_dir.join(format!("{}.psk", network.name)))
+            .ok()
+            .filter(|_| !iwd.ignore_profiles);
+
+        let psk = match profile {
+            Some(profile) => profile
+                .lines()
+                .find_map(|line| line.strip_prefix("PreSharedKey="))
+                .and_then(|key| hex::decode(key).ok()),
+            None => {
+                let (sender, path) = iwd
+                    .agent
+                    .clone()
+                    .ok_or_else(|| MethodErr::from(("net.connman.iwd.NoAgent", "No agent")))?;
+                let conn = TestBus::connect(&iwd.address);
+                let reply: Result<(String,), _> = conn
+                    .with_proxy(sender, path, DBUS_TIMEOUT)
+                    .method_call(IWD_AGENT_IFACE, "RequestPassphrase", (network_path(0),));
+                match reply {
+                    Ok((passphrase,)) => {
+                        Some(passphrase_to_psk(&passphrase, &network.name.into()).to_vec())
+                    }
+                    Err(_) => {
+                        return Err(MethodErr::from(("net.connman.iwd.Aborted", "Canceled")));
+                    }
+                }
+            }
+        };
+
+        match psk {
+            Some(psk) if psk == expected => Ok(()),
+            _ => Err(MethodErr::from((
+                "net.connman.iwd.Failed",
+                "Authentication failed",
+            ))),
+        }
+    }
+
+    /// Serve the iwd interfaces used by the backend on `bus`
+    fn serve_fake_iwd(bus: &TestBus, iwd: Shared) {
+        let address = bus.address.clone();
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let conn = TestBus::connect(&address);
+            conn.request_name(IWD_BUS_NAME, false, true, false).unwrap();
+
+            let mut cr = Crossroads::new();
+            let object_manager = cr.object_manager::<Shared>();
+
+            let agent_manager =
+                cr.register(IWD_AGENT_MANAGER_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                    b.method(
+                        "RegisterAgent",
+                        ("path",),
+                        (),
+                        |ctx, iwd, (path,): (Path<'static>,)| {
+                            let sender = ctx.message().sender().unwrap().to_string();
+                            let mut iwd = iwd.lock().unwrap();
+                            iwd.agent = Some((sender, path));
+                            iwd.agent_registrations += 1;
+                            Ok(())
+                        },
+                    );
+                    b.method(
+                        "UnregisterAgent",
+                        ("path",),
+                        (),
+                        |_, iwd, (_,): (Path<'static>,)| {
+                            iwd.lock().unwrap().agent = None;
+                            Ok(())
+                        },
+                    );
+                });
+
+            let device = cr.register(IWD_DEVICE_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.property("Name").get(|_, _| Ok("wlan0".to_string()));
+            });
+
+            let station = cr.register(IWD_STATION_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.property("State")
+                    .get(|_, iwd| Ok(iwd.lock().unwrap().state.to_string()));
+                b.property("Scanning").get(|_, iwd| {
+                    let mut iwd = iwd.lock().unwrap();
+                    let scanning = iwd.scanning_reads > 0;
+                    iwd.scanning_reads = iwd.scanning_reads.saturating_sub(1);
+                    Ok(scanning)
+                });
+                // Real iwd omits the property while disconnected
+                b.property("ConnectedNetwork").get(|_, iwd| {
+                    Ok(match iwd.lock().unwrap().connected {
+                        Some(index) => network_path(index),
+                        None => Path::from("/"),
+                    })
+                });
+                b.method("Scan", (), (), |_, iwd, ()| {
+                    let mut iwd = iwd.lock().unwrap();
+                    iwd.scans += 1;
+                    iwd.scanning_reads = 2;
+                    Ok(())
+                });
+                b.method("GetOrderedNetworks", (), ("networks",), |_, iwd, ()| {
+                    let iwd = iwd.lock().unwrap();
+                    let networks: Vec<(Path<'static>, i16)> = iwd
+                        .networks
+                        .iter()
+                        .enumerate()
+                        .map(|(index, network)| (network_path(index), network.signal))
+                        .collect();
+                    Ok((networks,))
+                });
+                b.method("Disconnect", (), (), |_, iwd, ()| {
+                    let mut iwd = iwd.lock().unwrap();
+                    if iwd.connected.take().is_none() {
+                        return Err(MethodErr::from((
+                            "net.connman.iwd.NotConnected",
+                            "Not connected",
+                        )));
+                    }
+                    iwd.state = "disconnected";
+                    Ok(())
+                });
+            });
+
+            let network = cr.register(IWD_NETWORK_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                fn with_network<T>(
+                    path: &Path,
+                    iwd: &Shared,
+                    f: impl FnOnce(&FakeNetwork) -> T,
+                ) -> Result<T, MethodErr> {
+                    let iwd = iwd.lock().unwrap();
+                    network_index(path)
+                        .and_then(|index| iwd.networks.get(index))
+                        .map(f)
+                        .ok_or_else(|| MethodErr::no_path(path))
+                }
+
+                b.property("Name")
+                    .get(|ctx, iwd| with_network(ctx.path(), iwd, |n| n.name.to_string()));
+                b.property("Type").get(|_, _| Ok("psk".to_string()));
+                b.property("Device")
+                    .get(|_, _| Ok(Path::from(STATION_PATH)));
+                b.property("ExtendedServiceSet").get(|ctx, _| {
+                    Ok(network_index(ctx.path())
+                        .map(bss_path)
+                        .into_iter()
+                        .collect::<Vec<_>>())
+                });
+                b.method("Connect", (), (), |ctx, iwd, ()| {
+                    let index = network_index(ctx.path()).unwrap();
+                    let mut iwd = iwd.lock().unwrap();
+                    authenticate(&iwd, &iwd.networks[index])?;
+                    iwd.state = "connected";
+                    iwd.connected = Some(index);
+                    Ok(())
+                });
+            });
+
+            let bss = cr.register(IWD_BSS_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.property("Address").get(|ctx, iwd| {
+                    let iwd = iwd.lock().unwrap();
+                    let index = network_index(ctx.path()).unwrap();
+                    Ok(iwd.networks[index].bssid.to_ascii_uppercase())
+                });
+            });
+
+            let known = cr.register(IWD_KNOWN_NETWORK_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.property("Name").get(|ctx, iwd| {
+                    let iwd = iwd.lock().unwrap();
+                    iwd.known
+                        .iter()
+                        .find(|name| known_path(name) == *ctx.path())
+                        .map(|name| name.to_string())
+                        .ok_or_else(|| MethodErr::no_path(ctx.path()))
+                });
+                b.property("Type").get(|_, _| Ok("psk".to_string()));
+            });
+
+            let count = iwd.lock().unwrap().networks.len();
+            for name in iwd.lock().unwrap().known.clone() {
+                cr.insert(known_path(name), &[known], iwd.clone());
+            }
+            cr.insert("/", &[object_manager], iwd.clone());
+            cr.insert(IWD_AGENT_MANAGER_PATH, &[agent_manager], iwd.clone());
+            cr.insert(STATION_PATH, &[device, station], iwd.clone());
+            for index in 0..count {
+                cr.insert(network_path(index), &[network], iwd.clone());
+                cr.insert(bss_path(index), &[bss], iwd.clone());
+            }
+
+            ready_tx.send(()).unwrap();
+            // Serving ends with an error once the test bus is torn down
+            let _ = cr.serve(&conn);
+        });
+
+        ready_rx.recv().unwrap();
+    }
+
+    fn setup() -> (TestBus, tempfile::TempDir, Shared, IwdBackend) {
+        let bus = TestBus::start().expect("dbus-daemon is required for this test");
+        let profile_dir = tempfile::tempdir().unwrap();
+        let iwd = Shared::new(Mutex::new(FakeIwd {
+            address: bus.address.clone(),
+            profile_dir: profile_dir.path().to_path_buf(),
+            networks: vec![
+                FakeNetwork {
+                    name: "HomeNet",
+                    bssid: "aa:bb:cc:dd:ee:01",
+                    signal: -5500,
+                    passphrase: "home-passphrase",
+                },
+                FakeNetwork {
+                    name: "Office",
+                    bssid: "aa:bb:cc:dd:ee:02",
+                    signal: -7200,
+                    passphrase: "office-passphrase",
+                },
+            ],
+            state: "disconnected",
+            connected: None,
+            scans: 0,
+            scanning_reads: 0,
+            ignore_profiles: false,
+            agent: None,
+            agent_registrations: 0,
+            known: vec!["Office", "Café"],
+        }));
+        serve_fake_iwd(&bus, iwd.clone());
+
+        let backend = IwdBackend::with_bus_address(
+            "wlan0".to_string(),
+            bus.address.clone(),
+            profile_dir.path().to_path_buf(),
+        );
+        (bus, profile_dir, iwd, backend)
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_saved_networks() {
+        let (_bus, _dir, _iwd, backend) = setup();
+
+        assert_eq!(
+            backend.saved_networks().await.unwrap(),
+            [Ssid::from("Café"), Ssid::from("Office")]
+        );
+    }
+
+    #[test]
+    fn test_profile_name() {
+        assert_eq!(profile_name(&"Home Net_2-4".into()), "Home Net_2-4.psk");
+        assert_eq!(profile_name(&"Café".into()), "=436166c3a9.psk");
+        assert_eq!(profile_name(&"a/b".into()), "=612f62.psk");
+
+        for ssid in ["Home Net_2-4", "Café", "a/b"] {
+            let ssid = Ssid::from(ssid);
+            assert_eq!(parse_profile_name(&profile_name(&ssid)), Some(ssid));
+        }
+        assert_eq!(parse_profile_name("Guest.open"), Some("Guest".into()));
+        assert_eq!(parse_profile_name("=zz.psk"), None);
+        assert_eq!(parse_profile_name(".psk"), None);
+        assert_eq!(parse_profile_name("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_station_state() {
+        assert_eq!(
+            station_state_to_connection_state("connected"),
+            ConnectionState::Connected
+        );
+        assert_eq!(
+            station_state_to_connection_state("roaming"),
+            ConnectionState::Connecting
+        );
+        assert_eq!(
+            station_state_to_connection_state("disconnecting"),
+            ConnectionState::Idle
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_scan_and_details() {
+        let (_bus, _dir, iwd, backend) = setup();
+
+        let networks = backend.scan(&ScanOptions::default()).await.unwrap();
+        assert_eq!(iwd.lock().unwrap().scans, 1);
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[0].ssid, "HomeNet");
+        assert_eq!(networks[0].mac, "aa:bb:cc:dd:ee:01");
+        assert_eq!(networks[0].rssi, -55);
+        assert_eq!(networks[1].rssi, -72);
+
+        let details = backend.network_details("aa:bb:cc:dd:ee:02").await.unwrap();
+        assert_eq!(details.ssid, "Office");
+        assert_eq!(details.flags, vec!["WPA2-PSK", "ESS"]);
+        assert!(matches!(
+            backend.network_details("aa:bb:cc:dd:ee:ff").await,
+            Err(WifiError::BssNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_connect_stores_profile() {
+        let (_bus, dir, iwd, backend) = setup();
+
+        let psk = passphrase_to_psk("office-passphrase", &"Office".into());
+        backend
+            .connect(&"Office".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+
+        let profile_path = dir.path().join("Office.psk");
+        let profile = fs::read_to_string(&profile_path).unwrap();
+        assert_eq!(
+            profile,
+            format!("[Security]\nPreSharedKey={}\n", hex::encode(psk))
+        );
+        let mode = fs::metadata(&profile_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        {
+            let iwd = iwd.lock().unwrap();
+            assert_eq!(iwd.agent_registrations, 1);
+            assert!(iwd.agent.is_none(), "agent must be unregistered");
+        }
+
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some("Office".into()));
+
+        backend.disconnect().await.unwrap();
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+        // Disconnecting while not connected is not an error
+        backend.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_connect_wrong_psk_removes_profile() {
+        let (_bus, dir, _iwd, backend) = setup();
+
+        let result = backend
+            .connect(&"HomeNet".into(), &[0; 32], &ConnectOptions::default())
+            .await;
+        assert!(matches!(result, Err(WifiError::ConnectionFailed(_))));
+        assert!(!dir.path().join("HomeNet.psk").exists());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_connect_wrong_psk_restores_profile() {
+        let (_bus, dir, _iwd, backend) = setup();
+        let psk = passphrase_to_psk("home-passphrase", &"HomeNet".into());
+        let profile_path = dir.path().join("HomeNet.psk");
+        let saved = format!("[Security]\nPreSharedKey={}\n", hex::encode(psk));
+        fs::write(&profile_path, &saved).unwrap();
+
+        let result = backend
+            .connect(&"HomeNet".into(), &[0; 32], &ConnectOptions::default())
+            .await;
+        assert!(matches!(result, Err(WifiError::ConnectionFailed(_))));
+        assert_eq!(fs::read_to_string(&profile_path).unwrap(), saved);
+
+        backend
+            .connect(&"HomeNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_agent_declines_passphrase_request() {
+        let (_bus, _dir, iwd, backend) = setup();
+        iwd.lock().unwrap().ignore_profiles = true;
+
+        let psk = passphrase_to_psk("home-passphrase", &"HomeNet".into());
+        let result = backend
+            .connect(&"HomeNet".into(), &psk, &ConnectOptions::default())
+            .await;
+        let Err(WifiError::ConnectionFailed(message)) = result else {
+            panic!("Expected connection failure, got {:?}", result);
+        };
+        assert!(message.contains("Canceled"), "{}", message);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_connect_unknown_network() {
+        let (_bus, _dir, iwd, backend) = setup();
+
+        let result = backend
+            .connect(&"Elsewhere".into(), &[0; 32], &ConnectOptions::default())
+            .await;
+        assert!(matches!(result, Err(WifiError::ConnectionFailed(_))));
+        // A scan is attempted before giving up
+        assert_eq!(iwd.lock().unwrap().scans, 1);
+    }
+}
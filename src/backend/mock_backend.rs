@@ -1,23 +1,122 @@
-//! Mock WiFi backend for testing
+//! Mock WiFi backend for testing and demos
 
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::{collections::HashMap, sync::Arc};
+use tokio::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    backend::WifiBackend,
+    backend::{WifiBackend, WpactrlBackend},
     core::{
         error::{WifiError, WifiResult},
-        types::{ConnectionState, ConnectionStatus, WifiNetwork},
+        types::{
+            BssDetails, Capabilities, ConnectOptions, ConnectionState, ConnectionStatus, DppConfig,
+            ScanOptions, Security, Ssid, WifiNetwork, WpsMethod, passphrase_to_psk,
+        },
     },
 };
 
+/// Bootstrap URI handed out by `start_dpp`
+const MOCK_DPP_URI: &str = "DPP:C:81/1;K:MDkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDIgADM2206avxHJaHXgLMkq/24e0rsrfMP9K1Tm8gx+ovP0I=;;";
+
+/// Address handed out by the simulated DHCP server
+const DEFAULT_DHCP_ADDRESS: &str = "192.168.1.100";
+
+/// Passphrase of all networks in the demo environment
+pub const DEMO_PASSPHRASE: &str = "password123";
+
+/// WPA2-PSK access point in the simulated radio environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualAp {
+    pub ssid: Ssid,
+    pub bssid: String,
+    /// Frequency in MHz
+    pub frequency: u32,
+    /// Signal strength in dBm
+    pub rssi: i16,
+    pub passphrase: String,
+}
+
+impl VirtualAp {
+    /// Create an access point with a good signal
+    pub fn new(ssid: impl Into<Ssid>, bssid: &str, frequency: u32, passphrase: &str) -> Self {
+        Self {
+            ssid: ssid.into(),
+            bssid: bssid.to_string(),
+            frequency,
+            rssi: -55,
+            passphrase: passphrase.to_string(),
+        }
+    }
+
+    /// Set the signal strength in dBm
+    pub fn with_rssi(mut self, rssi: i16) -> Self {
+        self.rssi = rssi;
+        self
+    }
+
+    fn channel(&self) -> u16 {
+        WpactrlBackend::frequency_to_channel(&self.frequency.to_string())
+    }
+
+    fn network(&self) -> WifiNetwork {
+        WifiNetwork {
+            ssid: self.ssid.clone(),
+            mac: self.bssid.clone(),
+            channel: self.channel(),
+            frequency: self.frequency,
+            rssi: self.rssi,
+            security: Security::Wpa2,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the AP accepts `psk`, checked against the PBKDF2 derivation
+    fn accepts(&self, psk: &[u8; 32]) -> bool {
+        passphrase_to_psk(&self.passphrase, &self.ssid) == *psk
+    }
+}
+
+/// Association with a virtual AP, resolved against the clock on access
+#[derive(Debug, Clone)]
+struct Association {
+    /// Selected AP, `None` if no AP matched
+    bssid: Option<String>,
+    /// Whether the AP accepted the PSK
+    authenticated: bool,
+    started: Instant,
+}
+
 /// Internal state for the mock backend
 #[derive(Debug, Clone)]
 struct MockState {
     scan_results: Vec<WifiNetwork>,
+    aps: Vec<VirtualAp>,
+    association: Option<Association>,
+    disconnect_at: Option<Instant>,
+    scan_latency: Duration,
+    connect_latency: Duration,
+    dhcp_delay: Duration,
+    dhcp_address: String,
+    bss_details: HashMap<String, BssDetails>,
     should_fail_scan: bool,
     should_fail_connect: bool,
-    connected_ssid: Option<String>,
+    scan_options: Option<ScanOptions>,
+    scan_aborts: usize,
+    connected_ssid: Option<Ssid>,
+    /// Networks with stored credentials, added by `connect`
+    saved: Vec<Ssid>,
+    saved_queries: usize,
+    connect_options: Option<ConnectOptions>,
+    wps_method: Option<WpsMethod>,
+    /// Outcome of the last WPS registration
+    wps_outcome: Option<Result<(), String>>,
+    dpp_listening: bool,
+    dpp_config: Option<DppConfig>,
+    country: Option<String>,
+    country_persisted: bool,
+    capabilities: Capabilities,
     connection_state: ConnectionState,
     ip_address: Option<String>,
 }
@@ -25,6 +124,13 @@ struct MockState {
 /// Mock WiFi backend for testing
 ///
 /// Allows configuring behavior for tests without requiring actual hardware.
+///
+/// Once virtual APs are added, connections are simulated: the PSK is checked
+/// against the AP passphrase, and association and DHCP complete after the
+/// configured latencies. Time is taken from the tokio clock, so tests running
+/// with paused time (`start_paused = true`) are deterministic. Without virtual
+/// APs, connections stay in `Connecting` until `complete_connection` or
+/// `fail_connection` is called.
 #[derive(Debug, Clone)]
 pub struct MockWifiBackend {
     inner: Arc<Mutex<MockState>>,
@@ -36,9 +142,35 @@ impl MockWifiBackend {
         Self {
             inner: Arc::new(Mutex::new(MockState {
                 scan_results: vec![],
+                aps: vec![],
+                association: None,
+                disconnect_at: None,
+                scan_latency: Duration::ZERO,
+                connect_latency: Duration::ZERO,
+                dhcp_delay: Duration::ZERO,
+                dhcp_address: DEFAULT_DHCP_ADDRESS.to_string(),
+                bss_details: HashMap::new(),
                 should_fail_scan: false,
                 should_fail_connect: false,
+                scan_options: None,
+                scan_aborts: 0,
                 connected_ssid: None,
+                saved: vec![],
+                saved_queries: 0,
+                connect_options: None,
+                wps_method: None,
+                wps_outcome: None,
+                dpp_listening: false,
+                dpp_config: None,
+                country: None,
+                country_persisted: false,
+                capabilities: Capabilities {
+                    wps: true,
+                    dpp: true,
+                    persistence: true,
+                    set_country: true,
+                    ..Default::default()
+                },
                 connection_state: ConnectionState::Idle,
                 ip_address: None,
             })),
@@ -50,6 +182,90 @@ impl MockWifiBackend {
         self.inner.lock().await.scan_results = networks;
     }
 
+    /// Create a mock backend simulating a typical home environment
+    ///
+    /// All networks use `DEMO_PASSPHRASE`. Scans and connections take a few
+    /// seconds, like on real hardware.
+    pub async fn demo() -> Self {
+        let backend = Self::new();
+        for ap in [
+            VirtualAp::new("HomeNetwork", "02:00:00:00:01:01", 2437, DEMO_PASSPHRASE),
+            VirtualAp::new("HomeNetwork", "02:00:00:00:01:02", 5180, DEMO_PASSPHRASE)
+                .with_rssi(-62),
+            VirtualAp::new("Office-5G", "02:00:00:00:02:01", 5745, DEMO_PASSPHRASE).with_rssi(-71),
+            VirtualAp::new("Café Gäste", "02:00:00:00:03:01", 2412, DEMO_PASSPHRASE).with_rssi(-78),
+            VirtualAp::new("FarAway", "02:00:00:00:04:01", 2462, DEMO_PASSPHRASE).with_rssi(-88),
+        ] {
+            backend.add_ap(ap).await;
+        }
+        backend.set_scan_latency(Duration::from_secs(2)).await;
+        backend.set_connect_latency(Duration::from_secs(3)).await;
+        backend.set_dhcp_delay(Duration::from_secs(1)).await;
+        backend
+    }
+
+    /// Add an access point to the simulated radio environment
+    pub async fn add_ap(&self, ap: VirtualAp) {
+        self.inner.lock().await.aps.push(ap);
+    }
+
+    /// Remove an access point; an association with it is lost
+    pub async fn remove_ap(&self, bssid: &str) {
+        let mut state = self.inner.lock().await;
+        state.advance();
+        state.aps.retain(|ap| ap.bssid != bssid);
+
+        let selected = state
+            .association
+            .as_ref()
+            .is_some_and(|association| association.bssid.as_deref() == Some(bssid));
+        if selected {
+            if state.associated() {
+                state.drop_link();
+            } else if let Some(association) = &mut state.association {
+                // Association will fail once the attempt times out
+                association.bssid = None;
+            }
+        }
+    }
+
+    /// Time a scan takes to complete
+    pub async fn set_scan_latency(&self, latency: Duration) {
+        self.inner.lock().await.scan_latency = latency;
+    }
+
+    /// Time from connect until the association completes or fails
+    pub async fn set_connect_latency(&self, latency: Duration) {
+        self.inner.lock().await.connect_latency = latency;
+    }
+
+    /// Time from association until an address is leased
+    pub async fn set_dhcp_delay(&self, delay: Duration) {
+        self.inner.lock().await.dhcp_delay = delay;
+    }
+
+    /// Address leased by the simulated DHCP server
+    pub async fn set_dhcp_address(&self, ip: &str) {
+        self.inner.lock().await.dhcp_address = ip.to_string();
+    }
+
+    /// Drop the association that exists `after` from now
+    pub async fn schedule_disconnect(&self, after: Duration) {
+        self.inner.lock().await.disconnect_at = Some(Instant::now() + after);
+    }
+
+    /// Configure detailed BSS information returned by `network_details`
+    ///
+    /// BSSes without explicit details are reported with the data available
+    /// from the scan results.
+    pub async fn set_network_details(&self, details: BssDetails) {
+        self.inner
+            .lock()
+            .await
+            .bss_details
+            .insert(details.bssid.clone(), details);
+    }
+
     /// Configure mock to fail scan operations
     pub async fn set_scan_failure(&self, should_fail: bool) {
         self.inner.lock().await.should_fail_scan = should_fail;
@@ -60,23 +276,160 @@ impl MockWifiBackend {
         self.inner.lock().await.should_fail_connect = should_fail;
     }
 
+    /// Options passed to the most recent scan call
+    pub async fn last_scan_options(&self) -> Option<ScanOptions> {
+        self.inner.lock().await.scan_options.clone()
+    }
+
+    /// Number of `abort_scan` calls
+    pub async fn scan_aborts(&self) -> usize {
+        self.inner.lock().await.scan_aborts
+    }
+
+    /// Number of `saved_networks` calls
+    pub async fn saved_queries(&self) -> usize {
+        self.inner.lock().await.saved_queries
+    }
+
+    /// Configure the networks reported by `saved_networks`
+    pub async fn set_saved_networks(&self, ssids: Vec<Ssid>) {
+        self.inner.lock().await.saved = ssids;
+    }
+
+    /// Options passed to the most recent connect call
+    pub async fn last_connect_options(&self) -> Option<ConnectOptions> {
+        self.inner.lock().await.connect_options.clone()
+    }
+
     /// Simulate connection completion (for async connect testing)
     ///
     /// Call this to simulate the network becoming connected with an IP address
     pub async fn complete_connection(&self, ip: &str) {
         let mut state = self.inner.lock().await;
+        state.association = None;
         state.connection_state = ConnectionState::Connected;
         state.ip_address = Some(ip.to_string());
     }
 
+    /// WPS method of the running registration (if any)
+    pub async fn wps_method(&self) -> Option<WpsMethod> {
+        self.inner.lock().await.wps_method.clone()
+    }
+
+    /// Simulate successful WPS registration with the given network
+    pub async fn complete_wps(&self, ssid: &str, ip: &str) {
+        let mut state = self.inner.lock().await;
+        state.wps_method = None;
+        state.wps_outcome = Some(Ok(()));
+        state.connected_ssid = Some(ssid.into());
+        if !state.saved.iter().any(|saved| *saved == ssid) {
+            state.saved.push(ssid.into());
+        }
+        state.connection_state = ConnectionState::Connected;
+        state.ip_address = Some(ip.to_string());
+    }
+
+    /// Simulate WPS credentials received, association still in progress
+    pub async fn receive_wps_credentials(&self, ssid: &str) {
+        let mut state = self.inner.lock().await;
+        state.wps_method = None;
+        state.wps_outcome = Some(Ok(()));
+        state.connected_ssid = Some(ssid.into());
+        if !state.saved.iter().any(|saved| *saved == ssid) {
+            state.saved.push(ssid.into());
+        }
+        state.connection_state = ConnectionState::Connecting;
+        state.ip_address = None;
+    }
+
+    /// Simulate failed WPS registration (e.g. a push-button overlap)
+    pub async fn fail_wps(&self) {
+        let mut state = self.inner.lock().await;
+        state.wps_method = None;
+        state.wps_outcome = Some(Err("Mock WPS failure".to_string()));
+    }
+
+    /// Whether DPP listening is active
+    pub async fn dpp_listening(&self) -> bool {
+        self.inner.lock().await.dpp_listening
+    }
+
+    /// Simulate a configuration object received from a DPP configurator
+    pub async fn deliver_dpp_config(&self, config: DppConfig) {
+        let mut state = self.inner.lock().await;
+        state.dpp_listening = false;
+        state.dpp_config = Some(config);
+    }
+
+    /// Configure the capabilities reported by `capabilities`
+    pub async fn set_capabilities(&self, capabilities: Capabilities) {
+        self.inner.lock().await.capabilities = capabilities;
+    }
+
+    /// Whether the country code was persisted by the last `set_country`
+    pub async fn country_persisted(&self) -> bool {
+        self.inner.lock().await.country_persisted
+    }
+
     /// Simulate connection failure
     pub async fn fail_connection(&self) {
         let mut state = self.inner.lock().await;
+        state.association = None;
         state.connection_state = ConnectionState::Failed;
         state.ip_address = None;
     }
 }
 
+impl MockState {
+    /// Bring the simulated connection up to date with the clock
+    fn advance(&mut self) {
+        let now = Instant::now();
+
+        if self.disconnect_at.is_some_and(|at| at <= now) {
+            self.disconnect_at = None;
+            if self.association.is_some() || self.connection_state == ConnectionState::Connected {
+                self.drop_link();
+            }
+            return;
+        }
+
+        let Some(association) = &self.association else {
+            return;
+        };
+        let associated_at = association.started + self.connect_latency;
+
+        if now < associated_at {
+            self.connection_state = ConnectionState::Connecting;
+        } else if association.bssid.is_none() || !association.authenticated {
+            // No matching AP or wrong PSK
+            self.association = None;
+            self.connection_state = ConnectionState::Failed;
+            self.ip_address = None;
+        } else if now < associated_at + self.dhcp_delay {
+            self.connection_state = ConnectionState::Connecting;
+        } else {
+            self.connection_state = ConnectionState::Connected;
+            self.ip_address = Some(self.dhcp_address.clone());
+        }
+    }
+
+    /// Whether the association has completed (possibly still waiting for DHCP)
+    fn associated(&self) -> bool {
+        self.association.as_ref().is_some_and(|association| {
+            association.bssid.is_some()
+                && association.authenticated
+                && Instant::now() >= association.started + self.connect_latency
+        })
+    }
+
+    fn drop_link(&mut self) {
+        self.association = None;
+        self.connected_ssid = None;
+        self.connection_state = ConnectionState::Idle;
+        self.ip_address = None;
+    }
+}
+
 impl Default for MockWifiBackend {
     fn default() -> Self {
         Self::new()
@@ -84,43 +437,196 @@ impl Default for MockWifiBackend {
 }
 
 impl WifiBackend for MockWifiBackend {
-    async fn scan(&self) -> WifiResult<Vec<WifiNetwork>> {
-        let state = self.inner.lock().await;
+    async fn scan(&self, options: &ScanOptions) -> WifiResult<Vec<WifiNetwork>> {
+        let latency = self.inner.lock().await.scan_latency;
+        tokio::time::sleep(latency).await;
+
+        let mut state = self.inner.lock().await;
+        state.scan_options = Some(options.clone());
         if state.should_fail_scan {
-            Err(WifiError::ScanFailed("Mock scan failure".into()))
-        } else {
-            Ok(state.scan_results.clone())
+            return Err(WifiError::ScanFailed("Mock scan failure".into()));
+        }
+
+        let mut networks = state.scan_results.clone();
+        networks.extend(state.aps.iter().map(VirtualAp::network));
+        Ok(networks)
+    }
+
+    async fn abort_scan(&self) -> WifiResult<()> {
+        self.inner.lock().await.scan_aborts += 1;
+        Ok(())
+    }
+
+    async fn network_details(&self, bssid: &str) -> WifiResult<BssDetails> {
+        let state = self.inner.lock().await;
+        if let Some(details) = state.bss_details.get(bssid) {
+            return Ok(details.clone());
         }
+
+        let virtual_aps = state.aps.iter().map(VirtualAp::network);
+        state
+            .scan_results
+            .iter()
+            .cloned()
+            .chain(virtual_aps)
+            .find(|network| network.mac == bssid)
+            .map(|network| BssDetails {
+                bssid: network.mac,
+                ssid: network.ssid,
+                frequency: network.frequency,
+                channel: network.channel,
+                rssi: network.rssi,
+                noise: None,
+                beacon_interval: 100,
+                capabilities: 0,
+                supported_rates: vec![],
+                ht: false,
+                vht: false,
+                he: false,
+                wps: None,
+                flags: vec![],
+                age_secs: 0,
+                ies: String::new(),
+            })
+            .ok_or_else(|| WifiError::BssNotFound(bssid.to_string()))
     }
 
-    async fn connect(&self, ssid: &str, _psk: &[u8; 32]) -> WifiResult<()> {
+    async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> WifiResult<()> {
         let mut state = self.inner.lock().await;
         if state.should_fail_connect {
-            Err(WifiError::ConnectionFailed("Mock connect failure".into()))
-        } else {
-            state.connected_ssid = Some(ssid.to_string());
-            state.connection_state = ConnectionState::Connecting;
-            state.ip_address = None;
-            Ok(())
+            return Err(WifiError::ConnectionFailed("Mock connect failure".into()));
+        }
+
+        state.connected_ssid = Some(ssid.clone());
+        if !state.saved.contains(ssid) {
+            state.saved.push(ssid.clone());
         }
+        state.connect_options = Some(options.clone());
+        state.connection_state = ConnectionState::Connecting;
+        state.ip_address = None;
+
+        if !state.aps.is_empty() {
+            // Strongest AP the options allow, like a supplicant would pick
+            let ap = state
+                .aps
+                .iter()
+                .filter(|ap| ap.ssid == *ssid)
+                .filter(|ap| {
+                    options
+                        .bssid
+                        .as_ref()
+                        .is_none_or(|bssid| *bssid == ap.bssid)
+                })
+                .filter(|ap| {
+                    options
+                        .freq_list
+                        .as_ref()
+                        .is_none_or(|freqs| freqs.is_empty() || freqs.contains(&ap.frequency))
+                })
+                .max_by_key(|ap| ap.rssi);
+            state.association = Some(Association {
+                bssid: ap.map(|ap| ap.bssid.clone()),
+                authenticated: ap.is_some_and(|ap| ap.accepts(psk)),
+                started: Instant::now(),
+            });
+        }
+        Ok(())
     }
 
-    async fn disconnect(&self) -> WifiResult<()> {
+    async fn start_wps(
+        &self,
+        method: &WpsMethod,
+        _bssid: Option<&str>,
+    ) -> WifiResult<Option<String>> {
         let mut state = self.inner.lock().await;
-        state.connected_ssid = None;
-        state.connection_state = ConnectionState::Idle;
-        state.ip_address = None;
+        if state.should_fail_connect {
+            return Err(WifiError::WpsFailed("Mock WPS failure".into()));
+        }
+
+        state.wps_method = Some(method.clone());
+        state.wps_outcome = None;
+        Ok(match method {
+            WpsMethod::PushButton => None,
+            WpsMethod::Pin(pin) => Some(pin.clone().unwrap_or_else(|| "12345670".to_string())),
+        })
+    }
+
+    async fn wps_result(&self) -> WifiResult<bool> {
+        match &self.inner.lock().await.wps_outcome {
+            Some(Ok(())) => Ok(true),
+            Some(Err(e)) => Err(WifiError::WpsFailed(e.clone())),
+            None => Ok(false),
+        }
+    }
+
+    async fn cancel_wps(&self) -> WifiResult<()> {
+        let mut state = self.inner.lock().await;
+        state.wps_method = None;
+        state.wps_outcome = None;
+        Ok(())
+    }
+
+    async fn start_dpp(&self) -> WifiResult<String> {
+        let mut state = self.inner.lock().await;
+        if state.should_fail_connect {
+            return Err(WifiError::DppFailed("Mock DPP failure".into()));
+        }
+
+        state.dpp_listening = true;
+        state.dpp_config = None;
+        Ok(MOCK_DPP_URI.to_string())
+    }
+
+    async fn dpp_config(&self) -> WifiResult<Option<DppConfig>> {
+        Ok(self.inner.lock().await.dpp_config.take())
+    }
+
+    async fn stop_dpp(&self) -> WifiResult<()> {
+        self.inner.lock().await.dpp_listening = false;
+        Ok(())
+    }
+
+    async fn get_country(&self) -> WifiResult<Option<String>> {
+        Ok(self.inner.lock().await.country.clone())
+    }
+
+    async fn set_country(&self, country: &str, persist: bool) -> WifiResult<()> {
+        let mut state = self.inner.lock().await;
+        state.country = Some(country.to_string());
+        state.country_persisted = persist;
+        Ok(())
+    }
+
+    async fn capabilities(&self) -> WifiResult<Capabilities> {
+        Ok(self.inner.lock().await.capabilities.clone())
+    }
+
+    async fn disconnect(&self) -> WifiResult<()> {
+        self.inner.lock().await.drop_link();
         Ok(())
     }
 
     async fn status(&self) -> WifiResult<ConnectionStatus> {
-        let state = self.inner.lock().await;
+        let mut state = self.inner.lock().await;
+        state.advance();
         Ok(ConnectionStatus {
             state: state.connection_state,
             ssid: state.connected_ssid.clone(),
             ip_address: state.ip_address.clone(),
+            country: None,
         })
     }
+
+    async fn saved_networks(&self) -> WifiResult<Vec<Ssid>> {
+        let mut state = self.inner.lock().await;
+        state.saved_queries += 1;
+        Ok(state.saved.clone())
+    }
 }
 
 #[cfg(test)]
@@ -132,7 +638,7 @@ mod tests {
         let backend = MockWifiBackend::new();
 
         // Initially empty
-        let results = backend.scan().await.unwrap();
+        let results = backend.scan(&ScanOptions::default()).await.unwrap();
         assert_eq!(results.len(), 0);
 
         // Set results
@@ -142,10 +648,11 @@ mod tests {
                 mac: "aa:bb:cc:dd:ee:ff".into(),
                 channel: 6,
                 rssi: -65,
+                ..Default::default()
             }])
             .await;
 
-        let results = backend.scan().await.unwrap();
+        let results = backend.scan(&ScanOptions::default()).await.unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].ssid, "TestNetwork");
     }
@@ -155,23 +662,51 @@ mod tests {
         let backend = MockWifiBackend::new();
         backend.set_scan_failure(true).await;
 
-        let result = backend.scan().await;
+        let result = backend.scan(&ScanOptions::default()).await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_mock_backend_network_details() {
+        let backend = MockWifiBackend::new();
+        backend
+            .set_scan_results(vec![WifiNetwork {
+                ssid: "TestNetwork".into(),
+                mac: "aa:bb:cc:dd:ee:ff".into(),
+                channel: 6,
+                rssi: -65,
+                ..Default::default()
+            }])
+            .await;
+
+        let details = backend.network_details("aa:bb:cc:dd:ee:ff").await.unwrap();
+        assert_eq!(details.ssid, "TestNetwork");
+        assert_eq!(details.channel, 6);
+
+        let result = backend.network_details("00:00:00:00:00:00").await;
+        assert!(matches!(result, Err(WifiError::BssNotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_mock_backend_connect() {
         let backend = MockWifiBackend::new();
 
         // Connect
         let psk = [0u8; 32];
-        backend.connect("MyNetwork", &psk).await.unwrap();
+        backend
+            .connect(&"MyNetwork".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
 
         // Check status
         let status = backend.status().await.unwrap();
         assert_eq!(status.state, ConnectionState::Connecting);
         assert_eq!(status.ssid, Some("MyNetwork".into()));
         assert_eq!(status.ip_address, None);
+        assert_eq!(
+            backend.saved_networks().await.unwrap(),
+            [Ssid::from("MyNetwork")]
+        );
 
         // Complete connection
         backend.complete_connection("192.168.1.100").await;
@@ -181,13 +716,56 @@ mod tests {
         assert_eq!(status.ip_address, Some("192.168.1.100".into()));
     }
 
+    #[tokio::test]
+    async fn test_mock_backend_wps() {
+        let backend = MockWifiBackend::new();
+
+        let pin = backend
+            .start_wps(&WpsMethod::Pin(None), None)
+            .await
+            .unwrap();
+        assert_eq!(pin, Some("12345670".to_string()));
+        assert_eq!(backend.wps_method().await, Some(WpsMethod::Pin(None)));
+
+        backend.complete_wps("WpsNetwork", "192.168.1.100").await;
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some("WpsNetwork".into()));
+        assert_eq!(backend.wps_method().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_mock_backend_dpp() {
+        let backend = MockWifiBackend::new();
+
+        let uri = backend.start_dpp().await.unwrap();
+        assert!(uri.starts_with("DPP:"));
+        assert!(backend.dpp_listening().await);
+        assert_eq!(backend.dpp_config().await.unwrap(), None);
+
+        let config = DppConfig {
+            ssid: "DppNetwork".into(),
+            passphrase: Some("secret-passphrase".to_string()),
+            psk: None,
+        };
+        backend.deliver_dpp_config(config.clone()).await;
+        assert!(!backend.dpp_listening().await);
+
+        // Configuration is handed out once
+        assert_eq!(backend.dpp_config().await.unwrap(), Some(config));
+        assert_eq!(backend.dpp_config().await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_mock_backend_disconnect() {
         let backend = MockWifiBackend::new();
 
         // Connect and complete
         let psk = [0u8; 32];
-        backend.connect("MyNetwork", &psk).await.unwrap();
+        backend
+            .connect(&"MyNetwork".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
         backend.complete_connection("192.168.1.100").await;
 
         // Disconnect
@@ -198,4 +776,182 @@ mod tests {
         assert_eq!(status.ssid, None);
         assert_eq!(status.ip_address, None);
     }
+
+    fn home_network() -> (Ssid, [u8; 32]) {
+        let ssid = Ssid::from("Home");
+        let psk = passphrase_to_psk("correct horse", &ssid);
+        (ssid, psk)
+    }
+
+    async fn simulated_backend() -> MockWifiBackend {
+        let backend = MockWifiBackend::new();
+        backend
+            .add_ap(
+                VirtualAp::new("Home", "02:00:00:00:00:01", 2437, "correct horse").with_rssi(-70),
+            )
+            .await;
+        backend
+            .add_ap(VirtualAp::new(
+                "Home",
+                "02:00:00:00:00:02",
+                5180,
+                "correct horse",
+            ))
+            .await;
+        backend.set_connect_latency(Duration::from_secs(2)).await;
+        backend.set_dhcp_delay(Duration::from_secs(3)).await;
+        backend
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_demo_environment() {
+        let backend = MockWifiBackend::demo().await;
+
+        let results = backend.scan(&ScanOptions::default()).await.unwrap();
+        assert_eq!(results.len(), 5);
+
+        let ssid = Ssid::from("Café Gäste");
+        let psk = passphrase_to_psk(DEMO_PASSPHRASE, &ssid);
+        backend
+            .connect(&ssid, &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Connected
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_simulated_scan_latency() {
+        let backend = simulated_backend().await;
+        backend.set_scan_latency(Duration::from_secs(4)).await;
+
+        let started = Instant::now();
+        let results = backend.scan(&ScanOptions::default()).await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_secs(4));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].channel, 6);
+        assert_eq!(results[1].channel, 36);
+
+        let details = backend.network_details("02:00:00:00:00:02").await.unwrap();
+        assert_eq!(details.frequency, 5180);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_simulated_connect() {
+        let backend = simulated_backend().await;
+        let (ssid, psk) = home_network();
+
+        backend
+            .connect(&ssid, &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Connecting
+        );
+
+        // Associated, waiting for DHCP
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Connecting);
+        assert_eq!(status.ip_address, None);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some(ssid));
+        assert_eq!(status.ip_address, Some(DEFAULT_DHCP_ADDRESS.to_string()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_simulated_wrong_psk() {
+        let backend = simulated_backend().await;
+        let ssid = Ssid::from("Home");
+        let psk = passphrase_to_psk("wrong horse", &ssid);
+
+        backend
+            .connect(&ssid, &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Connecting
+        );
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Failed);
+        assert_eq!(status.ip_address, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_simulated_connect_options() {
+        let backend = simulated_backend().await;
+        let (ssid, psk) = home_network();
+
+        // Only the weaker 2.4 GHz AP is allowed
+        let options = ConnectOptions {
+            freq_list: Some(vec![2437]),
+            ..Default::default()
+        };
+        backend.connect(&ssid, &psk, &options).await.unwrap();
+
+        // Losing the selected AP during association fails the attempt
+        backend.remove_ap("02:00:00:00:00:01").await;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Failed
+        );
+
+        // No AP with that SSID
+        backend
+            .connect(&"Elsewhere".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Failed
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_simulated_link_loss() {
+        let backend = simulated_backend().await;
+        let (ssid, psk) = home_network();
+
+        backend
+            .connect(&ssid, &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        backend.schedule_disconnect(Duration::from_secs(10)).await;
+        tokio::time::sleep(Duration::from_secs(9)).await;
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Connected
+        );
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Idle);
+        assert_eq!(status.ssid, None);
+
+        // Removing the AP in use drops an established link
+        backend
+            .connect(&ssid, &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Connected
+        );
+        backend.remove_ap("02:00:00:00:00:02").await;
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+    }
 }
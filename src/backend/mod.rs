@@ -1,10 +1,22 @@
 //! WiFi backend abstraction layer
 
+pub mod config_file_backend;
+pub mod iwd_backend;
 pub mod mock_backend;
+pub mod networkmanager_backend;
 pub mod wifi_backend;
 pub mod wpactrl_backend;
 
-pub use {wifi_backend::WifiBackend, wpactrl_backend::WpactrlBackend};
-
 #[cfg(test)]
-pub use mock_backend::MockWifiBackend;
+mod fake_supplicant;
+#[cfg(test)]
+mod test_bus;
+
+pub use {
+    config_file_backend::{ConfigFileBackend, ConfigFormat},
+    iwd_backend::IwdBackend,
+    mock_backend::{MockWifiBackend, VirtualAp},
+    networkmanager_backend::NetworkManagerBackend,
+    wifi_backend::WifiBackend,
+    wpactrl_backend::WpactrlBackend,
+};
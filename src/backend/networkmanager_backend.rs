@@ -0,0 +1,1372 @@
+//! NetworkManager backend implementation (D-Bus)
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use dbus::{
+    Path,
+    arg::{PropMap, RefArg, Variant, prop_cast},
+    blocking::{Connection, stdintf::org_freedesktop_dbus::Properties},
+    channel::Channel,
+    message::MatchRule,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    backend::{WifiBackend, WpactrlBackend},
+    core::{
+        error::{WifiError, WifiResult},
+        types::{
+            Band, BssDetails, Capabilities, ConnectOptions, ConnectionState, ConnectionStatus,
+            DppConfig, ScanOptions, Security, Ssid, WifiNetwork, WpsMethod,
+        },
+    },
+};
+
+pub(crate) const NM_BUS_NAME: &str = "org.freedesktop.NetworkManager";
+pub(crate) const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+pub(crate) const NM_IFACE: &str = "org.freedesktop.NetworkManager";
+pub(crate) const NM_DEVICE_IFACE: &str = "org.freedesktop.NetworkManager.Device";
+pub(crate) const NM_WIRELESS_IFACE: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+pub(crate) const NM_AP_IFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+pub(crate) const NM_IP4_CONFIG_IFACE: &str = "org.freedesktop.NetworkManager.IP4Config";
+pub(crate) const NM_SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+pub(crate) const NM_SETTINGS_IFACE: &str = "org.freedesktop.NetworkManager.Settings";
+pub(crate) const NM_CONNECTION_IFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+
+/// NMDeviceState values
+pub(crate) const NM_DEVICE_STATE_PREPARE: u32 = 40;
+pub(crate) const NM_DEVICE_STATE_ACTIVATED: u32 = 100;
+pub(crate) const NM_DEVICE_STATE_FAILED: u32 = 120;
+const NM_DEVICE_STATE_SECONDARIES: u32 = 90;
+
+/// NM80211ApFlags / NM80211ApSecurityFlags values
+const NM_AP_FLAGS_PRIVACY: u32 = 0x1;
+const NM_AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const NM_AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+const NM_AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+
+/// NMDeviceWifiCapabilities values
+const NM_WIFI_DEVICE_CAP_FREQ_6GHZ: u32 = 0x800;
+
+/// Timeout for single D-Bus method calls
+const DBUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound for a scan requested with `RequestScan`
+const SCAN_TIMEOUT: Duration = Duration::from_secs(10);
+const SCAN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound for a device activation after `AddAndActivateConnection`
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Access point properties as exported by NetworkManager
+#[derive(Debug, Clone)]
+struct AccessPoint {
+    ssid: Ssid,
+    bssid: String,
+    frequency: u32,
+    strength: u8,
+    max_bitrate: u32,
+    flags: Vec<String>,
+    last_seen: i32,
+}
+
+impl AccessPoint {
+    fn to_network(&self) -> WifiNetwork {
+        WifiNetwork {
+            ssid: self.ssid.clone(),
+            mac: self.bssid.clone(),
+            channel: WpactrlBackend::frequency_to_channel(&self.frequency.to_string()),
+            frequency: self.frequency,
+            rssi: strength_to_rssi(self.strength),
+            security: Security::from_flags(&self.flags),
+            ..Default::default()
+        }
+    }
+}
+
+/// Approximate the signal level from NetworkManager's 0..100 strength
+///
+/// NetworkManager maps -100..-40 dBm linearly onto 0..100 %.
+fn strength_to_rssi(strength: u8) -> i16 {
+    i16::from(strength.min(100)) * 60 / 100 - 100
+}
+
+/// Describe AP security the way wpa_supplicant flags do
+fn security_flags(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Vec<String> {
+    let mut result = Vec::new();
+
+    for (prefix, sec) in [("WPA", wpa_flags), ("WPA2", rsn_flags)] {
+        if sec & NM_AP_SEC_KEY_MGMT_PSK != 0 {
+            result.push(format!("{}-PSK", prefix));
+        }
+        if sec & NM_AP_SEC_KEY_MGMT_802_1X != 0 {
+            result.push(format!("{}-EAP", prefix));
+        }
+    }
+    if rsn_flags & NM_AP_SEC_KEY_MGMT_SAE != 0 {
+        result.push("SAE".to_string());
+    }
+    if result.is_empty() && flags & NM_AP_FLAGS_PRIVACY != 0 {
+        result.push("WEP".to_string());
+    }
+    result.push("ESS".to_string());
+
+    result
+}
+
+/// Map NMDeviceState to the service connection state
+fn device_state_to_connection_state(state: u32) -> ConnectionState {
+    match state {
+        NM_DEVICE_STATE_ACTIVATED => ConnectionState::Connected,
+        NM_DEVICE_STATE_PREPARE..=NM_DEVICE_STATE_SECONDARIES => ConnectionState::Connecting,
+        NM_DEVICE_STATE_FAILED => ConnectionState::Failed,
+        _ => ConnectionState::Idle,
+    }
+}
+
+fn nm_error(e: dbus::Error) -> WifiError {
+    WifiError::NetworkManagerError(e.to_string())
+}
+
+fn variant<T: RefArg + 'static>(value: T) -> Variant<Box<dyn RefArg>> {
+    Variant(Box::new(value))
+}
+
+/// Build the options for `RequestScan`
+///
+/// NetworkManager can probe SSIDs but always scans all channels actively.
+fn scan_request_options(options: &ScanOptions) -> PropMap {
+    let mut request = PropMap::new();
+    if !options.ssids.is_empty() {
+        let ssids: Vec<Vec<u8>> = options
+            .ssids
+            .iter()
+            .map(|ssid| ssid.as_bytes().to_vec())
+            .collect();
+        request.insert("ssids".into(), variant(ssids));
+    }
+    request
+}
+
+/// Build the settings for `AddAndActivateConnection`
+fn connection_settings(
+    ssid: &Ssid,
+    psk: &[u8; 32],
+    options: &ConnectOptions,
+) -> HashMap<String, PropMap> {
+    let mut connection = PropMap::new();
+    connection.insert("id".into(), variant(ssid.to_string()));
+    connection.insert("type".into(), variant("802-11-wireless".to_string()));
+
+    let mut wireless = PropMap::new();
+    wireless.insert("ssid".into(), variant(ssid.as_bytes().to_vec()));
+    wireless.insert("mode".into(), variant("infrastructure".to_string()));
+    if let Some(bssid) = options
+        .bssid
+        .as_deref()
+        .and_then(|b| hex::decode(b.replace(':', "")).ok())
+    {
+        wireless.insert("bssid".into(), variant(bssid));
+    }
+
+    // NetworkManager has no frequency list, only a band and an optional channel
+    let freq_list = options.freq_list.as_deref().unwrap_or_default();
+    let freq_band = match freq_list.first().map(|f| Band::from_frequency(*f)) {
+        Some(Some(first))
+            if freq_list
+                .iter()
+                .all(|f| Band::from_frequency(*f) == Some(first)) =>
+        {
+            Some(first)
+        }
+        _ => None,
+    };
+    match options.band.or(freq_band) {
+        Some(Band::Band2_4Ghz) => {
+            wireless.insert("band".into(), variant("bg".to_string()));
+        }
+        Some(Band::Band5Ghz) => {
+            wireless.insert("band".into(), variant("a".to_string()));
+        }
+        Some(Band::Band6Ghz) => debug!("6 GHz band cannot be pinned with NetworkManager, ignoring"),
+        None if !freq_list.is_empty() => {
+            debug!("Frequency list spans several bands, ignoring")
+        }
+        None => {}
+    }
+    if let ([freq], Some(band)) = (freq_list, freq_band) {
+        if options.band.is_none_or(|b| b == band) {
+            let channel = WpactrlBackend::frequency_to_channel(&freq.to_string());
+            wireless.insert("channel".into(), variant(u32::from(channel)));
+        }
+    }
+
+    let mut security = PropMap::new();
+    security.insert("key-mgmt".into(), variant("wpa-psk".to_string()));
+    // A 64 hex digit PSK is used as is
+    security.insert("psk".into(), variant(hex::encode(psk)));
+
+    HashMap::from([
+        ("connection".to_string(), connection),
+        ("802-11-wireless".to_string(), wireless),
+        ("802-11-wireless-security".to_string(), security),
+    ])
+}
+
+/// Saved WiFi connection profiles with their SSID
+fn wifi_profiles(conn: &Connection) -> WifiResult<Vec<(Path<'static>, Ssid)>> {
+    let (paths,): (Vec<Path<'static>>,) = conn
+        .with_proxy(NM_BUS_NAME, NM_SETTINGS_PATH, DBUS_TIMEOUT)
+        .method_call(NM_SETTINGS_IFACE, "ListConnections", ())
+        .map_err(nm_error)?;
+
+    let mut profiles = Vec::new();
+    for path in paths {
+        let settings: Result<(HashMap<String, PropMap>,), _> = conn
+            .with_proxy(NM_BUS_NAME, &path, DBUS_TIMEOUT)
+            .method_call(NM_CONNECTION_IFACE, "GetSettings", ());
+        let settings = match settings {
+            Ok((settings,)) => settings,
+            Err(e) => {
+                // Profiles may be deleted between listing and reading them
+                debug!("Skipping connection {}: {}", path, e);
+                continue;
+            }
+        };
+
+        // Only WiFi profiles have an SSID
+        if let Some(ssid) = settings
+            .get("802-11-wireless")
+            .and_then(|wireless| prop_cast::<Vec<u8>>(wireless, "ssid"))
+        {
+            profiles.push((path, Ssid::from(ssid.clone())));
+        }
+    }
+    Ok(profiles)
+}
+
+/// Settings of the profile at `path` including its WiFi secrets
+///
+/// `GetSettings` leaves out secrets, and `Update` replaces the whole
+/// profile, so they are read separately to be able to write the profile back.
+fn profile_settings(conn: &Connection, path: &Path) -> WifiResult<HashMap<String, PropMap>> {
+    let proxy = conn.with_proxy(NM_BUS_NAME, path, DBUS_TIMEOUT);
+    let (mut settings,): (HashMap<String, PropMap>,) = proxy
+        .method_call(NM_CONNECTION_IFACE, "GetSettings", ())
+        .map_err(nm_error)?;
+
+    if settings.contains_key("802-11-wireless-security") {
+        let (secrets,): (HashMap<String, PropMap>,) = proxy
+            .method_call(
+                NM_CONNECTION_IFACE,
+                "GetSecrets",
+                ("802-11-wireless-security",),
+            )
+            .map_err(nm_error)?;
+        for (group, props) in secrets {
+            settings.entry(group).or_default().extend(props);
+        }
+    }
+    Ok(settings)
+}
+
+/// NetworkManager backend using the D-Bus API
+pub struct NetworkManagerBackend {
+    interface: String,
+    /// Private bus address; the system bus is used if `None`
+    bus_address: Option<String>,
+}
+
+impl NetworkManagerBackend {
+    /// Create a new NetworkManager backend on the system bus
+    pub fn new(interface: String) -> Self {
+        Self {
+            interface,
+            bus_address: None,
+        }
+    }
+
+    /// Create a new NetworkManager backend talking to the bus at `address`
+    pub fn with_bus_address(interface: String, address: String) -> Self {
+        Self {
+            interface,
+            bus_address: Some(address),
+        }
+    }
+
+    /// Run `f` with a bus connection and the device path of the interface
+    async fn with_device<T, F>(&self, f: F) -> WifiResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection, &Path<'static>) -> WifiResult<T> + Send + 'static,
+    {
+        let interface = self.interface.clone();
+        let bus_address = self.bus_address.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = match bus_address {
+                Some(address) => Channel::open_private(&address)
+                    .and_then(|mut channel| channel.register().map(|()| channel))
+                    .map(Connection::from),
+                None => Connection::new_system(),
+            }
+            .map_err(|e| WifiError::BackendUnavailable(format!("D-Bus: {}", e)))?;
+
+            let nm = conn.with_proxy(NM_BUS_NAME, NM_PATH, DBUS_TIMEOUT);
+            let (device,): (Path<'static>,) = nm
+                .method_call(NM_IFACE, "GetDeviceByIpIface", (interface.as_str(),))
+                .map_err(|e| WifiError::InterfaceError(format!("{}: {}", interface, e)))?;
+
+            f(&conn, &device)
+        })
+        .await
+        .map_err(|e| WifiError::NetworkManagerError(format!("Task join error: {}", e)))?
+    }
+
+    fn access_points(conn: &Connection, device: &Path<'static>) -> WifiResult<Vec<AccessPoint>> {
+        let wireless = conn.with_proxy(NM_BUS_NAME, device, DBUS_TIMEOUT);
+        let (paths,): (Vec<Path<'static>>,) = wireless
+            .method_call(NM_WIRELESS_IFACE, "GetAllAccessPoints", ())
+            .map_err(nm_error)?;
+
+        Ok(paths
+            .iter()
+            .filter_map(|path| match Self::access_point(conn, path) {
+                Ok(ap) => Some(ap),
+                Err(e) => {
+                    // APs may vanish between listing and reading them
+                    debug!("Skipping access point {}: {}", path, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    fn access_point(conn: &Connection, path: &Path<'static>) -> WifiResult<AccessPoint> {
+        let props = conn
+            .with_proxy(NM_BUS_NAME, path, DBUS_TIMEOUT)
+            .get_all(NM_AP_IFACE)
+            .map_err(nm_error)?;
+        let u32_prop = |name: &str| prop_cast::<u32>(&props, name).copied().unwrap_or(0);
+
+        Ok(AccessPoint {
+            ssid: prop_cast::<Vec<u8>>(&props, "Ssid")
+                .cloned()
+                .unwrap_or_default()
+                .into(),
+            bssid: prop_cast::<String>(&props, "HwAddress")
+                .map(|s| s.to_ascii_lowercase())
+                .unwrap_or_default(),
+            frequency: u32_prop("Frequency"),
+            strength: prop_cast::<u8>(&props, "Strength").copied().unwrap_or(0),
+            max_bitrate: u32_prop("MaxBitrate"),
+            flags: security_flags(
+                u32_prop("Flags"),
+                u32_prop("WpaFlags"),
+                u32_prop("RsnFlags"),
+            ),
+            last_seen: prop_cast::<i32>(&props, "LastSeen").copied().unwrap_or(-1),
+        })
+    }
+
+    /// Seconds since boot (CLOCK_BOOTTIME), the time base of `LastSeen`
+    fn boot_time_secs() -> Option<i64> {
+        let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+        let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+        Some(secs as i64)
+    }
+
+    fn ip4_address(conn: &Connection, device: &Path<'static>) -> Option<String> {
+        let config: Path<'static> = conn
+            .with_proxy(NM_BUS_NAME, device, DBUS_TIMEOUT)
+            .get(NM_DEVICE_IFACE, "Ip4Config")
+            .ok()?;
+        if &*config == "/" {
+            return None;
+        }
+
+        let addresses: Vec<PropMap> = conn
+            .with_proxy(NM_BUS_NAME, config, DBUS_TIMEOUT)
+            .get(NM_IP4_CONFIG_IFACE, "AddressData")
+            .ok()?;
+        addresses
+            .iter()
+            .find_map(|address| prop_cast::<String>(address, "address").cloned())
+    }
+}
+
+impl WifiBackend for NetworkManagerBackend {
+    async fn scan(&self, options: &ScanOptions) -> WifiResult<Vec<WifiNetwork>> {
+        debug!("Starting NetworkManager scan on {}", self.interface);
+
+        if options.passive || options.freq_list.is_some() {
+            warn!("NetworkManager backend ignores passive and frequency scan options");
+        }
+        let request = scan_request_options(options);
+
+        let access_points = self
+            .with_device(|conn, device| {
+                let wireless = conn.with_proxy(NM_BUS_NAME, device, DBUS_TIMEOUT);
+                let last_scan = |w: &dbus::blocking::Proxy<&Connection>| {
+                    w.get::<i64>(NM_WIRELESS_IFACE, "LastScan")
+                };
+                let previous = last_scan(&wireless).map_err(nm_error)?;
+
+                match wireless.method_call::<(), _, _, _>(
+                    NM_WIRELESS_IFACE,
+                    "RequestScan",
+                    (request,),
+                ) {
+                    Ok(()) => {
+                        let deadline = Instant::now() + SCAN_TIMEOUT;
+                        while last_scan(&wireless).map_err(nm_error)? == previous {
+                            if Instant::now() >= deadline {
+                                return Err(WifiError::ScanFailed("Scan timed out".into()));
+                            }
+                            std::thread::sleep(SCAN_POLL_INTERVAL);
+                        }
+                    }
+                    // NetworkManager rate-limits scan requests; fall back to its cached results
+                    Err(e) => warn!("Scan request rejected, using cached results: {}", e),
+                }
+
+                Self::access_points(conn, device)
+            })
+            .await?;
+
+        let networks: Vec<WifiNetwork> =
+            access_points.iter().map(AccessPoint::to_network).collect();
+        debug!("Scan completed, found {} networks", networks.len());
+        Ok(networks)
+    }
+
+    async fn abort_scan(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("Aborting scans".into()))
+    }
+
+    async fn network_details(&self, bssid: &str) -> WifiResult<BssDetails> {
+        let bssid = bssid.to_string();
+        let access_points = self.with_device(Self::access_points).await?;
+
+        let ap = access_points
+            .into_iter()
+            .find(|ap| ap.bssid == bssid)
+            .ok_or(WifiError::BssNotFound(bssid))?;
+        let age_secs = match (Self::boot_time_secs(), ap.last_seen) {
+            (Some(now), last_seen) if last_seen >= 0 => (now - i64::from(last_seen)).max(0) as u32,
+            _ => 0,
+        };
+
+        // NetworkManager does not export IEs, so capability details stay empty
+        Ok(BssDetails {
+            bssid: ap.bssid.clone(),
+            ssid: ap.ssid.clone(),
+            frequency: ap.frequency,
+            channel: WpactrlBackend::frequency_to_channel(&ap.frequency.to_string()),
+            rssi: strength_to_rssi(ap.strength),
+            noise: None,
+            beacon_interval: 0,
+            capabilities: 0,
+            supported_rates: if ap.max_bitrate > 0 {
+                vec![ap.max_bitrate]
+            } else {
+                vec![]
+            },
+            ht: false,
+            vht: false,
+            he: false,
+            wps: None,
+            flags: ap.flags,
+            age_secs,
+            ies: String::new(),
+        })
+    }
+
+    async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> WifiResult<()> {
+        if ssid.is_empty() || ssid.len() > Ssid::MAX_LEN {
+            return Err(WifiError::InvalidSsid(format!(
+                "SSID must be 1 to {} octets, got {}",
+                Ssid::MAX_LEN,
+                ssid.len()
+            )));
+        }
+
+        debug!("Connecting to network: {}", ssid);
+        let settings = connection_settings(ssid, psk, options);
+        let ssid = ssid.clone();
+
+        self.with_device(move |conn, device| {
+            // Subscribe before activating so that no transition is missed
+            let transitions = Arc::new(Mutex::new(Vec::new()));
+            let sink = transitions.clone();
+            let rule =
+                MatchRule::new_signal(NM_DEVICE_IFACE, "StateChanged").with_path(device.clone());
+            conn.add_match(
+                rule,
+                move |(new, _old, reason): (u32, u32, u32), _: &Connection, _: &dbus::Message| {
+                    sink.lock().unwrap().push((new, reason));
+                    true
+                },
+            )
+            .map_err(nm_error)?;
+
+            let nm = conn.with_proxy(NM_BUS_NAME, NM_PATH, DBUS_TIMEOUT);
+            let saved = wifi_profiles(conn)?
+                .into_iter()
+                .find(|(_, saved)| *saved == ssid)
+                .map(|(path, _)| path);
+            let (profile, previous, activated) = match saved {
+                // Update the saved profile instead of adding another one
+                Some(profile) => {
+                    let previous = profile_settings(conn, &profile)?;
+                    conn.with_proxy(NM_BUS_NAME, &profile, DBUS_TIMEOUT)
+                        .method_call::<(), _, _, _>(NM_CONNECTION_IFACE, "Update", (settings,))
+                        .map_err(|e| WifiError::ConnectionFailed(e.to_string()))?;
+                    let activated = nm
+                        .method_call::<(Path<'static>,), _, _, _>(
+                            NM_IFACE,
+                            "ActivateConnection",
+                            (profile.clone(), device.clone(), Path::from("/")),
+                        )
+                        .map(|_| ())
+                        .map_err(|e| WifiError::ConnectionFailed(e.to_string()));
+                    (profile, Some(previous), activated)
+                }
+                None => {
+                    let (profile, _): (Path<'static>, Path<'static>) = nm
+                        .method_call(
+                            NM_IFACE,
+                            "AddAndActivateConnection",
+                            (settings, device.clone(), Path::from("/")),
+                        )
+                        .map_err(|e| WifiError::ConnectionFailed(e.to_string()))?;
+                    (profile, None, Ok(()))
+                }
+            };
+
+            let outcome = activated.and_then(|()| {
+                let deadline = Instant::now() + ACTIVATION_TIMEOUT;
+                while Instant::now() < deadline {
+                    conn.process(Duration::from_millis(100)).map_err(nm_error)?;
+
+                    for (state, reason) in transitions.lock().unwrap().drain(..) {
+                        match state {
+                            NM_DEVICE_STATE_ACTIVATED => return Ok(()),
+                            NM_DEVICE_STATE_FAILED => {
+                                return Err(WifiError::ConnectionFailed(format!(
+                                    "Activation failed (reason {})",
+                                    reason
+                                )));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                Err(WifiError::ConnectionFailed("Activation timed out".into()))
+            });
+
+            if outcome.is_err() {
+                let proxy = conn.with_proxy(NM_BUS_NAME, &profile, DBUS_TIMEOUT);
+                let restored: Result<(), dbus::Error> = match previous {
+                    // Put back the credentials that worked before
+                    Some(previous) => proxy.method_call(NM_CONNECTION_IFACE, "Update", (previous,)),
+                    // Don't keep a profile with credentials that do not work
+                    None => proxy.method_call(NM_CONNECTION_IFACE, "Delete", ()),
+                };
+                if let Err(e) = restored {
+                    warn!("Failed to restore connection profile {}: {}", profile, e);
+                }
+            }
+            outcome
+        })
+        .await?;
+
+        debug!("Connection activated");
+        Ok(())
+    }
+
+    async fn start_wps(
+        &self,
+        _method: &WpsMethod,
+        _bssid: Option<&str>,
+    ) -> WifiResult<Option<String>> {
+        Err(WifiError::NotSupported("WPS".into()))
+    }
+
+    async fn wps_result(&self) -> WifiResult<bool> {
+        Ok(false)
+    }
+
+    async fn cancel_wps(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("WPS".into()))
+    }
+
+    async fn start_dpp(&self) -> WifiResult<String> {
+        Err(WifiError::NotSupported("DPP".into()))
+    }
+
+    async fn dpp_config(&self) -> WifiResult<Option<DppConfig>> {
+        Ok(None)
+    }
+
+    async fn stop_dpp(&self) -> WifiResult<()> {
+        Err(WifiError::NotSupported("DPP".into()))
+    }
+
+    async fn get_country(&self) -> WifiResult<Option<String>> {
+        // The regulatory domain is not part of the NetworkManager API
+        Ok(None)
+    }
+
+    async fn set_country(&self, _country: &str, _persist: bool) -> WifiResult<()> {
+        Err(WifiError::NotSupported("Setting the country code".into()))
+    }
+
+    async fn capabilities(&self) -> WifiResult<Capabilities> {
+        let caps: u32 = self
+            .with_device(|conn, device| {
+                conn.with_proxy(NM_BUS_NAME, device, DBUS_TIMEOUT)
+                    .get(NM_WIRELESS_IFACE, "WirelessCapabilities")
+                    .map_err(nm_error)
+            })
+            .await?;
+
+        Ok(Capabilities {
+            // connect only creates WPA-PSK profiles
+            sae: false,
+            enterprise: false,
+            wps: false,
+            dpp: false,
+            band_6ghz: caps & NM_WIFI_DEVICE_CAP_FREQ_6GHZ != 0,
+            // Connections are saved as NetworkManager profiles
+            persistence: true,
+            set_country: false,
+        })
+    }
+
+    async fn disconnect(&self) -> WifiResult<()> {
+        debug!("Disconnecting from network");
+
+        self.with_device(|conn, device| {
+            let result: Result<(), dbus::Error> = conn
+                .with_proxy(NM_BUS_NAME, device, DBUS_TIMEOUT)
+                .method_call(NM_DEVICE_IFACE, "Disconnect", ());
+            match result {
+                Err(e) if e.name() != Some("org.freedesktop.NetworkManager.Device.NotActive") => {
+                    Err(nm_error(e))
+                }
+                _ => Ok(()),
+            }
+        })
+        .await?;
+
+        debug!("Disconnected successfully");
+        Ok(())
+    }
+
+    async fn status(&self) -> WifiResult<ConnectionStatus> {
+        self.with_device(|conn, device| {
+            let proxy = conn.with_proxy(NM_BUS_NAME, device, DBUS_TIMEOUT);
+            let state: u32 = proxy.get(NM_DEVICE_IFACE, "State").map_err(nm_error)?;
+            let state = device_state_to_connection_state(state);
+
+            let ssid = match state {
+                ConnectionState::Connected | ConnectionState::Connecting => proxy
+                    .get::<Path<'static>>(NM_WIRELESS_IFACE, "ActiveAccessPoint")
+                    .ok()
+                    .filter(|ap| &**ap != "/")
+                    .and_then(|ap| Self::access_point(conn, &ap).ok())
+                    .map(|ap| ap.ssid),
+                _ => None,
+            };
+
+            let ip_address = if state == ConnectionState::Connected {
+                Self::ip4_address(conn, device)
+            } else {
+                None
+            };
+
+            Ok(ConnectionStatus {
+                state,
+                ssid,
+                ip_address,
+                country: None,
+            })
+        })
+        .await
+    }
+
+    async fn saved_networks(&self) -> WifiResult<Vec<Ssid>> {
+        self.with_device(|conn, _| {
+            let mut ssids: Vec<Ssid> = Vec::new();
+            for (_, ssid) in wifi_profiles(conn)? {
+                if !ssids.contains(&ssid) {
+                    ssids.push(ssid);
+                }
+            }
+            Ok(ssids)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_bus::TestBus;
+    use dbus_crossroads::{Crossroads, IfaceBuilder, MethodErr};
+
+    const DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/1";
+    const IP4_CONFIG_PATH: &str = "/org/freedesktop/NetworkManager/IP4Config/1";
+
+    /// Access point served by the stand-in
+    struct FakeAp {
+        ssid: &'static str,
+        bssid: &'static str,
+        frequency: u32,
+        strength: u8,
+        passphrase: &'static str,
+    }
+
+    /// NetworkManager state served by the stand-in
+    #[derive(Default)]
+    struct FakeNm {
+        aps: Vec<FakeAp>,
+        state: u32,
+        active_ap: Option<usize>,
+        last_scan: i64,
+        scan_ssids: Vec<Vec<u8>>,
+        last_settings: Option<HashMap<String, HashMap<String, String>>>,
+        connections: Vec<FakeProfile>,
+    }
+
+    /// Saved connection profile served by the stand-in
+    #[derive(Debug, Clone, PartialEq)]
+    enum FakeProfile {
+        Wired,
+        /// SSID and hex-encoded PSK
+        Wifi(Vec<u8>, String),
+        Deleted,
+    }
+
+    /// Settings with values flattened to strings, octet arrays hex-encoded
+    fn flatten(settings: &HashMap<String, PropMap>) -> HashMap<String, HashMap<String, String>> {
+        settings
+            .iter()
+            .map(|(group, props)| {
+                let props = props
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match prop_cast::<Vec<u8>>(props, key) {
+                            Some(bytes) => hex::encode(bytes),
+                            None => value
+                                .as_str()
+                                .map(str::to_string)
+                                .or_else(|| value.as_u64().map(|v| v.to_string()))
+                                .unwrap_or_default(),
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect();
+                (group.clone(), props)
+            })
+            .collect()
+    }
+
+    /// Store `settings` as profile `index` (appended if `None`)
+    fn save_profile(
+        nm: &mut FakeNm,
+        index: Option<usize>,
+        settings: &HashMap<String, PropMap>,
+    ) -> usize {
+        let flat = flatten(settings);
+        let profile = FakeProfile::Wifi(
+            hex::decode(&flat["802-11-wireless"]["ssid"]).unwrap(),
+            flat["802-11-wireless-security"]["psk"].clone(),
+        );
+        nm.last_settings = Some(flat);
+        match index {
+            Some(index) => {
+                nm.connections[index] = profile;
+                index
+            }
+            None => {
+                nm.connections.push(profile);
+                nm.connections.len() - 1
+            }
+        }
+    }
+
+    /// Activate profile `index`, emitting the device state transitions
+    fn activate(ctx: &mut dbus_crossroads::Context, nm: &mut FakeNm, index: usize) {
+        let FakeProfile::Wifi(ssid, psk) = nm.connections[index].clone() else {
+            return;
+        };
+
+        let old = nm.state;
+        ctx.push_msg(state_changed(NM_DEVICE_STATE_PREPARE, old, 0));
+
+        let found = nm.aps.iter().position(|ap| {
+            ap.ssid.as_bytes() == ssid.as_slice()
+                && hex::encode(crate::core::types::passphrase_to_psk(
+                    ap.passphrase,
+                    &ap.ssid.into(),
+                )) == psk
+        });
+        match found {
+            Some(index) => {
+                nm.state = NM_DEVICE_STATE_ACTIVATED;
+                nm.active_ap = Some(index);
+                ctx.push_msg(state_changed(NM_DEVICE_STATE_ACTIVATED, 40, 0));
+            }
+            None => {
+                // NM_DEVICE_STATE_REASON_NO_SECRETS
+                ctx.push_msg(state_changed(NM_DEVICE_STATE_FAILED, 40, 7));
+                ctx.push_msg(state_changed(30, NM_DEVICE_STATE_FAILED, 7));
+                nm.state = 30;
+            }
+        }
+    }
+
+    /// Connection paths served, saved profiles beyond are not reachable
+    const MAX_CONNECTIONS: usize = 4;
+
+    fn connection_path(index: usize) -> Path<'static> {
+        Path::from(format!("{}/{}", NM_SETTINGS_PATH, index))
+    }
+
+    type Shared = Arc<Mutex<FakeNm>>;
+
+    fn ap_index(path: &Path) -> Option<usize> {
+        path.rsplit('/').next()?.parse().ok()
+    }
+
+    fn ap_path(index: usize) -> Path<'static> {
+        Path::from(format!(
+            "/org/freedesktop/NetworkManager/AccessPoint/{}",
+            index
+        ))
+    }
+
+    fn state_changed(new: u32, old: u32, reason: u32) -> dbus::Message {
+        dbus::Message::signal(
+            &Path::from(DEVICE_PATH),
+            &NM_DEVICE_IFACE.into(),
+            &"StateChanged".into(),
+        )
+        .append3(new, old, reason)
+    }
+
+    /// Serve the NetworkManager interfaces used by the backend on `bus`
+    fn serve_fake_nm(bus: &TestBus, nm: Shared) {
+        let address = bus.address.clone();
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let conn = TestBus::connect(&address);
+            conn.request_name(NM_BUS_NAME, false, true, false).unwrap();
+
+            let mut cr = Crossroads::new();
+            let manager = cr.register(NM_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.method(
+                    "GetDeviceByIpIface",
+                    ("iface",),
+                    ("device",),
+                    |_, _, (iface,): (String,)| match iface.as_str() {
+                        "wlan0" => Ok((Path::from(DEVICE_PATH),)),
+                        _ => Err(MethodErr::failed("No such device")),
+                    },
+                );
+                b.method(
+                    "AddAndActivateConnection",
+                    ("connection", "device", "specific_object"),
+                    ("path", "active_connection"),
+                    |ctx, nm, (settings, _, _): (HashMap<String, PropMap>, Path, Path)| {
+                        let mut nm = nm.lock().unwrap();
+                        let index = save_profile(&mut nm, None, &settings);
+                        activate(ctx, &mut nm, index);
+                        Ok((
+                            connection_path(index),
+                            Path::from("/org/freedesktop/NetworkManager/ActiveConnection/1"),
+                        ))
+                    },
+                );
+                b.method(
+                    "ActivateConnection",
+                    ("connection", "device", "specific_object"),
+                    ("active_connection",),
+                    |ctx, nm, (connection, _, _): (Path, Path, Path)| {
+                        let mut nm = nm.lock().unwrap();
+                        let index = ap_index(&connection)
+                            .filter(|index| {
+                                nm.connections
+                                    .get(*index)
+                                    .is_some_and(|profile| *profile != FakeProfile::Deleted)
+                            })
+                            .ok_or_else(|| MethodErr::failed("Unknown connection"))?;
+                        activate(ctx, &mut nm, index);
+                        Ok((Path::from(
+                            "/org/freedesktop/NetworkManager/ActiveConnection/1",
+                        ),))
+                    },
+                );
+            });
+
+            let device = cr.register(NM_DEVICE_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.property("State")
+                    .get(|_, nm| Ok(nm.lock().unwrap().state));
+                b.property("Ip4Config").get(|_, nm| {
+                    Ok(match nm.lock().unwrap().state {
+                        NM_DEVICE_STATE_ACTIVATED => Path::from(IP4_CONFIG_PATH),
+                        _ => Path::from("/"),
+                    })
+                });
+                b.method("Disconnect", (), (), |_, nm, ()| {
+                    let mut nm = nm.lock().unwrap();
+                    if nm.state != NM_DEVICE_STATE_ACTIVATED {
+                        return Err(MethodErr::from((
+                            "org.freedesktop.NetworkManager.Device.NotActive",
+                            "This device is not active",
+                        )));
+                    }
+                    nm.state = 30;
+                    nm.active_ap = None;
+                    Ok(())
+                });
+            });
+
+            let wireless = cr.register(NM_WIRELESS_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.property("LastScan")
+                    .get(|_, nm| Ok(nm.lock().unwrap().last_scan));
+                // Ciphers, WPA, RSN, 2.4 and 5 GHz
+                b.property("WirelessCapabilities").get(|_, _| Ok(0x73fu32));
+                b.property("ActiveAccessPoint").get(|_, nm| {
+                    Ok(match nm.lock().unwrap().active_ap {
+                        Some(index) => ap_path(index),
+                        None => Path::from("/"),
+                    })
+                });
+                b.method(
+                    "RequestScan",
+                    ("options",),
+                    (),
+                    |_, nm, (options,): (PropMap,)| {
+                        let mut nm = nm.lock().unwrap();
+                        nm.last_scan += 1000;
+                        // Received arrays are untyped, collect the octets by hand
+                        nm.scan_ssids = options
+                            .get("ssids")
+                            .and_then(|ssids| ssids.0.as_iter())
+                            .map(|ssids| {
+                                ssids
+                                    .filter_map(|ssid| ssid.as_iter())
+                                    .map(|octets| {
+                                        octets.filter_map(|o| o.as_u64()).map(|o| o as u8).collect()
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        Ok(())
+                    },
+                );
+                b.method("GetAllAccessPoints", (), ("access_points",), |_, nm, ()| {
+                    let count = nm.lock().unwrap().aps.len();
+                    Ok(((0..count).map(ap_path).collect::<Vec<_>>(),))
+                });
+            });
+
+            let access_point = cr.register(NM_AP_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                fn with_ap<T>(
+                    ctx: &dbus_crossroads::PropContext,
+                    nm: &Shared,
+                    f: impl FnOnce(&FakeAp) -> T,
+                ) -> Result<T, MethodErr> {
+                    let nm = nm.lock().unwrap();
+                    ap_index(ctx.path())
+                        .and_then(|index| nm.aps.get(index))
+                        .map(f)
+                        .ok_or_else(|| MethodErr::no_path(ctx.path()))
+                }
+
+                b.property("Ssid")
+                    .get(|ctx, nm| with_ap(ctx, nm, |ap| ap.ssid.as_bytes().to_vec()));
+                b.property("HwAddress")
+                    .get(|ctx, nm| with_ap(ctx, nm, |ap| ap.bssid.to_ascii_uppercase()));
+                b.property("Frequency")
+                    .get(|ctx, nm| with_ap(ctx, nm, |ap| ap.frequency));
+                b.property("Strength")
+                    .get(|ctx, nm| with_ap(ctx, nm, |ap| ap.strength));
+                b.property("MaxBitrate").get(|_, _| Ok(130_000u32));
+                b.property("Flags").get(|_, _| Ok(NM_AP_FLAGS_PRIVACY));
+                b.property("WpaFlags").get(|_, _| Ok(0u32));
+                b.property("RsnFlags")
+                    .get(|_, _| Ok(NM_AP_SEC_KEY_MGMT_PSK | NM_AP_SEC_KEY_MGMT_SAE));
+                b.property("LastSeen").get(|_, _| Ok(-1i32));
+            });
+
+            let settings = cr.register(NM_SETTINGS_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.method("ListConnections", (), ("connections",), |_, nm, ()| {
+                    let nm = nm.lock().unwrap();
+                    let paths: Vec<_> = (0..nm.connections.len())
+                        .filter(|index| nm.connections[*index] != FakeProfile::Deleted)
+                        .map(connection_path)
+                        .collect();
+                    Ok((paths,))
+                });
+            });
+
+            let connection = cr.register(NM_CONNECTION_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.method("GetSettings", (), ("settings",), |ctx, nm, ()| {
+                    let nm = nm.lock().unwrap();
+                    let profile = ap_index(ctx.path())
+                        .and_then(|index| nm.connections.get(index))
+                        .ok_or_else(|| MethodErr::no_path(ctx.path()))?;
+
+                    let mut settings: HashMap<String, PropMap> = HashMap::new();
+                    let mut connection = PropMap::new();
+                    match profile {
+                        FakeProfile::Wifi(ssid, _) => {
+                            connection
+                                .insert("type".into(), variant("802-11-wireless".to_string()));
+                            let mut wireless = PropMap::new();
+                            wireless.insert("ssid".into(), variant(ssid.clone()));
+                            settings.insert("802-11-wireless".into(), wireless);
+                            // The PSK is only returned by GetSecrets
+                            let mut security = PropMap::new();
+                            security.insert("key-mgmt".into(), variant("wpa-psk".to_string()));
+                            settings.insert("802-11-wireless-security".into(), security);
+                        }
+                        FakeProfile::Wired => {
+                            connection.insert("type".into(), variant("802-3-ethernet".to_string()));
+                        }
+                        FakeProfile::Deleted => return Err(MethodErr::no_path(ctx.path())),
+                    }
+                    settings.insert("connection".into(), connection);
+                    Ok((settings,))
+                });
+                b.method(
+                    "GetSecrets",
+                    ("setting_name",),
+                    ("secrets",),
+                    |ctx, nm, (setting,): (String,)| {
+                        let nm = nm.lock().unwrap();
+                        let Some(FakeProfile::Wifi(_, psk)) =
+                            ap_index(ctx.path()).and_then(|index| nm.connections.get(index))
+                        else {
+                            return Err(MethodErr::no_path(ctx.path()));
+                        };
+
+                        let mut secrets: HashMap<String, PropMap> = HashMap::new();
+                        if setting == "802-11-wireless-security" {
+                            let mut security = PropMap::new();
+                            security.insert("psk".into(), variant(psk.clone()));
+                            secrets.insert(setting, security);
+                        }
+                        Ok((secrets,))
+                    },
+                );
+                b.method(
+                    "Update",
+                    ("properties",),
+                    (),
+                    |ctx, nm, (settings,): (HashMap<String, PropMap>,)| {
+                        let mut nm = nm.lock().unwrap();
+                        let index = ap_index(ctx.path())
+                            .filter(|index| {
+                                nm.connections
+                                    .get(*index)
+                                    .is_some_and(|profile| *profile != FakeProfile::Deleted)
+                            })
+                            .ok_or_else(|| MethodErr::no_path(ctx.path()))?;
+                        save_profile(&mut nm, Some(index), &settings);
+                        Ok(())
+                    },
+                );
+                b.method("Delete", (), (), |ctx, nm, ()| {
+                    let mut nm = nm.lock().unwrap();
+                    let profile = ap_index(ctx.path())
+                        .and_then(|index| nm.connections.get_mut(index))
+                        .ok_or_else(|| MethodErr::no_path(ctx.path()))?;
+                    *profile = FakeProfile::Deleted;
+                    Ok(())
+                });
+            });
+
+            let ip4_config = cr.register(NM_IP4_CONFIG_IFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.property("AddressData").get(|_, _| {
+                    let mut address = PropMap::new();
+                    address.insert("address".into(), variant("192.168.1.100".to_string()));
+                    address.insert("prefix".into(), variant(24u32));
+                    Ok(vec![address])
+                });
+            });
+
+            let ap_count = nm.lock().unwrap().aps.len();
+            cr.insert(NM_PATH, &[manager], nm.clone());
+            cr.insert(DEVICE_PATH, &[device, wireless], nm.clone());
+            cr.insert(IP4_CONFIG_PATH, &[ip4_config], nm.clone());
+            cr.insert(NM_SETTINGS_PATH, &[settings], nm.clone());
+            for index in 0..MAX_CONNECTIONS {
+                cr.insert(connection_path(index), &[connection], nm.clone());
+            }
+            for index in 0..ap_count {
+                cr.insert(ap_path(index), &[access_point], nm.clone());
+            }
+
+            ready_tx.send(()).unwrap();
+            // Serving ends with an error once the test bus is torn down
+            let _ = cr.serve(&conn);
+        });
+
+        ready_rx.recv().unwrap();
+    }
+
+    /// PSK of the saved `HomeNet` profile
+    fn home_psk() -> String {
+        hex::encode(crate::core::types::passphrase_to_psk(
+            "home-passphrase",
+            &"HomeNet".into(),
+        ))
+    }
+
+    fn setup() -> (TestBus, Shared, NetworkManagerBackend) {
+        let bus = TestBus::start().expect("dbus-daemon is required for this test");
+        let nm = Shared::new(Mutex::new(FakeNm {
+            aps: vec![
+                FakeAp {
+                    ssid: "HomeNet",
+                    bssid: "aa:bb:cc:dd:ee:01",
+                    frequency: 2437,
+                    strength: 75,
+                    passphrase: "home-passphrase",
+                },
+                FakeAp {
+                    ssid: "Office",
+                    bssid: "aa:bb:cc:dd:ee:02",
+                    frequency: 5180,
+                    strength: 40,
+                    passphrase: "office-passphrase",
+                },
+            ],
+            state: 30,
+            connections: vec![
+                FakeProfile::Wired,
+                FakeProfile::Wifi(b"HomeNet".to_vec(), home_psk()),
+            ],
+            ..Default::default()
+        }));
+        serve_fake_nm(&bus, nm.clone());
+
+        let backend =
+            NetworkManagerBackend::with_bus_address("wlan0".to_string(), bus.address.clone());
+        (bus, nm, backend)
+    }
+
+    #[test]
+    fn test_strength_to_rssi() {
+        assert_eq!(strength_to_rssi(0), -100);
+        assert_eq!(strength_to_rssi(100), -40);
+        assert_eq!(strength_to_rssi(50), -70);
+        assert_eq!(strength_to_rssi(255), -40);
+    }
+
+    #[test]
+    fn test_security_flags() {
+        assert_eq!(
+            security_flags(NM_AP_FLAGS_PRIVACY, 0, NM_AP_SEC_KEY_MGMT_PSK),
+            vec!["WPA2-PSK", "ESS"]
+        );
+        assert_eq!(
+            security_flags(NM_AP_FLAGS_PRIVACY, 0, 0),
+            vec!["WEP", "ESS"]
+        );
+        assert_eq!(security_flags(0, 0, 0), vec!["ESS"]);
+    }
+
+    #[test]
+    fn test_connection_settings_band() {
+        let options = ConnectOptions {
+            bssid: Some("aa:bb:cc:dd:ee:ff".to_string()),
+            freq_list: Some(vec![5180]),
+            band: None,
+        };
+        let settings = connection_settings(&"Net".into(), &[0; 32], &options);
+        let wireless = &settings["802-11-wireless"];
+        assert_eq!(
+            prop_cast::<Vec<u8>>(wireless, "bssid"),
+            Some(&vec![0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff])
+        );
+        assert_eq!(prop_cast::<String>(wireless, "band").unwrap(), "a");
+        assert_eq!(prop_cast::<u32>(wireless, "channel"), Some(&36));
+
+        // Any channel of the band can be pinned
+        let options = ConnectOptions {
+            freq_list: Some(vec![5600]),
+            ..Default::default()
+        };
+        let settings = connection_settings(&"Net".into(), &[0; 32], &options);
+        assert_eq!(
+            prop_cast::<u32>(&settings["802-11-wireless"], "channel"),
+            Some(&120)
+        );
+
+        // Mixed bands cannot be expressed
+        let options = ConnectOptions {
+            freq_list: Some(vec![2412, 5180]),
+            ..Default::default()
+        };
+        let settings = connection_settings(&"Net".into(), &[0; 32], &options);
+        assert!(prop_cast::<String>(&settings["802-11-wireless"], "band").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_scan_and_details() {
+        let (_bus, nm, backend) = setup();
+
+        let networks = backend.scan(&ScanOptions::default()).await.unwrap();
+        assert_eq!(nm.lock().unwrap().last_scan, 1000);
+        assert!(nm.lock().unwrap().scan_ssids.is_empty());
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[0].ssid, "HomeNet");
+        assert_eq!(networks[0].mac, "aa:bb:cc:dd:ee:01");
+        assert_eq!(networks[0].channel, 6);
+        assert_eq!(networks[0].rssi, -55);
+        assert_eq!(networks[1].channel, 36);
+
+        let details = backend.network_details("aa:bb:cc:dd:ee:02").await.unwrap();
+        assert_eq!(details.ssid, "Office");
+        assert_eq!(details.frequency, 5180);
+        assert_eq!(details.supported_rates, vec![130_000]);
+        assert_eq!(details.flags, vec!["WPA2-PSK", "SAE", "ESS"]);
+
+        assert!(matches!(
+            backend.network_details("aa:bb:cc:dd:ee:ff").await,
+            Err(WifiError::BssNotFound(_))
+        ));
+
+        let options = ScanOptions {
+            ssids: vec!["Hidden".into()],
+            ..Default::default()
+        };
+        backend.scan(&options).await.unwrap();
+        assert_eq!(nm.lock().unwrap().scan_ssids, [b"Hidden".to_vec()]);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_connect_and_status() {
+        let (_bus, nm, backend) = setup();
+
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Idle);
+        // The wired profile is skipped
+        assert_eq!(
+            backend.saved_networks().await.unwrap(),
+            [Ssid::from("HomeNet")]
+        );
+
+        let psk = crate::core::types::passphrase_to_psk("office-passphrase", &"Office".into());
+        backend
+            .connect(&"Office".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+
+        let settings = nm.lock().unwrap().last_settings.clone().unwrap();
+        assert_eq!(settings["802-11-wireless"]["ssid"], hex::encode("Office"));
+        assert_eq!(settings["802-11-wireless-security"]["key-mgmt"], "wpa-psk");
+
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some("Office".into()));
+        assert_eq!(status.ip_address, Some("192.168.1.100".to_string()));
+
+        backend.disconnect().await.unwrap();
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+        // Disconnecting an inactive device is not an error
+        backend.disconnect().await.unwrap();
+        assert_eq!(
+            backend.saved_networks().await.unwrap(),
+            [Ssid::from("HomeNet"), Ssid::from("Office")]
+        );
+
+        // Connecting again reuses the saved profile
+        backend
+            .connect(&"Office".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(nm.lock().unwrap().connections.len(), 3);
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Connected
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_connect_wrong_psk() {
+        let (_bus, nm, backend) = setup();
+
+        // A new profile with credentials that failed is removed
+        let result = backend
+            .connect(&"Office".into(), &[0; 32], &ConnectOptions::default())
+            .await;
+        assert!(matches!(result, Err(WifiError::ConnectionFailed(_))));
+        assert_eq!(nm.lock().unwrap().connections[2], FakeProfile::Deleted);
+
+        // A saved profile gets its working credentials back
+        let result = backend
+            .connect(&"HomeNet".into(), &[0; 32], &ConnectOptions::default())
+            .await;
+        assert!(matches!(result, Err(WifiError::ConnectionFailed(_))));
+        assert_eq!(
+            nm.lock().unwrap().connections[1],
+            FakeProfile::Wifi(b"HomeNet".to_vec(), home_psk())
+        );
+        assert_eq!(
+            backend.saved_networks().await.unwrap(),
+            [Ssid::from("HomeNet")]
+        );
+
+        let psk = crate::core::types::passphrase_to_psk("home-passphrase", &"HomeNet".into());
+        backend
+            .connect(&"HomeNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(nm.lock().unwrap().connections.len(), 3);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_unknown_interface() {
+        let (bus, _nm, _backend) = setup();
+
+        let backend =
+            NetworkManagerBackend::with_bus_address("eth9".to_string(), bus.address.clone());
+        assert!(matches!(
+            backend.status().await,
+            Err(WifiError::InterfaceError(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon"]
+    async fn test_capabilities() {
+        let (_bus, _nm, backend) = setup();
+
+        let caps = backend.capabilities().await.unwrap();
+        assert!(caps.persistence);
+        assert!(!caps.sae && !caps.enterprise);
+        assert!(!caps.band_6ghz && !caps.wps && !caps.dpp && !caps.set_country);
+    }
+}
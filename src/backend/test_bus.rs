@@ -0,0 +1,51 @@
+//! Private D-Bus daemon for backend tests
+
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+};
+
+use dbus::{blocking::Connection, channel::Channel};
+
+/// A `dbus-daemon` instance that lives as long as this value
+pub struct TestBus {
+    pub address: String,
+    daemon: Child,
+}
+
+impl TestBus {
+    /// Start a private session bus, or `None` if `dbus-daemon` is unavailable
+    pub fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        let stdout = daemon.stdout.take()?;
+        BufReader::new(stdout).read_line(&mut address).ok()?;
+        let address = address.trim().to_string();
+        if address.is_empty() {
+            let _ = daemon.kill();
+            return None;
+        }
+
+        Some(Self { address, daemon })
+    }
+
+    /// Open a blocking connection to the bus at `address`
+    pub fn connect(address: &str) -> Connection {
+        let mut channel = Channel::open_private(address).expect("Failed to open test bus");
+        channel.register().expect("Failed to register on test bus");
+        Connection::from(channel)
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
@@ -3,7 +3,10 @@
 use trait_variant::make;
 
 use crate::core::error::WifiResult;
-use crate::core::types::{ConnectionStatus, WifiNetwork};
+use crate::core::types::{
+    BssDetails, Capabilities, ConnectOptions, ConnectionStatus, DppConfig, ScanOptions, Ssid,
+    WifiNetwork, WpsMethod,
+};
 
 /// Abstraction over WiFi control interface (typically wpa_supplicant)
 ///
@@ -15,16 +18,89 @@ pub trait WifiBackend: Sync + 'static {
     ///
     /// This triggers a scan and returns the discovered networks.
     /// The scan operation may take several seconds.
-    async fn scan(&self) -> WifiResult<Vec<WifiNetwork>>;
+    ///
+    /// # Arguments
+    /// * `options` - Normalized scan parameters (see `ScanOptions::normalized`);
+    ///   backends ignore the ones they cannot apply
+    async fn scan(&self, options: &ScanOptions) -> WifiResult<Vec<WifiNetwork>>;
+
+    /// Abort a running scan
+    ///
+    /// Returns `WifiError::NotSupported` if scans cannot be aborted.
+    async fn abort_scan(&self) -> WifiResult<()>;
+
+    /// Get detailed information about a BSS from the last scan
+    ///
+    /// # Arguments
+    /// * `bssid` - MAC address of the BSS (lowercase, colon-separated)
+    ///
+    /// Returns `WifiError::BssNotFound` if the BSS is not known to the backend.
+    async fn network_details(&self, bssid: &str) -> WifiResult<BssDetails>;
 
     /// Connect to a WiFi network using SSID and pre-shared key
     ///
     /// # Arguments
-    /// * `ssid` - Network SSID (up to 32 bytes UTF-8)
+    /// * `ssid` - Network SSID (up to 32 octets, not necessarily UTF-8)
     /// * `psk` - 32-byte PBKDF2-derived PSK (not the passphrase)
+    /// * `options` - Normalized BSSID/frequency restrictions (see `ConnectOptions::normalized`)
     ///
     /// The PSK should be calculated as: PBKDF2(HMAC-SHA1, passphrase, ssid, 4096, 256)
-    async fn connect(&self, ssid: &str, psk: &[u8; 32]) -> WifiResult<()>;
+    async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> WifiResult<()>;
+
+    /// Start WPS registration
+    ///
+    /// # Arguments
+    /// * `method` - Push-button or PIN method
+    /// * `bssid` - Restrict registration to this AP (any AP if `None`)
+    ///
+    /// Returns the PIN in use for the PIN method. Registration continues in the
+    /// background; its outcome is reported by `wps_result`.
+    async fn start_wps(
+        &self,
+        method: &WpsMethod,
+        bssid: Option<&str>,
+    ) -> WifiResult<Option<String>>;
+
+    /// Outcome of the running WPS registration
+    ///
+    /// `true` once credentials were received, `false` while registration is
+    /// still running (or none was started).
+    async fn wps_result(&self) -> WifiResult<bool>;
+
+    /// Cancel a running WPS registration
+    async fn cancel_wps(&self) -> WifiResult<()>;
+
+    /// Generate a DPP bootstrap key and start listening as enrollee
+    ///
+    /// Returns the bootstrap URI (`DPP:...`) to be rendered as QR code.
+    async fn start_dpp(&self) -> WifiResult<String>;
+
+    /// Take the configuration object received from a DPP configurator, if any
+    async fn dpp_config(&self) -> WifiResult<Option<DppConfig>>;
+
+    /// Stop listening for DPP authentication
+    async fn stop_dpp(&self) -> WifiResult<()>;
+
+    /// Get the active regulatory domain (`None` if unset)
+    async fn get_country(&self) -> WifiResult<Option<String>>;
+
+    /// Set the regulatory domain
+    ///
+    /// # Arguments
+    /// * `country` - Normalized country code (see `normalize_country_code`)
+    /// * `persist` - Also store it in the backend's persistent configuration
+    async fn set_country(&self, country: &str, persist: bool) -> WifiResult<()>;
+
+    /// Report the features available through this backend
+    ///
+    /// Features the backend cannot provide (e.g. WPS when `start_wps` returns
+    /// `WifiError::NotSupported`) are reported as unsupported.
+    async fn capabilities(&self) -> WifiResult<Capabilities>;
 
     /// Disconnect from the current network
     async fn disconnect(&self) -> WifiResult<()>;
@@ -33,4 +109,7 @@ pub trait WifiBackend: Sync + 'static {
     ///
     /// Returns the connection state, SSID, and IP address (if connected)
     async fn status(&self) -> WifiResult<ConnectionStatus>;
+
+    /// SSIDs of the networks the device has credentials for
+    async fn saved_networks(&self) -> WifiResult<Vec<Ssid>>;
 }
@@ -1,36 +1,272 @@
 //! wpa_supplicant backend implementation
 
-use std::path::Path;
+use std::{
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 use tokio::process::Command;
-use tracing::debug;
+use tracing::{debug, warn};
 use wpactrl::Client;
 
 use crate::{
     backend::WifiBackend,
     core::{
         error::{WifiError, WifiResult},
-        types::{ConnectionStatus, WifiNetwork},
+        types::{
+            Band, BssDetails, Capabilities, ConnectOptions, ConnectionStatus, DppConfig,
+            ScanOptions, Security, Ssid, WifiNetwork, WpsMethod, WpsState,
+        },
     },
 };
 
+/// Information element ids used for BSS details
+const IE_SUPPORTED_RATES: u8 = 1;
+const IE_HT_CAPABILITIES: u8 = 45;
+const IE_EXTENDED_RATES: u8 = 50;
+const IE_VHT_CAPABILITIES: u8 = 191;
+const IE_EXTENSION: u8 = 255;
+const IE_EXT_HE_CAPABILITIES: u8 = 35;
+
+/// DPP listen channel (2.4 GHz channel 6, operating class 81)
+const DPP_LISTEN_FREQ: u32 = 2437;
+const DPP_LISTEN_CHANNEL: &str = "81/6";
+
+/// Time a scan usually takes to complete
+pub const DEFAULT_SCAN_WAIT: Duration = Duration::from_secs(3);
+
+/// Added to the expected duration of scans with a dwell hint
+const SCAN_WAIT_MARGIN: Duration = Duration::from_millis(200);
+
+/// Interval at which event listeners poll the control socket
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Result slot filled by an event listener
+type EventResult<T> = Arc<Mutex<Option<Result<T, String>>>>;
+
+/// Running DPP enrollee session
+struct DppSession {
+    bootstrap_id: String,
+    /// `dpp_config_processing` value to restore when the session ends
+    config_processing: Option<String>,
+    stop: Arc<AtomicBool>,
+    result: EventResult<DppConfig>,
+}
+
+/// Running event listener whose outcome `STATUS` cannot tell
+///
+/// `STATUS` does not tell a failed WPS registration or a rejected passphrase
+/// from an idle interface, so the outcome is taken from the events.
+struct EventSession {
+    stop: Arc<AtomicBool>,
+    result: EventResult<()>,
+}
+
+/// Strip the "<level>" prefix of an event
+fn strip_level(event: &str) -> &str {
+    match event.split_once('>') {
+        Some((_, rest)) if event.starts_with('<') => rest,
+        _ => event,
+    }
+}
+
+/// Outcome of WPS registration reported by an event
+fn wps_event_outcome(event: &str) -> Option<Result<(), String>> {
+    let name = strip_level(event).split(' ').next().unwrap_or_default();
+
+    match name {
+        "WPS-SUCCESS" => Some(Ok(())),
+        "WPS-FAIL" => Some(Err("WPS registration failed".to_string())),
+        "WPS-TIMEOUT" => Some(Err("WPS walk time expired".to_string())),
+        "WPS-OVERLAP-DETECTED" => Some(Err(
+            "Several access points are in WPS push-button mode".to_string()
+        )),
+        _ => None,
+    }
+}
+
+/// Outcome of an association reported by an event
+///
+/// wpa_supplicant keeps retrying a network whose authentication failed, so
+/// without these events a wrong passphrase looks like a long association.
+fn association_event_outcome(event: &str) -> Option<Result<(), String>> {
+    let event = strip_level(event);
+    let (name, args) = event.split_once(' ').unwrap_or((event, ""));
+
+    match name {
+        "CTRL-EVENT-CONNECTED" => Some(Ok(())),
+        "CTRL-EVENT-SSID-TEMP-DISABLED" => {
+            let reason = args
+                .split_whitespace()
+                .find_map(|arg| arg.strip_prefix("reason="))
+                .unwrap_or("unknown");
+            Some(Err(match reason {
+                "WRONG_KEY" => "Wrong passphrase".to_string(),
+                reason => format!("Authentication failed ({})", reason),
+            }))
+        }
+        "CTRL-EVENT-AUTH-REJECT" => Some(Err("Authentication rejected".to_string())),
+        _ => None,
+    }
+}
+
+/// Assembles a configuration object from DPP control interface events
+///
+/// wpa_supplicant (with `dpp_config_processing=0`) reports the received
+/// configuration as a sequence of `DPP-CONFOBJ-*` events instead of adding a
+/// network itself.
+#[derive(Debug, Default)]
+struct DppConfigCollector {
+    ssid: Option<Ssid>,
+}
+
+impl DppConfigCollector {
+    /// Feed one event; returns the outcome once the configuration is complete
+    fn feed(&mut self, event: &str) -> Option<Result<DppConfig, String>> {
+        let event = strip_level(event);
+        let (name, value) = event.split_once(' ').unwrap_or((event, ""));
+
+        match name {
+            "DPP-CONF-FAILED" => Some(Err("Configuration failed".to_string())),
+            "DPP-AUTH-INIT-FAILED" => Some(Err("Authentication failed".to_string())),
+            // `connect` configures WPA-PSK only, SAE-only networks are refused
+            "DPP-CONFOBJ-AKM" if !value.split('+').any(|akm| akm == "psk") => {
+                Some(Err(format!("Unsupported DPP AKM: {}", value)))
+            }
+            "DPP-CONFOBJ-SSID" => {
+                self.ssid = Some(WpactrlBackend::decode_ssid(value));
+                None
+            }
+            "DPP-CONFOBJ-PASS" => {
+                let passphrase = hex::decode(value)
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok());
+                Some(self.complete(passphrase, None))
+            }
+            "DPP-CONFOBJ-PSK" => {
+                let psk = hex::decode(value)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+                Some(self.complete(None, psk))
+            }
+            _ => None,
+        }
+    }
+
+    fn complete(
+        &mut self,
+        passphrase: Option<String>,
+        psk: Option<[u8; 32]>,
+    ) -> Result<DppConfig, String> {
+        let ssid = self
+            .ssid
+            .take()
+            .ok_or_else(|| "Configuration object without SSID".to_string())?;
+        if passphrase.is_none() && psk.is_none() {
+            return Err("Malformed credential in configuration object".to_string());
+        }
+
+        Ok(DppConfig {
+            ssid,
+            passphrase,
+            psk,
+        })
+    }
+}
+
+/// Serializes short-lived control connections
+///
+/// `wpactrl` names client sockets after the process id and a counter that
+/// restarts for every connection, so two connections open at the same time
+/// unlink each other's socket and one of them never sees its reply.
+static CTRL_LOCK: Mutex<()> = Mutex::new(());
+
+/// Control connection holding `CTRL_LOCK` while open
+struct CtrlConnection {
+    client: Client,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Deref for CtrlConnection {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl DerefMut for CtrlConnection {
+    fn deref_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+}
+
+/// Client socket directory of the event listener for `purpose` on `interface`
+///
+/// Listeners of several interfaces run at the same time, and client sockets
+/// are only unique per process, so each gets its own directory.
+fn event_client_dir(interface: &str, purpose: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "wifi-commissioning-{}-{}-{}",
+        std::process::id(),
+        interface,
+        purpose
+    ))
+}
+
+/// Open a control connection to the wpa_supplicant socket at `ctrl_socket`
+fn open_ctrl(ctrl_socket: &str) -> WifiResult<CtrlConnection> {
+    let guard = CTRL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let client = Client::builder()
+        .ctrl_path(ctrl_socket)
+        .open()
+        .map_err(|e| {
+            WifiError::WpaSupplicantError(format!("Failed to connect to wpa_supplicant: {}", e))
+        })?;
+
+    Ok(CtrlConnection {
+        client,
+        _guard: guard,
+    })
+}
+
 /// Real wpa_supplicant backend implementation
 pub struct WpactrlBackend {
     interface: String,
     ctrl_socket: String,
+    /// Time given to a scan before its results are read
+    scan_wait: Duration,
+    dpp: Mutex<Option<DppSession>>,
+    wps: Mutex<Option<EventSession>>,
+    association: Mutex<Option<EventSession>>,
 }
 
 impl WpactrlBackend {
     /// Create a new wpa_supplicant backend
     pub fn new(interface: String) -> Self {
         let ctrl_socket = format!("/var/run/wpa_supplicant/{}", interface);
+        Self::with_ctrl_socket(interface, ctrl_socket, DEFAULT_SCAN_WAIT)
+    }
+
+    /// Create a new wpa_supplicant backend using the control socket at
+    /// `ctrl_socket` and waiting `scan_wait` for scan results
+    pub fn with_ctrl_socket(interface: String, ctrl_socket: String, scan_wait: Duration) -> Self {
         Self {
             interface,
             ctrl_socket,
+            scan_wait,
+            dpp: Mutex::new(None),
+            wps: Mutex::new(None),
+            association: Mutex::new(None),
         }
     }
 
     /// Parse scan results from wpa_supplicant output
-    fn parse_scan_results(output: &str) -> Vec<WifiNetwork> {
+    pub(crate) fn parse_scan_results(output: &str) -> Vec<WifiNetwork> {
         let mut networks = Vec::new();
 
         for line in output.lines().skip(1) {
@@ -39,14 +275,22 @@ impl WpactrlBackend {
             if parts.len() >= 5 {
                 let mac = parts[0].to_string();
                 let channel = Self::frequency_to_channel(parts[1]);
+                let frequency = parts[1].parse::<u32>().unwrap_or(0);
                 let rssi = parts[2].parse::<i16>().unwrap_or(0);
-                let ssid = parts[4].to_string();
+                let flags: Vec<&str> = parts[3]
+                    .split(['[', ']'])
+                    .filter(|flag| !flag.is_empty())
+                    .collect();
+                let ssid = Self::decode_ssid(parts[4]);
 
                 networks.push(WifiNetwork {
                     ssid,
                     mac,
                     channel,
+                    frequency,
                     rssi,
+                    security: Security::from_flags(&flags),
+                    ..Default::default()
                 });
             }
         }
@@ -54,54 +298,328 @@ impl WpactrlBackend {
         networks
     }
 
+    /// Parse the output of the `BSS <bssid>` command
+    ///
+    /// Returns `None` if wpa_supplicant does not know the BSS (empty output).
+    fn parse_bss(output: &str) -> Option<BssDetails> {
+        let mut fields = std::collections::HashMap::new();
+        for line in output.lines() {
+            if let Some((key, value)) = line.split_once('=') {
+                fields.insert(key, value);
+            }
+        }
+
+        let bssid = fields.get("bssid")?.to_string();
+        let freq = fields.get("freq").copied().unwrap_or_default();
+        let ies = fields.get("ie").copied().unwrap_or_default().to_string();
+        let ie_bytes = hex::decode(&ies).unwrap_or_default();
+
+        let flags: Vec<String> = fields
+            .get("flags")
+            .copied()
+            .unwrap_or_default()
+            .split(['[', ']'])
+            .filter(|flag| !flag.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let wps = flags.iter().find_map(|flag| match flag.as_str() {
+            "WPS" => Some(WpsState::Available),
+            "WPS-PBC" => Some(WpsState::PushButton),
+            "WPS-PIN" => Some(WpsState::Pin),
+            "WPS-AUTH" => Some(WpsState::Authorized),
+            _ => None,
+        });
+
+        let capabilities = fields
+            .get("capabilities")
+            .and_then(|caps| u16::from_str_radix(caps.trim_start_matches("0x"), 16).ok())
+            .unwrap_or(0);
+
+        Some(BssDetails {
+            bssid,
+            ssid: Self::decode_ssid(fields.get("ssid").copied().unwrap_or_default()),
+            frequency: freq.parse().unwrap_or(0),
+            channel: Self::frequency_to_channel(freq),
+            rssi: fields
+                .get("level")
+                .and_then(|level| level.parse().ok())
+                .unwrap_or(0),
+            noise: fields.get("noise").and_then(|noise| noise.parse().ok()),
+            beacon_interval: fields
+                .get("beacon_int")
+                .and_then(|interval| interval.parse().ok())
+                .unwrap_or(0),
+            capabilities,
+            supported_rates: Self::supported_rates(&ie_bytes),
+            ht: Self::find_ie(&ie_bytes, IE_HT_CAPABILITIES).is_some(),
+            vht: Self::find_ie(&ie_bytes, IE_VHT_CAPABILITIES).is_some(),
+            he: Self::information_elements(&ie_bytes).any(|(id, data)| {
+                id == IE_EXTENSION && data.first() == Some(&IE_EXT_HE_CAPABILITIES)
+            }),
+            wps,
+            flags,
+            age_secs: fields
+                .get("age")
+                .and_then(|age| age.parse().ok())
+                .unwrap_or(0),
+            ies,
+        })
+    }
+
+    /// Iterate over `(element id, payload)` pairs of raw information elements
+    ///
+    /// Iteration stops at the first truncated element.
+    fn information_elements(ies: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+        let mut rest = ies;
+        std::iter::from_fn(move || {
+            let (&id, tail) = rest.split_first()?;
+            let (&len, tail) = tail.split_first()?;
+            let data = tail.get(..len as usize)?;
+            rest = &tail[len as usize..];
+            Some((id, data))
+        })
+    }
+
+    /// Find the payload of the first information element with the given id
+    fn find_ie(ies: &[u8], id: u8) -> Option<&[u8]> {
+        Self::information_elements(ies)
+            .find(|(ie_id, _)| *ie_id == id)
+            .map(|(_, data)| data)
+    }
+
+    /// Collect supported and extended supported rates in kbit/s
+    fn supported_rates(ies: &[u8]) -> Vec<u32> {
+        Self::information_elements(ies)
+            .filter(|(id, _)| *id == IE_SUPPORTED_RATES || *id == IE_EXTENDED_RATES)
+            .flat_map(|(_, data)| data.iter())
+            // Rates are in 500 kbit/s units; the high bit marks basic rates
+            .map(|rate| u32::from(rate & 0x7f) * 500)
+            .collect()
+    }
+
+    /// Decode an SSID printed by wpa_supplicant
+    ///
+    /// wpa_supplicant escapes SSIDs in its control interface output with
+    /// printf-style sequences (`\\`, `\"`, `\e`, `\n`, `\r`, `\t` and
+    /// `\xNN` for any other non-printable octet). This reverses that encoding
+    /// and returns the raw SSID octets.
+    pub(crate) fn decode_ssid(escaped: &str) -> Ssid {
+        let input = escaped.as_bytes();
+        let mut bytes = Vec::with_capacity(input.len());
+        let mut i = 0;
+
+        while i < input.len() {
+            if input[i] != b'\\' || i + 1 >= input.len() {
+                bytes.push(input[i]);
+                i += 1;
+                continue;
+            }
+
+            match input[i + 1] {
+                b'\\' => bytes.push(b'\\'),
+                b'"' => bytes.push(b'"'),
+                b'e' => bytes.push(0x1b),
+                b'n' => bytes.push(b'\n'),
+                b'r' => bytes.push(b'\r'),
+                b't' => bytes.push(b'\t'),
+                b'x' => {
+                    let value = input
+                        .get(i + 2..i + 4)
+                        .and_then(|digits| std::str::from_utf8(digits).ok())
+                        .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+
+                    if let Some(value) = value {
+                        bytes.push(value);
+                        i += 4;
+                    } else {
+                        // Malformed escape: keep it verbatim
+                        bytes.extend_from_slice(&input[i..i + 2]);
+                        i += 2;
+                    }
+                    continue;
+                }
+                other => {
+                    // Unknown escape: keep it verbatim
+                    bytes.push(b'\\');
+                    bytes.push(other);
+                }
+            }
+            i += 2;
+        }
+
+        Ssid::new(bytes)
+    }
+
     /// Convert frequency (MHz) to channel number
-    fn frequency_to_channel(freq_str: &str) -> u16 {
+    ///
+    /// Channels are counted in 5 MHz steps from the start of their band; 0 if
+    /// the frequency is no channel center.
+    pub(crate) fn frequency_to_channel(freq_str: &str) -> u16 {
         let freq = freq_str.parse::<u16>().unwrap_or(0);
-        match freq {
-            2412 => 1,
-            2417 => 2,
-            2422 => 3,
-            2427 => 4,
-            2432 => 5,
-            2437 => 6,
-            2442 => 7,
-            2447 => 8,
-            2452 => 9,
-            2457 => 10,
-            2462 => 11,
-            2467 => 12,
-            2472 => 13,
-            2484 => 14,
-            // 5GHz channels (simplified)
-            5180 => 36,
-            5200 => 40,
-            5220 => 44,
-            5240 => 48,
-            5260 => 52,
-            5280 => 56,
-            5300 => 60,
-            5320 => 64,
-            5500 => 100,
-            5520 => 104,
-            5540 => 108,
-            5560 => 112,
-            5580 => 116,
-            5660 => 132,
-            5680 => 136,
-            5700 => 140,
-            5745 => 149,
-            5765 => 153,
-            5785 => 157,
-            5805 => 161,
-            5825 => 165,
+        let start = match freq {
+            2484 => return 14,
+            2412..=2472 => 2407,
+            5150..=5925 => 5000,
+            // The only 6 GHz channel off the 20 MHz grid
+            5935 => return 2,
+            5955..=7115 => 5950,
+            _ => return 0,
+        };
+
+        match (freq - start) % 5 {
+            0 => (freq - start) / 5,
             _ => 0,
         }
     }
 
-    /// Get IP address using ip command
-    async fn get_ip_address(&self) -> Option<String> {
+    /// Send a command and return its reply, mapping `FAIL` to `error`
+    async fn checked_request(
+        &self,
+        command: String,
+        error: fn(String) -> WifiError,
+    ) -> WifiResult<String> {
+        let ctrl_socket = self.ctrl_socket.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
+
+            let reply = ctrl
+                .request(&command)
+                .map_err(|e| error(format!("{}: {}", command, e)))?;
+            let reply = reply.trim().to_string();
+            if reply.starts_with("FAIL") {
+                return Err(error(format!("{} rejected", command)));
+            }
+            Ok(reply)
+        })
+        .await
+        .map_err(|e| WifiError::WpaSupplicantError(format!("Task join error: {}", e)))?
+    }
+
+    /// Start an event listener for `purpose`, returning once it is attached
+    /// so that no event of the following command is missed
+    async fn listen<T: Send + 'static>(
+        &self,
+        purpose: &str,
+        feed: impl FnMut(&str) -> Option<Result<T, String>> + Send + 'static,
+    ) -> (Arc<AtomicBool>, EventResult<T>) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let result = EventResult::default();
+        let (attached, attaching) = tokio::sync::oneshot::channel();
+        let ctrl_socket = self.ctrl_socket.clone();
+        let client_dir = event_client_dir(&self.interface, purpose);
+        let (listener_stop, listener_result) = (stop.clone(), result.clone());
+        std::thread::spawn(move || {
+            Self::event_listener(
+                ctrl_socket,
+                client_dir,
+                listener_stop,
+                listener_result,
+                attached,
+                feed,
+            )
+        });
+        // A failed attach is reported through the result
+        let _ = attaching.await;
+        (stop, result)
+    }
+
+    /// Listen for events until `feed` returns an outcome or `stop` is set
+    fn event_listener<T>(
+        ctrl_socket: String,
+        client_dir: PathBuf,
+        stop: Arc<AtomicBool>,
+        result: EventResult<T>,
+        attached: tokio::sync::oneshot::Sender<()>,
+        mut feed: impl FnMut(&str) -> Option<Result<T, String>>,
+    ) {
+        // The listener stays open for the whole session, so it gets its own
+        // client directory instead of holding CTRL_LOCK
+        let ctrl = std::fs::create_dir_all(&client_dir)
+            .map_err(wpactrl::Error::from)
+            .and_then(|()| {
+                Client::builder()
+                    .ctrl_path(&ctrl_socket)
+                    .cli_path(client_dir)
+                    .open()
+            })
+            .and_then(|ctrl| ctrl.attach());
+        let mut ctrl = match ctrl {
+            Ok(ctrl) => ctrl,
+            Err(e) => {
+                *result.lock().unwrap() = Some(Err(format!("Failed to attach: {}", e)));
+                return;
+            }
+        };
+        let _ = attached.send(());
+
+        while !stop.load(Ordering::Relaxed) {
+            match ctrl.recv() {
+                Ok(Some(event)) => {
+                    if let Some(outcome) = feed(&event) {
+                        *result.lock().unwrap() = Some(outcome);
+                        return;
+                    }
+                }
+                Ok(None) => std::thread::sleep(EVENT_POLL_INTERVAL),
+                Err(e) => {
+                    *result.lock().unwrap() = Some(Err(format!("Event receive failed: {}", e)));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Stop an event listener and forget its outcome
+    fn end_session(session: &Mutex<Option<EventSession>>) {
+        if let Some(session) = session.lock().unwrap().take() {
+            session.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the event listener of `session` reported a failure
+    fn session_failed(session: &Mutex<Option<EventSession>>) -> bool {
+        session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|session| matches!(*session.result.lock().unwrap(), Some(Err(_))))
+    }
+
+    /// Build capabilities from `GET_CAPABILITY` replies
+    ///
+    /// `key_mgmt` and `eap` are space-separated lists, `freq` lists the
+    /// enabled frequencies per hardware mode (`Mode[A] Channels: 5180 ...`).
+    fn parse_capabilities(
+        key_mgmt: &str,
+        eap: &str,
+        freq: &str,
+        persistence: bool,
+    ) -> Capabilities {
+        let key_mgmt: Vec<&str> = key_mgmt.split_whitespace().collect();
+
+        Capabilities {
+            // connect only configures WPA-PSK networks, even if the
+            // supplicant offers SAE or WPA-EAP
+            sae: false,
+            enterprise: false,
+            // WPS registration runs as EAP-WSC
+            wps: eap.split_whitespace().any(|method| method == "WSC"),
+            dpp: key_mgmt.contains(&"DPP"),
+            band_6ghz: freq
+                .split_whitespace()
+                .filter_map(|token| token.parse().ok())
+                .any(|freq| Band::from_frequency(freq) == Some(Band::Band6Ghz)),
+            persistence,
+            set_country: true,
+        }
+    }
+
+    /// Get IP address of `interface` using ip command
+    pub(crate) async fn get_ip_address(interface: &str) -> Option<String> {
         let output = Command::new("ip")
-            .args(["-4", "addr", "show", &self.interface])
+            .args(["-4", "addr", "show", interface])
             .output()
             .await
             .ok()?;
@@ -121,12 +639,59 @@ impl WpactrlBackend {
         None
     }
 
+    /// Build the `SCAN` command for `options`
+    ///
+    /// SSIDs to probe are passed hex-encoded as `ssid <hex>` pairs.
+    fn scan_command(options: &ScanOptions) -> String {
+        let mut command = "SCAN".to_string();
+        for ssid in &options.ssids {
+            command.push_str(&format!(" ssid {}", ssid.to_hex()));
+        }
+        if let Some(freqs) = options.freq_list.as_ref().filter(|f| !f.is_empty()) {
+            let freqs: Vec<String> = freqs.iter().map(u32::to_string).collect();
+            command.push_str(&format!(" freq={}", freqs.join(",")));
+        }
+        if options.passive {
+            command.push_str(" passive=1");
+        }
+        command
+    }
+
+    /// Time to wait for the results of a scan
+    ///
+    /// With a frequency list and dwell hint the scan is expected to take
+    /// `channels * dwell_ms`, bounded by the configured scan wait.
+    fn scan_duration(&self, options: &ScanOptions) -> Duration {
+        match (&options.freq_list, options.dwell_ms) {
+            (Some(freqs), Some(dwell_ms)) => {
+                let expected = Duration::from_millis(freqs.len() as u64 * u64::from(dwell_ms));
+                (expected + SCAN_WAIT_MARGIN).min(self.scan_wait)
+            }
+            _ => self.scan_wait,
+        }
+    }
+
+    /// Parse `LIST_NETWORKS` output into the configured SSIDs
+    pub(crate) fn parse_list_networks(output: &str) -> Vec<Ssid> {
+        let mut ssids: Vec<Ssid> = Vec::new();
+        for line in output.lines().skip(1) {
+            let Some(ssid) = line.split('\t').nth(1) else {
+                continue;
+            };
+            let ssid = Self::decode_ssid(ssid);
+            if !ssid.is_empty() && !ssids.contains(&ssid) {
+                ssids.push(ssid);
+            }
+        }
+        ssids
+    }
+
     /// Get SSID of connected network
-    async fn get_connected_ssid(&self) -> Option<String> {
+    async fn get_connected_ssid(&self) -> Option<Ssid> {
         let ctrl_socket = self.ctrl_socket.clone();
 
         let status = tokio::task::spawn_blocking(move || {
-            let mut ctrl = Client::builder().ctrl_path(&ctrl_socket).open().ok()?;
+            let mut ctrl = open_ctrl(&ctrl_socket).ok()?;
 
             ctrl.request("STATUS").ok()
         })
@@ -135,7 +700,7 @@ impl WpactrlBackend {
 
         for line in status.lines() {
             if let Some(stripped) = line.strip_prefix("ssid=") {
-                return Some(stripped.to_string());
+                return Some(Self::decode_ssid(stripped));
             }
         }
 
@@ -144,7 +709,7 @@ impl WpactrlBackend {
 }
 
 impl WifiBackend for WpactrlBackend {
-    async fn scan(&self) -> WifiResult<Vec<WifiNetwork>> {
+    async fn scan(&self, options: &ScanOptions) -> WifiResult<Vec<WifiNetwork>> {
         debug!("Starting WiFi scan on interface: {}", self.interface);
 
         // Check if socket exists
@@ -156,41 +721,26 @@ impl WifiBackend for WpactrlBackend {
         }
 
         let ctrl_socket = self.ctrl_socket.clone();
+        let command = Self::scan_command(options);
 
         // Trigger scan in blocking thread
         tokio::task::spawn_blocking(move || {
-            let mut ctrl = Client::builder()
-                .ctrl_path(&ctrl_socket)
-                .open()
-                .map_err(|e| {
-                    WifiError::WpaSupplicantError(format!(
-                        "Failed to connect to wpa_supplicant: {}",
-                        e
-                    ))
-                })?;
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
 
-            ctrl.request("SCAN")
+            ctrl.request(&command)
                 .map_err(|e| WifiError::WpaSupplicantError(format!("Failed to start scan: {}", e)))
         })
         .await
         .map_err(|e| WifiError::WpaSupplicantError(format!("Task join error: {}", e)))??;
 
         // Wait for scan to complete
-        tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+        tokio::time::sleep(self.scan_duration(options)).await;
 
         let ctrl_socket = self.ctrl_socket.clone();
 
         // Get scan results in blocking thread
         let results = tokio::task::spawn_blocking(move || {
-            let mut ctrl = Client::builder()
-                .ctrl_path(&ctrl_socket)
-                .open()
-                .map_err(|e| {
-                    WifiError::WpaSupplicantError(format!(
-                        "Failed to connect to wpa_supplicant: {}",
-                        e
-                    ))
-                })?;
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
 
             ctrl.request("SCAN_RESULTS").map_err(|e| {
                 WifiError::WpaSupplicantError(format!("Failed to get scan results: {}", e))
@@ -205,24 +755,60 @@ impl WifiBackend for WpactrlBackend {
         Ok(networks)
     }
 
-    async fn connect(&self, ssid: &str, psk: &[u8; 32]) -> WifiResult<()> {
-        let ssid_str = ssid.to_string();
-        debug!("Connecting to network: {}", ssid_str);
+    async fn abort_scan(&self) -> WifiResult<()> {
+        debug!("Aborting scan on interface: {}", self.interface);
+        self.checked_request("ABORT_SCAN".to_string(), WifiError::ScanFailed)
+            .await?;
+        Ok(())
+    }
+
+    async fn network_details(&self, bssid: &str) -> WifiResult<BssDetails> {
+        debug!("Requesting BSS details for {}", bssid);
 
         let ctrl_socket = self.ctrl_socket.clone();
+        let command = format!("BSS {}", bssid);
+
+        let output = tokio::task::spawn_blocking(move || {
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
+
+            ctrl.request(&command).map_err(|e| {
+                WifiError::WpaSupplicantError(format!("Failed to get BSS details: {}", e))
+            })
+        })
+        .await
+        .map_err(|e| WifiError::WpaSupplicantError(format!("Task join error: {}", e)))??;
+
+        Self::parse_bss(&output).ok_or_else(|| WifiError::BssNotFound(bssid.to_string()))
+    }
+
+    async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> WifiResult<()> {
+        debug!("Connecting to network: {}", ssid);
+        Self::end_session(&self.wps);
+        Self::end_session(&self.association);
+
+        if ssid.is_empty() || ssid.len() > Ssid::MAX_LEN {
+            return Err(WifiError::InvalidSsid(format!(
+                "SSID must be 1 to {} octets, got {}",
+                Ssid::MAX_LEN,
+                ssid.len()
+            )));
+        }
+
+        let ctrl_socket = self.ctrl_socket.clone();
+        let ssid_hex = ssid.to_hex();
         let psk = *psk;
+        let options = options.clone();
 
-        tokio::task::spawn_blocking(move || {
-            let ssid = ssid_str;
-            let mut ctrl = Client::builder()
-                .ctrl_path(&ctrl_socket)
-                .open()
-                .map_err(|e| {
-                    WifiError::WpaSupplicantError(format!(
-                        "Failed to connect to wpa_supplicant: {}",
-                        e
-                    ))
-                })?;
+        let (stop, result) = self.listen("association", association_event_outcome).await;
+        *self.association.lock().unwrap() = Some(EventSession { stop, result });
+
+        let initiated = tokio::task::spawn_blocking(move || {
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
 
             // Convert PSK to hex string
             let psk_hex = hex::encode(psk);
@@ -236,8 +822,8 @@ impl WifiBackend for WpactrlBackend {
                 .trim()
                 .to_string();
 
-            // Set SSID
-            ctrl.request(&format!("SET_NETWORK {} ssid \"{}\"", network_id, ssid))
+            // Set SSID (hex-encoded, so arbitrary octets need no quoting)
+            ctrl.request(&format!("SET_NETWORK {} ssid {}", network_id, ssid_hex))
                 .map_err(|e| {
                     WifiError::WpaSupplicantError(format!("Failed to set network SSID: {}", e))
                 })?;
@@ -248,6 +834,30 @@ impl WifiBackend for WpactrlBackend {
                     WifiError::WpaSupplicantError(format!("Failed to set network PSK: {}", e))
                 })?;
 
+            // Pin to a specific BSS
+            if let Some(bssid) = &options.bssid {
+                ctrl.request(&format!("SET_NETWORK {} bssid {}", network_id, bssid))
+                    .map_err(|e| {
+                        WifiError::WpaSupplicantError(format!("Failed to set network BSSID: {}", e))
+                    })?;
+            }
+
+            // Restrict usable frequencies
+            if let Some(freq_list) = options.freq_list.as_ref().filter(|f| !f.is_empty()) {
+                let freqs: Vec<String> = freq_list.iter().map(u32::to_string).collect();
+                ctrl.request(&format!(
+                    "SET_NETWORK {} freq_list {}",
+                    network_id,
+                    freqs.join(" ")
+                ))
+                .map_err(|e| {
+                    WifiError::WpaSupplicantError(format!(
+                        "Failed to set network frequency list: {}",
+                        e
+                    ))
+                })?;
+            }
+
             // Enable network
             ctrl.request(&format!("ENABLE_NETWORK {}", network_id))
                 .map_err(|e| {
@@ -263,27 +873,267 @@ impl WifiBackend for WpactrlBackend {
             Ok::<(), WifiError>(())
         })
         .await
-        .map_err(|e| WifiError::WpaSupplicantError(format!("Task join error: {}", e)))??;
+        .map_err(|e| WifiError::WpaSupplicantError(format!("Task join error: {}", e)))
+        .and_then(|initiated| initiated);
+        if let Err(e) = initiated {
+            Self::end_session(&self.association);
+            return Err(e);
+        }
 
         debug!("Connection initiated");
         Ok(())
     }
 
+    async fn start_wps(
+        &self,
+        method: &WpsMethod,
+        bssid: Option<&str>,
+    ) -> WifiResult<Option<String>> {
+        Self::end_session(&self.wps);
+        Self::end_session(&self.association);
+        let (stop, result) = self.listen("wps", wps_event_outcome).await;
+        *self.wps.lock().unwrap() = Some(EventSession { stop, result });
+
+        let started = match method {
+            WpsMethod::PushButton => {
+                debug!("Starting WPS push-button registration");
+                let command = match bssid {
+                    Some(bssid) => format!("WPS_PBC {}", bssid),
+                    None => "WPS_PBC".to_string(),
+                };
+                self.checked_request(command, WifiError::WpsFailed)
+                    .await
+                    .map(|_| None)
+            }
+            WpsMethod::Pin(pin) => {
+                debug!("Starting WPS PIN registration");
+                let mut command = format!("WPS_PIN {}", bssid.unwrap_or("any"));
+                if let Some(pin) = pin {
+                    command.push(' ');
+                    command.push_str(pin);
+                }
+                // wpa_supplicant replies with the PIN in use (generated if not given)
+                self.checked_request(command, WifiError::WpsFailed)
+                    .await
+                    .map(|reply| Some(pin.clone().unwrap_or(reply)))
+            }
+        };
+        if started.is_err() {
+            Self::end_session(&self.wps);
+        }
+        started
+    }
+
+    async fn wps_result(&self) -> WifiResult<bool> {
+        let outcome = match self.wps.lock().unwrap().as_ref() {
+            Some(session) => session.result.lock().unwrap().clone(),
+            None => None,
+        };
+
+        match outcome {
+            Some(Ok(())) => Ok(true),
+            Some(Err(e)) => Err(WifiError::WpsFailed(e)),
+            None => Ok(false),
+        }
+    }
+
+    async fn cancel_wps(&self) -> WifiResult<()> {
+        debug!("Cancelling WPS registration");
+        Self::end_session(&self.wps);
+        self.checked_request("WPS_CANCEL".to_string(), WifiError::WpsFailed)
+            .await?;
+        Ok(())
+    }
+
+    async fn start_dpp(&self) -> WifiResult<String> {
+        debug!("Starting DPP enrollee");
+
+        // Stop a previous session first, its bootstrap key is replaced
+        self.stop_dpp().await?;
+
+        // Read before anything changes, the session restores it when it ends
+        let config_processing = self
+            .checked_request(
+                "GET dpp_config_processing".to_string(),
+                WifiError::DppFailed,
+            )
+            .await
+            .ok();
+
+        let mut command = format!("DPP_BOOTSTRAP_GEN type=qrcode chan={}", DPP_LISTEN_CHANNEL);
+        let address_path = format!("/sys/class/net/{}/address", self.interface);
+        if let Ok(mac) = tokio::fs::read_to_string(&address_path).await {
+            command.push_str(&format!(" mac={}", mac.trim()));
+        }
+        let bootstrap_id = self.checked_request(command, WifiError::DppFailed).await?;
+        // From here on `stop_dpp` removes the key and restores the setting
+        *self.dpp.lock().unwrap() = Some(DppSession {
+            bootstrap_id: bootstrap_id.clone(),
+            config_processing,
+            stop: Arc::default(),
+            result: EventResult::default(),
+        });
+
+        let started = async {
+            let uri = self
+                .checked_request(
+                    format!("DPP_BOOTSTRAP_GET_URI {}", bootstrap_id),
+                    WifiError::DppFailed,
+                )
+                .await?;
+
+            // Report configuration objects as events instead of adding a network
+            self.checked_request(
+                "SET dpp_config_processing 0".to_string(),
+                WifiError::DppFailed,
+            )
+            .await?;
+
+            let mut collector = DppConfigCollector::default();
+            let (stop, result) = self.listen("dpp", move |event| collector.feed(event)).await;
+            if let Some(session) = self.dpp.lock().unwrap().as_mut() {
+                session.stop = stop;
+                session.result = result;
+            }
+
+            self.checked_request(
+                format!("DPP_LISTEN {}", DPP_LISTEN_FREQ),
+                WifiError::DppFailed,
+            )
+            .await?;
+            Ok(uri)
+        }
+        .await;
+        let uri = match started {
+            Ok(uri) => uri,
+            Err(e) => {
+                let _ = self.stop_dpp().await;
+                return Err(e);
+            }
+        };
+
+        debug!("DPP listening, bootstrap URI: {}", uri);
+        Ok(uri)
+    }
+
+    async fn dpp_config(&self) -> WifiResult<Option<DppConfig>> {
+        let outcome = match self.dpp.lock().unwrap().as_ref() {
+            Some(session) => session.result.lock().unwrap().take(),
+            None => None,
+        };
+
+        outcome.transpose().map_err(WifiError::DppFailed)
+    }
+
+    async fn stop_dpp(&self) -> WifiResult<()> {
+        let Some(session) = self.dpp.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        debug!("Stopping DPP enrollee");
+        session.stop.store(true, Ordering::Relaxed);
+        let stopped = self
+            .checked_request("DPP_STOP_LISTEN".to_string(), WifiError::DppFailed)
+            .await;
+        if let Err(e) = self
+            .checked_request(
+                format!("DPP_BOOTSTRAP_REMOVE {}", session.bootstrap_id),
+                WifiError::DppFailed,
+            )
+            .await
+        {
+            warn!("Failed to remove DPP bootstrap key: {}", e);
+        }
+        if let Some(previous) = session.config_processing {
+            if let Err(e) = self
+                .checked_request(
+                    format!("SET dpp_config_processing {}", previous),
+                    WifiError::DppFailed,
+                )
+                .await
+            {
+                warn!("Failed to restore dpp_config_processing: {}", e);
+            }
+        }
+        stopped.map(|_| ())
+    }
+
+    async fn get_country(&self) -> WifiResult<Option<String>> {
+        // GET fails while no country is configured
+        match self
+            .checked_request("GET country".to_string(), WifiError::RegulatoryError)
+            .await
+        {
+            Ok(country) if !country.is_empty() => Ok(Some(country)),
+            Ok(_) | Err(WifiError::RegulatoryError(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn set_country(&self, country: &str, persist: bool) -> WifiResult<()> {
+        debug!("Setting country code to {}", country);
+        self.checked_request(
+            format!("SET country {}", country),
+            WifiError::RegulatoryError,
+        )
+        .await?;
+
+        if persist {
+            // Requires update_config=1 in wpa_supplicant.conf
+            self.checked_request("SAVE_CONFIG".to_string(), WifiError::RegulatoryError)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn capabilities(&self) -> WifiResult<Capabilities> {
+        let key_mgmt = self
+            .checked_request(
+                "GET_CAPABILITY key_mgmt".to_string(),
+                WifiError::WpaSupplicantError,
+            )
+            .await?;
+        // Fails if wpa_supplicant was built without EAP or driver support
+        let eap = self
+            .checked_request(
+                "GET_CAPABILITY eap".to_string(),
+                WifiError::WpaSupplicantError,
+            )
+            .await
+            .unwrap_or_default();
+        let freq = self
+            .checked_request(
+                "GET_CAPABILITY freq".to_string(),
+                WifiError::WpaSupplicantError,
+            )
+            .await
+            .unwrap_or_default();
+        // SAVE_CONFIG only works with update_config=1
+        let persistence = self
+            .checked_request(
+                "GET update_config".to_string(),
+                WifiError::WpaSupplicantError,
+            )
+            .await
+            .is_ok_and(|value| value == "1");
+
+        Ok(Self::parse_capabilities(
+            &key_mgmt,
+            &eap,
+            &freq,
+            persistence,
+        ))
+    }
+
     async fn disconnect(&self) -> WifiResult<()> {
         debug!("Disconnecting from network");
+        Self::end_session(&self.wps);
+        Self::end_session(&self.association);
 
         let ctrl_socket = self.ctrl_socket.clone();
 
         tokio::task::spawn_blocking(move || {
-            let mut ctrl = Client::builder()
-                .ctrl_path(&ctrl_socket)
-                .open()
-                .map_err(|e| {
-                    WifiError::WpaSupplicantError(format!(
-                        "Failed to connect to wpa_supplicant: {}",
-                        e
-                    ))
-                })?;
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
 
             ctrl.request("DISCONNECT")
                 .map_err(|e| WifiError::WpaSupplicantError(format!("Failed to disconnect: {}", e)))
@@ -299,15 +1149,7 @@ impl WifiBackend for WpactrlBackend {
         let ctrl_socket = self.ctrl_socket.clone();
 
         let status_output = tokio::task::spawn_blocking(move || {
-            let mut ctrl = Client::builder()
-                .ctrl_path(&ctrl_socket)
-                .open()
-                .map_err(|e| {
-                    WifiError::WpaSupplicantError(format!(
-                        "Failed to connect to wpa_supplicant: {}",
-                        e
-                    ))
-                })?;
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
 
             ctrl.request("STATUS")
                 .map_err(|e| WifiError::WpaSupplicantError(format!("Failed to get status: {}", e)))
@@ -324,7 +1166,11 @@ impl WifiBackend for WpactrlBackend {
             }
         }
 
+        // Kept until the next connect, disconnect or registration
+        let failed = Self::session_failed(&self.association);
+
         let state = match wpa_state.as_str() {
+            _ if failed => crate::core::types::ConnectionState::Failed,
             "COMPLETED" => crate::core::types::ConnectionState::Connected,
             "ASSOCIATING" | "AUTHENTICATING" | "4WAY_HANDSHAKE" | "GROUP_HANDSHAKE" => {
                 crate::core::types::ConnectionState::Connecting
@@ -342,22 +1188,464 @@ impl WifiBackend for WpactrlBackend {
         };
 
         let ip_address = if state == crate::core::types::ConnectionState::Connected {
-            self.get_ip_address().await
+            Self::get_ip_address(&self.interface).await
         } else {
             None
         };
 
+        // The country code is added by the connection service
         Ok(ConnectionStatus {
             state,
             ssid,
             ip_address,
+            country: None,
         })
     }
+
+    async fn saved_networks(&self) -> WifiResult<Vec<Ssid>> {
+        let ctrl_socket = self.ctrl_socket.clone();
+
+        let output = tokio::task::spawn_blocking(move || {
+            let mut ctrl = open_ctrl(&ctrl_socket)?;
+
+            ctrl.request("LIST_NETWORKS").map_err(|e| {
+                WifiError::WpaSupplicantError(format!("Failed to list networks: {}", e))
+            })
+        })
+        .await
+        .map_err(|e| WifiError::WpaSupplicantError(format!("Task join error: {}", e)))??;
+
+        Ok(Self::parse_list_networks(&output))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        backend::fake_supplicant::{FakeAp, FakeSupplicant},
+        core::types::{ConnectionState, passphrase_to_psk},
+    };
+
+    fn setup() -> (tempfile::TempDir, FakeSupplicant, WpactrlBackend) {
+        let dir = tempfile::tempdir().unwrap();
+        let supplicant = FakeSupplicant::start(&dir.path().join("fake0"));
+        supplicant.add_ap(FakeAp::new("02:00:00:00:00:01", "Home", 2437, "password1"));
+        supplicant.add_ap(FakeAp::new("02:00:00:00:00:02", "Home", 5180, "password1"));
+        let backend = WpactrlBackend::with_ctrl_socket(
+            "fake0".to_string(),
+            supplicant.path(),
+            Duration::from_millis(10),
+        );
+        (dir, supplicant, backend)
+    }
+
+    #[tokio::test]
+    async fn test_fake_scan() {
+        let (_dir, supplicant, backend) = setup();
+        let mut ap = FakeAp::new("02:00:00:00:00:03", "café\t\"x\"", 2412, "password2");
+        ap.signal = -80;
+        supplicant.add_ap(ap);
+
+        let networks = backend.scan(&ScanOptions::default()).await.unwrap();
+
+        assert_eq!(networks.len(), 3);
+        assert_eq!(networks[0].ssid, "Home");
+        assert_eq!(networks[0].channel, 6);
+        assert_eq!(networks[1].channel, 36);
+        assert_eq!(networks[2].ssid, "café\t\"x\"");
+        assert_eq!(networks[2].rssi, -80);
+        assert_eq!(supplicant.commands(), ["SCAN", "SCAN_RESULTS"]);
+    }
+
+    #[tokio::test]
+    async fn test_fake_targeted_scan() {
+        let (_dir, supplicant, backend) = setup();
+        let options = ScanOptions {
+            ssids: vec!["Hidden".into()],
+            freq_list: Some(vec![2437]),
+            ..Default::default()
+        };
+
+        let networks = backend.scan(&options).await.unwrap();
+
+        assert_eq!(networks.len(), 1);
+        assert_eq!(networks[0].ssid, "Home");
+        assert_eq!(
+            supplicant.commands(),
+            ["SCAN ssid 48696464656e freq=2437", "SCAN_RESULTS"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fake_abort_scan() {
+        let (_dir, supplicant, backend) = setup();
+        backend.abort_scan().await.unwrap();
+        assert_eq!(supplicant.commands(), ["ABORT_SCAN"]);
+    }
+
+    #[test]
+    fn test_scan_command() {
+        assert_eq!(
+            WpactrlBackend::scan_command(&ScanOptions::default()),
+            "SCAN"
+        );
+        let options = ScanOptions {
+            freq_list: Some(vec![2412, 5180]),
+            passive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            WpactrlBackend::scan_command(&options),
+            "SCAN freq=2412,5180 passive=1"
+        );
+
+        let backend = WpactrlBackend::new("wlan0".to_string());
+        let options = ScanOptions {
+            freq_list: Some(vec![2412, 2437, 2462]),
+            dwell_ms: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(backend.scan_duration(&options), Duration::from_millis(500));
+        assert_eq!(
+            backend.scan_duration(&ScanOptions::default()),
+            DEFAULT_SCAN_WAIT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fake_network_details() {
+        let (_dir, _supplicant, backend) = setup();
+        backend.scan(&ScanOptions::default()).await.unwrap();
+
+        let details = backend.network_details("02:00:00:00:00:02").await.unwrap();
+        assert_eq!(details.ssid, "Home");
+        assert_eq!(details.frequency, 5180);
+        assert_eq!(details.channel, 36);
+        assert_eq!(details.supported_rates, [6000, 12000, 24000]);
+
+        assert!(matches!(
+            backend.network_details("02:00:00:00:00:09").await,
+            Err(WifiError::BssNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fake_connect() {
+        let (_dir, supplicant, backend) = setup();
+        let ssid = Ssid::from("Home");
+        let psk = passphrase_to_psk("password1", &ssid);
+        let options = ConnectOptions {
+            bssid: Some("02:00:00:00:00:02".to_string()),
+            freq_list: Some(vec![5180]),
+            band: None,
+        };
+
+        backend.connect(&ssid, &psk, &options).await.unwrap();
+
+        // The association listener attaches before the network is selected
+        assert_eq!(
+            supplicant.commands(),
+            [
+                "ATTACH".to_string(),
+                "ADD_NETWORK".to_string(),
+                format!("SET_NETWORK 0 ssid {}", ssid.to_hex()),
+                format!("SET_NETWORK 0 psk {}", hex::encode(psk)),
+                "SET_NETWORK 0 bssid 02:00:00:00:00:02".to_string(),
+                "SET_NETWORK 0 freq_list 5180".to_string(),
+                "ENABLE_NETWORK 0".to_string(),
+                "SELECT_NETWORK 0".to_string(),
+            ]
+        );
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some(ssid.clone()));
+
+        backend.disconnect().await.unwrap();
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+
+        // The network stays configured
+        assert_eq!(backend.saved_networks().await.unwrap(), [ssid]);
+    }
+
+    #[tokio::test]
+    async fn test_fake_connect_wrong_psk() {
+        let (_dir, supplicant, backend) = setup();
+        let ssid = Ssid::from("Home");
+        let psk = passphrase_to_psk("wrong password", &ssid);
+
+        backend
+            .connect(&ssid, &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(supplicant.wpa_state(), "DISCONNECTED");
+        let mut status = backend.status().await.unwrap();
+        for _ in 0..100 {
+            if status.state == ConnectionState::Failed {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            status = backend.status().await.unwrap();
+        }
+        assert_eq!(status.state, ConnectionState::Failed);
+        assert_eq!(status.ssid, None);
+
+        // The next attempt starts over
+        let psk = passphrase_to_psk("password1", &ssid);
+        backend
+            .connect(&ssid, &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        let status = backend.status().await.unwrap();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some(ssid));
+    }
+
+    #[tokio::test]
+    async fn test_fake_ap_lost() {
+        let (_dir, supplicant, backend) = setup();
+        let ssid = Ssid::from("Home");
+        let psk = passphrase_to_psk("password1", &ssid);
+        let options = ConnectOptions {
+            bssid: Some("02:00:00:00:00:01".to_string()),
+            ..Default::default()
+        };
+        backend.connect(&ssid, &psk, &options).await.unwrap();
+        assert_eq!(
+            backend.status().await.unwrap().state,
+            ConnectionState::Connected
+        );
+
+        supplicant.remove_ap("02:00:00:00:00:01");
+
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_fake_country() {
+        let (_dir, supplicant, backend) = setup();
+        assert_eq!(backend.get_country().await.unwrap(), None);
+
+        backend.set_country("DE", true).await.unwrap();
+
+        assert_eq!(backend.get_country().await.unwrap().as_deref(), Some("DE"));
+        assert!(supplicant.commands().contains(&"SAVE_CONFIG".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_fake_capabilities() {
+        let (_dir, supplicant, backend) = setup();
+
+        let caps = backend.capabilities().await.unwrap();
+        assert_eq!(
+            caps,
+            Capabilities {
+                sae: false,
+                enterprise: false,
+                wps: true,
+                dpp: true,
+                band_6ghz: true,
+                persistence: true,
+                set_country: true,
+            }
+        );
+
+        supplicant.set_capability("key_mgmt", "NONE WPA-PSK");
+        supplicant.set_capability("eap", "FAIL");
+        supplicant.set_capability("freq", "Mode[G] Channels: 2412 2437");
+        supplicant.set_update_config(false);
+
+        let caps = backend.capabilities().await.unwrap();
+        assert_eq!(
+            caps,
+            Capabilities {
+                set_country: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fake_dpp() {
+        let (_dir, supplicant, backend) = setup();
+
+        let uri = backend.start_dpp().await.unwrap();
+        assert!(uri.starts_with("DPP:"));
+        for _ in 0..100 {
+            if supplicant.attached() > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(supplicant.attached(), 1);
+        assert_eq!(backend.dpp_config().await.unwrap(), None);
+
+        supplicant.send_event(&format!("DPP-CONFOBJ-SSID {}", "Home"));
+        supplicant.send_event(&format!("DPP-CONFOBJ-PASS {}", hex::encode("password1")));
+
+        let mut config = None;
+        for _ in 0..100 {
+            config = backend.dpp_config().await.unwrap();
+            if config.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let config = config.unwrap();
+        assert_eq!(config.ssid, "Home");
+        assert_eq!(config.passphrase.as_deref(), Some("password1"));
+
+        backend.stop_dpp().await.unwrap();
+        let commands = supplicant.commands();
+        assert!(commands.contains(&"DPP_BOOTSTRAP_REMOVE 1".to_string()));
+        // The supplicant's own configuration processing is restored
+        assert!(commands.contains(&"SET dpp_config_processing 0".to_string()));
+        assert_eq!(
+            commands.last().map(String::as_str),
+            Some("SET dpp_config_processing 2")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fake_dpp_start_failure() {
+        let (_dir, supplicant, backend) = setup();
+        supplicant.reject("DPP_BOOTSTRAP_GET_URI");
+
+        assert!(matches!(
+            backend.start_dpp().await,
+            Err(WifiError::DppFailed(_))
+        ));
+        // The generated key is removed and the setting left as it was
+        assert!(
+            supplicant
+                .commands()
+                .contains(&"DPP_BOOTSTRAP_REMOVE 1".to_string())
+        );
+        assert_eq!(supplicant.dpp_config_processing(), 2);
+        assert_eq!(backend.dpp_config().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_fake_wps_success() {
+        let (_dir, supplicant, backend) = setup();
+
+        let pin = backend
+            .start_wps(&WpsMethod::PushButton, None)
+            .await
+            .unwrap();
+        assert_eq!(pin, None);
+        assert!(!backend.wps_result().await.unwrap());
+
+        supplicant.send_event("WPS-SUCCESS");
+        let mut received = false;
+        for _ in 0..100 {
+            received = backend.wps_result().await.unwrap();
+            if received {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(received);
+    }
+
+    #[tokio::test]
+    async fn test_fake_wps_failure() {
+        let (_dir, supplicant, backend) = setup();
+
+        let pin = backend
+            .start_wps(&WpsMethod::Pin(None), Some("02:00:00:00:00:01"))
+            .await
+            .unwrap();
+        assert_eq!(pin, Some("12345670".to_string()));
+        for _ in 0..100 {
+            if supplicant.attached() > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(supplicant.attached(), 1);
+        assert!(!backend.wps_result().await.unwrap());
+
+        supplicant.send_event("WPS-OVERLAP-DETECTED");
+        let mut result = Ok(false);
+        for _ in 0..100 {
+            result = backend.wps_result().await;
+            if result.is_err() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(matches!(result, Err(WifiError::WpsFailed(_))));
+        // The interface itself is idle
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+
+        // Cancelling forgets the outcome
+        backend.cancel_wps().await.unwrap();
+        assert!(!backend.wps_result().await.unwrap());
+        assert!(supplicant.commands().contains(&"WPS_CANCEL".to_string()));
+    }
+
+    #[test]
+    fn test_event_client_dir() {
+        assert_ne!(
+            event_client_dir("wlan0", "dpp"),
+            event_client_dir("wlan1", "dpp")
+        );
+        assert_ne!(
+            event_client_dir("wlan0", "dpp"),
+            event_client_dir("wlan0", "wps")
+        );
+    }
+
+    #[test]
+    fn test_wps_event_outcome() {
+        assert_eq!(wps_event_outcome("<3>WPS-SUCCESS"), Some(Ok(())));
+        assert!(
+            wps_event_outcome("<3>WPS-FAIL msg=8 config_error=15")
+                .unwrap()
+                .is_err()
+        );
+        assert!(wps_event_outcome("<3>WPS-TIMEOUT").unwrap().is_err());
+        assert!(
+            wps_event_outcome("<3>WPS-OVERLAP-DETECTED")
+                .unwrap()
+                .is_err()
+        );
+        assert_eq!(wps_event_outcome("<3>WPS-PBC-ACTIVE"), None);
+        assert_eq!(wps_event_outcome("<3>CTRL-EVENT-SCAN-RESULTS "), None);
+    }
+
+    #[test]
+    fn test_association_event_outcome() {
+        assert_eq!(
+            association_event_outcome(
+                "<3>CTRL-EVENT-SSID-TEMP-DISABLED id=0 ssid=\"Home\" auth_failures=1 duration=10 reason=WRONG_KEY"
+            ),
+            Some(Err("Wrong passphrase".to_string()))
+        );
+        assert_eq!(
+            association_event_outcome(
+                "<3>CTRL-EVENT-SSID-TEMP-DISABLED id=0 ssid=\"Home\" auth_failures=2 duration=20 reason=AUTH_FAILED"
+            ),
+            Some(Err("Authentication failed (AUTH_FAILED)".to_string()))
+        );
+        assert!(
+            association_event_outcome("<3>CTRL-EVENT-AUTH-REJECT 02:00:00:00:00:01 auth_type=0")
+                .unwrap()
+                .is_err()
+        );
+        assert_eq!(
+            association_event_outcome(
+                "<3>CTRL-EVENT-CONNECTED - Connection to 02:00:00:00:00:01 completed"
+            ),
+            Some(Ok(()))
+        );
+        assert_eq!(
+            association_event_outcome("<3>CTRL-EVENT-NETWORK-NOT-FOUND"),
+            None
+        );
+    }
 
     #[test]
     fn test_parse_scan_results_basic() {
@@ -401,9 +1689,165 @@ mod tests {
         let networks = WpactrlBackend::parse_scan_results(input);
 
         assert_eq!(networks.len(), 3);
-        assert_eq!(networks[0].ssid, "Test\\tNetwork");
+        assert_eq!(networks[0].ssid, "Test\tNetwork");
         assert_eq!(networks[1].ssid, "Test\"Quote");
-        assert_eq!(networks[2].ssid, "Test\\nNewline");
+        assert_eq!(networks[2].ssid, "Test\nNewline");
+    }
+
+    #[test]
+    fn test_parse_scan_results_escaped_utf8() {
+        // wpa_supplicant escapes every octet >= 0x7f, so UTF-8 arrives as \x sequences
+        let input = "bssid / frequency / signal level / flags / ssid\n\
+                     01:02:03:04:05:06\t2437\t-45\t[WPA2-PSK-CCMP][ESS]\tMyWiFi\\xf0\\x9f\\x92\\xa9";
+
+        let networks = WpactrlBackend::parse_scan_results(input);
+
+        assert_eq!(networks.len(), 1);
+        assert_eq!(networks[0].ssid, "MyWiFi💩");
+    }
+
+    #[test]
+    fn test_parse_scan_results_non_utf8_ssid() {
+        let input = "bssid / frequency / signal level / flags / ssid\n\
+                     01:02:03:04:05:06\t2412\t-50\t[WPA2-PSK-CCMP][ESS]\tCaf\\xe9";
+
+        let networks = WpactrlBackend::parse_scan_results(input);
+
+        assert_eq!(networks.len(), 1);
+        assert_eq!(networks[0].ssid.as_bytes(), b"Caf\xe9");
+        assert!(networks[0].ssid.as_str().is_none());
+    }
+
+    #[test]
+    fn test_decode_ssid_escapes() {
+        assert_eq!(
+            WpactrlBackend::decode_ssid(r#"a\\b\"c\ed\re"#).as_bytes(),
+            b"a\\b\"c\x1bd\re"
+        );
+        assert_eq!(
+            WpactrlBackend::decode_ssid(r"\x00\xff").as_bytes(),
+            &[0x00, 0xff]
+        );
+    }
+
+    #[test]
+    fn test_decode_ssid_malformed_escapes() {
+        // Incomplete or unknown escapes are kept verbatim
+        assert_eq!(WpactrlBackend::decode_ssid(r"a\xz").as_bytes(), br"a\xz");
+        assert_eq!(WpactrlBackend::decode_ssid(r"a\q").as_bytes(), br"a\q");
+        assert_eq!(WpactrlBackend::decode_ssid(r"a\").as_bytes(), br"a\");
+    }
+
+    #[test]
+    fn test_parse_bss() {
+        // Supported rates (1, 2, 5.5, 11 Mbit/s basic), extended rates (6, 54),
+        // HT capabilities and an HE capabilities extension element
+        let ies = "010482848b96\
+                   32020c6c\
+                   2d02ef01\
+                   ff0323aabb";
+        let input = format!(
+            "id=3\n\
+             bssid=aa:bb:cc:dd:ee:ff\n\
+             freq=2437\n\
+             beacon_int=100\n\
+             capabilities=0x0431\n\
+             qual=0\n\
+             noise=-92\n\
+             level=-48\n\
+             tsf=0000001234567890\n\
+             age=7\n\
+             ie={}\n\
+             flags=[WPA2-PSK-CCMP][WPS-PBC][ESS]\n\
+             ssid=Caf\\xc3\\xa9\n\
+             snr=44\n",
+            ies
+        );
+
+        let details = WpactrlBackend::parse_bss(&input).unwrap();
+
+        assert_eq!(details.bssid, "aa:bb:cc:dd:ee:ff");
+        assert_eq!(details.ssid, "Café");
+        assert_eq!(details.frequency, 2437);
+        assert_eq!(details.channel, 6);
+        assert_eq!(details.rssi, -48);
+        assert_eq!(details.noise, Some(-92));
+        assert_eq!(details.beacon_interval, 100);
+        assert_eq!(details.capabilities, 0x0431);
+        assert_eq!(
+            details.supported_rates,
+            vec![1000, 2000, 5500, 11000, 6000, 54000]
+        );
+        assert!(details.ht);
+        assert!(!details.vht);
+        assert!(details.he);
+        assert_eq!(details.wps, Some(WpsState::PushButton));
+        assert_eq!(details.flags, vec!["WPA2-PSK-CCMP", "WPS-PBC", "ESS"]);
+        assert_eq!(details.age_secs, 7);
+        assert_eq!(details.ies, ies);
+    }
+
+    #[test]
+    fn test_dpp_config_collector_passphrase() {
+        let mut collector = DppConfigCollector::default();
+        assert_eq!(collector.feed("<3>DPP-CONF-RECEIVED"), None);
+        assert_eq!(collector.feed("<3>DPP-CONFOBJ-AKM psk+sae"), None);
+        assert_eq!(collector.feed(r"<3>DPP-CONFOBJ-SSID Caf\xc3\xa9"), None);
+        assert_eq!(collector.feed("<3>DPP-CONFOBJ-SSID_CHARSET 0"), None);
+
+        let config = collector
+            .feed("<3>DPP-CONFOBJ-PASS 7365637265742d70617373")
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.ssid, "Café");
+        assert_eq!(config.passphrase, Some("secret-pass".to_string()));
+        assert_eq!(config.psk, None);
+    }
+
+    #[test]
+    fn test_dpp_config_collector_psk() {
+        let mut collector = DppConfigCollector::default();
+        collector.feed("<3>DPP-CONFOBJ-SSID Warehouse");
+
+        let psk = "ab".repeat(32);
+        let config = collector
+            .feed(&format!("<3>DPP-CONFOBJ-PSK {}", psk))
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.ssid, "Warehouse");
+        assert_eq!(config.psk, Some([0xab; 32]));
+    }
+
+    #[test]
+    fn test_dpp_config_collector_failures() {
+        let mut collector = DppConfigCollector::default();
+        assert!(collector.feed("<3>DPP-CONF-FAILED").unwrap().is_err());
+
+        // DPP connector-only configurations are not supported
+        let mut collector = DppConfigCollector::default();
+        assert!(collector.feed("<3>DPP-CONFOBJ-AKM dpp").unwrap().is_err());
+        let mut collector = DppConfigCollector::default();
+        assert!(collector.feed("<3>DPP-CONFOBJ-AKM sae").unwrap().is_err());
+        let mut collector = DppConfigCollector::default();
+        assert_eq!(collector.feed("<3>DPP-CONFOBJ-AKM dpp+psk+sae"), None);
+
+        let mut collector = DppConfigCollector::default();
+        assert!(collector.feed("<3>DPP-CONFOBJ-PASS 6162").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_parse_bss_unknown() {
+        assert!(WpactrlBackend::parse_bss("").is_none());
+        assert!(WpactrlBackend::parse_bss("FAIL\n").is_none());
+    }
+
+    #[test]
+    fn test_information_elements_truncated() {
+        // Second element claims 4 bytes but only 2 remain
+        let ies = [0x00, 0x01, b'a', 0x01, 0x04, 0x82, 0x84];
+        let elements: Vec<_> = WpactrlBackend::information_elements(&ies).collect();
+
+        assert_eq!(elements, vec![(0x00, &b"a"[..])]);
     }
 
     #[test]
@@ -444,6 +1888,20 @@ mod tests {
         assert_eq!(networks[0].rssi, 0); // Should default to 0 on parse error
     }
 
+    #[test]
+    fn test_parse_list_networks() {
+        let input = "network id / ssid / bssid / flags\n\
+                     0\tHome\tany\t[CURRENT]\n\
+                     1\tCaf\\xc3\\xa9\t02:00:00:00:00:01\t[DISABLED]\n\
+                     2\tHome\tany\t\n\
+                     3\t\tany\t[DISABLED]\n\
+                     malformed\n";
+
+        let ssids = WpactrlBackend::parse_list_networks(input);
+
+        assert_eq!(ssids, [Ssid::from("Home"), Ssid::from("Café")]);
+    }
+
     #[test]
     fn test_parse_scan_results_empty() {
         let input = "bssid / frequency / signal level / flags / ssid\n";
@@ -472,11 +1930,39 @@ mod tests {
         assert_eq!(WpactrlBackend::frequency_to_channel("5240"), 48);
         assert_eq!(WpactrlBackend::frequency_to_channel("5745"), 149);
         assert_eq!(WpactrlBackend::frequency_to_channel("5825"), 165);
+        assert_eq!(WpactrlBackend::frequency_to_channel("5600"), 120);
+        assert_eq!(WpactrlBackend::frequency_to_channel("5640"), 128);
+        assert_eq!(WpactrlBackend::frequency_to_channel("5720"), 144);
+        assert_eq!(WpactrlBackend::frequency_to_channel("5845"), 169);
+        assert_eq!(WpactrlBackend::frequency_to_channel("5885"), 177);
+    }
+
+    #[test]
+    fn test_frequency_to_channel_6ghz() {
+        assert_eq!(WpactrlBackend::frequency_to_channel("5935"), 2);
+        assert_eq!(WpactrlBackend::frequency_to_channel("5955"), 1);
+        assert_eq!(WpactrlBackend::frequency_to_channel("6115"), 33);
+        assert_eq!(WpactrlBackend::frequency_to_channel("7115"), 233);
+    }
+
+    #[test]
+    fn test_frequency_to_channel_every_band_channel() {
+        for band in [Band::Band2_4Ghz, Band::Band5Ghz, Band::Band6Ghz] {
+            for freq in band.frequencies() {
+                assert_ne!(
+                    WpactrlBackend::frequency_to_channel(&freq.to_string()),
+                    0,
+                    "{} MHz",
+                    freq
+                );
+            }
+        }
     }
 
     #[test]
     fn test_frequency_to_channel_unmapped() {
         assert_eq!(WpactrlBackend::frequency_to_channel("9999"), 0);
+        assert_eq!(WpactrlBackend::frequency_to_channel("2413"), 0);
         assert_eq!(WpactrlBackend::frequency_to_channel("invalid"), 0);
         assert_eq!(WpactrlBackend::frequency_to_channel(""), 0);
     }
@@ -508,7 +1994,7 @@ mod tests {
         let networks = WpactrlBackend::parse_scan_results(&input);
 
         assert_eq!(networks.len(), 1);
-        assert_eq!(networks[0].ssid, long_ssid);
+        assert_eq!(networks[0].ssid, long_ssid.as_str());
     }
 
     #[test]
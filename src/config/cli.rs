@@ -1,14 +1,82 @@
 //! Command-line argument parsing
 
-use clap::Parser;
+use std::{path::PathBuf, time::Duration};
+
+use clap::{Parser, ValueEnum};
+
+use crate::{backend::ConfigFormat, core::scan_policy::ScanPolicy};
+
+/// WiFi backend implementation
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    /// wpa_supplicant control interface
+    Wpactrl,
+    /// NetworkManager D-Bus API
+    #[value(name = "networkmanager")]
+    NetworkManager,
+    /// iwd D-Bus API
+    Iwd,
+    /// Offline configuration files, activated when the supplicant starts
+    #[value(name = "config-file")]
+    ConfigFile,
+    /// Simulated radio environment, no hardware required
+    Mock,
+}
+
+/// BLE secret used in demo mode unless one is given
+pub const DEMO_BLE_SECRET: &str = "demo";
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "wifi-commissioning-service", version, author)]
 #[command(about = "WiFi commissioning service with BLE and Unix socket support")]
 pub struct CliArgs {
-    /// Wireless network interface name
-    #[arg(short, long, default_value = "wlan0")]
-    pub interface: String,
+    /// Wireless network interface names (repeat or comma-separate to manage
+    /// several; the first is the default)
+    #[arg(
+        short,
+        long = "interface",
+        default_value = "wlan0",
+        value_delimiter = ','
+    )]
+    pub interfaces: Vec<String>,
+
+    /// WiFi backend to use
+    #[arg(short, long, value_enum, default_value = "wpactrl")]
+    pub backend: BackendKind,
+
+    /// Configuration format written by the config-file backend
+    #[arg(long, value_enum, default_value = "wpa_supplicant")]
+    pub config_format: ConfigFormat,
+
+    /// Configuration file or iwd profile directory for the config-file backend
+    /// [default: /etc/wpa_supplicant/wpa_supplicant-<interface>.conf or /var/lib/iwd]
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+
+    /// Demo mode: simulated networks instead of hardware (implies
+    /// --backend mock and --enable-unix-socket, BLE secret defaults to "demo")
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    pub demo: bool,
+
+    /// Rescan every SECS seconds while a client is connected (0 disables)
+    #[arg(long, value_name = "SECS", default_value = "0")]
+    pub background_scan: u64,
+
+    /// Minimum time between two scans; earlier requests get the cached results
+    #[arg(long, value_name = "SECS", default_value = "0")]
+    pub scan_min_interval: u64,
+
+    /// Scans each client session may start (0 for no limit)
+    #[arg(long, value_name = "COUNT", default_value = "0")]
+    pub scan_quota: u32,
+
+    /// Refuse scans while connected, as they interrupt traffic on the link
+    #[arg(long, default_value = "false", action = clap::ArgAction::SetTrue)]
+    pub defer_scan_while_connected: bool,
+
+    /// Saved `wpa_cli scan_results` output served by the config-file backend
+    #[arg(long)]
+    pub scan_cache: Option<PathBuf>,
 
     /// Secret shared between BLE client and server (device ID)
     #[arg(short = 's', long)]
@@ -30,3 +98,25 @@ pub struct CliArgs {
     #[arg(long, default_value = "660")]
     pub socket_mode: String,
 }
+
+impl CliArgs {
+    /// Apply the options implied by `--demo`
+    pub fn resolved(mut self) -> Self {
+        if self.demo {
+            self.backend = BackendKind::Mock;
+            self.enable_unix_socket = true;
+            self.ble_secret
+                .get_or_insert_with(|| DEMO_BLE_SECRET.to_string());
+        }
+        self
+    }
+
+    /// Scan policy selected by the `--scan-*` options
+    pub fn scan_policy(&self) -> ScanPolicy {
+        ScanPolicy {
+            min_interval: Duration::from_secs(self.scan_min_interval),
+            session_quota: (self.scan_quota > 0).then_some(self.scan_quota),
+            defer_while_connected: self.defer_scan_while_connected,
+        }
+    }
+}
@@ -3,5 +3,5 @@
 pub mod cli;
 pub mod settings;
 
-pub use cli::CliArgs;
+pub use cli::{BackendKind, CliArgs, DEMO_BLE_SECRET};
 pub use settings::Settings;
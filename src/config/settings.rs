@@ -1,11 +1,26 @@
 //! Runtime settings
 
-use crate::config::CliArgs;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    backend::ConfigFormat,
+    config::{BackendKind, CliArgs},
+    core::scan_policy::ScanPolicy,
+};
 
 /// Runtime configuration settings
 #[derive(Debug, Clone)]
 pub struct Settings {
-    pub interface: String,
+    pub interfaces: Vec<String>,
+    pub backend: BackendKind,
+    pub demo: bool,
+    pub config_format: ConfigFormat,
+    /// Explicit config-file location; each interface uses its default otherwise
+    pub config_path: Option<PathBuf>,
+    pub scan_cache: Option<PathBuf>,
+    /// Background scan interval, `None` if disabled
+    pub background_scan: Option<Duration>,
+    pub scan_policy: ScanPolicy,
     pub ble_secret: Option<String>,
     pub enable_ble: bool,
     pub enable_unix_socket: bool,
@@ -15,11 +30,23 @@ pub struct Settings {
 
 impl From<CliArgs> for Settings {
     fn from(args: CliArgs) -> Self {
+        let args = args.resolved();
+
         // Parse octal socket mode
         let socket_mode = u32::from_str_radix(&args.socket_mode, 8).unwrap_or(0o660);
 
+        let scan_policy = args.scan_policy();
+
         Settings {
-            interface: args.interface,
+            interfaces: args.interfaces,
+            backend: args.backend,
+            demo: args.demo,
+            config_format: args.config_format,
+            config_path: args.config_path,
+            scan_cache: args.scan_cache,
+            background_scan: (args.background_scan > 0)
+                .then(|| Duration::from_secs(args.background_scan)),
+            scan_policy,
             ble_secret: args.ble_secret,
             enable_ble: args.enable_ble,
             enable_unix_socket: args.enable_unix_socket,
@@ -1,23 +1,46 @@
 //! WiFi connection service with state machine
 
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, time::Duration};
+use tracing::debug;
 
 use crate::{
     backend::WifiBackend,
     core::{
         error::{ServiceError, ServiceResult},
-        types::{ConnectionState, ConnectionStatus},
+        types::{Capabilities, ConnectOptions, ConnectionState, ConnectionStatus, Ssid, WpsMethod},
     },
 };
 
+/// Interval at which the backend is polled while a connection is established
+pub const CONNECT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time association and DHCP may take before the attempt fails
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Interval at which an established connection is checked for link loss
+const LINK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Interval at which the backend is polled during WPS registration
+const WPS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// WPS walk time after which registration is abandoned
+const WPS_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Interval at which the backend is polled for a DPP configuration object
+const DPP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Connection state machine
 #[derive(Debug)]
 struct ConnectionStateMachine {
     state: ConnectionState,
-    ssid: Option<String>,
+    ssid: Option<Ssid>,
     ip_address: Option<String>,
     error: Option<String>,
+    /// Bootstrap URI of the current DPP session
+    dpp_uri: Option<String>,
+    /// Incremented on every new attempt so stale background tasks can bail out
+    attempt: u64,
 }
 
 impl ConnectionStateMachine {
@@ -27,23 +50,60 @@ impl ConnectionStateMachine {
             ssid: None,
             ip_address: None,
             error: None,
+            dpp_uri: None,
+            attempt: 0,
         }
     }
 
     /// Start connection attempt
-    fn start_connect(&mut self, ssid: String) -> ServiceResult<()> {
+    ///
+    /// Returns the attempt number the connection monitor is bound to.
+    fn start_connect(&mut self, ssid: Ssid) -> ServiceResult<u64> {
         match self.state {
-            ConnectionState::Idle | ConnectionState::Failed => {
+            ConnectionState::Idle | ConnectionState::Failed | ConnectionState::Stored => {
                 self.state = ConnectionState::Connecting;
                 self.ssid = Some(ssid);
                 self.ip_address = None;
                 self.error = None;
-                Ok(())
+                self.attempt += 1;
+                Ok(self.attempt)
+            }
+            _ => Err(ServiceError::OperationInProgress),
+        }
+    }
+
+    /// Start WPS or DPP registration, entering `state`
+    ///
+    /// Returns the attempt number the registration monitor is bound to.
+    fn start_registration(&mut self, state: ConnectionState) -> ServiceResult<u64> {
+        match self.state {
+            ConnectionState::Idle | ConnectionState::Failed | ConnectionState::Stored => {
+                self.state = state;
+                self.ssid = None;
+                self.ip_address = None;
+                self.error = None;
+                self.attempt += 1;
+                Ok(self.attempt)
             }
             _ => Err(ServiceError::OperationInProgress),
         }
     }
 
+    /// Credentials were received and association is in progress
+    fn credentials_received(&mut self, ssid: Option<Ssid>) {
+        self.state = ConnectionState::Connecting;
+        if ssid.is_some() {
+            self.ssid = ssid;
+        }
+    }
+
+    /// Cancel WPS or DPP registration
+    fn cancel_registration(&mut self, state: ConnectionState) {
+        if self.state == state {
+            self.disconnect();
+        }
+    }
+
     /// Mark connection as successful
     fn complete_connect(&mut self, ip_address: String) {
         self.state = ConnectionState::Connected;
@@ -51,6 +111,13 @@ impl ConnectionStateMachine {
         self.error = None;
     }
 
+    /// Credentials were stored by an offline backend, activation is pending
+    fn store_connect(&mut self) {
+        self.state = ConnectionState::Stored;
+        self.ip_address = None;
+        self.error = None;
+    }
+
     /// Mark connection as failed
     fn fail_connect(&mut self, error: String) {
         self.state = ConnectionState::Failed;
@@ -60,12 +127,20 @@ impl ConnectionStateMachine {
 
     /// Disconnect
     fn disconnect(&mut self) {
+        self.attempt += 1;
         self.state = ConnectionState::Idle;
         self.ssid = None;
         self.ip_address = None;
         self.error = None;
     }
 
+    /// Bootstrap URI, available while DPP is listening
+    fn dpp_uri(&self) -> Option<String> {
+        self.dpp_uri
+            .clone()
+            .filter(|_| self.state == ConnectionState::DppActive)
+    }
+
     fn state(&self) -> ConnectionState {
         self.state
     }
@@ -75,6 +150,7 @@ impl ConnectionStateMachine {
             state: self.state,
             ssid: self.ssid.clone(),
             ip_address: self.ip_address.clone(),
+            country: None,
         }
     }
 }
@@ -95,50 +171,133 @@ impl<B: WifiBackend> ConnectionService<B> {
     }
 
     /// Connect to a WiFi network
-    pub async fn connect(&self, ssid: &str, psk: &[u8; 32]) -> ServiceResult<()> {
+    ///
+    /// `options` must already be normalized (see `ConnectOptions::normalized`).
+    pub async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> ServiceResult<()> {
         // Check and update state
-        self.state_machine
+        let attempt = self
+            .state_machine
             .write()
             .await
-            .start_connect(ssid.to_string())?;
+            .start_connect(ssid.clone())?;
 
         // Perform connection in background
         let backend = self.backend.clone();
         let state_machine = self.state_machine.clone();
-        let ssid_owned = ssid.to_string();
+        let ssid_owned = ssid.clone();
         let psk_owned = *psk;
+        let options_owned = options.clone();
 
         tokio::spawn(async move {
-            match backend.connect(&ssid_owned, &psk_owned).await {
-                Ok(()) => {
-                    // Poll for IP address (in real implementation, this would come from backend)
-                    // For now, simulate getting IP from status
-                    tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-                    match backend.status().await {
-                        Ok(status) => {
-                            if let Some(ip) = status.ip_address {
-                                state_machine.write().await.complete_connect(ip);
-                            } else {
-                                state_machine
-                                    .write()
-                                    .await
-                                    .complete_connect("0.0.0.0".to_string());
-                            }
-                        }
-                        Err(e) => {
-                            state_machine.write().await.fail_connect(e.to_string());
-                        }
-                    }
-                }
-                Err(e) => {
-                    state_machine.write().await.fail_connect(e.to_string());
-                }
+            establish(
+                backend,
+                state_machine,
+                attempt,
+                ssid_owned,
+                psk_owned,
+                options_owned,
+            )
+            .await;
+        });
+
+        Ok(())
+    }
+
+    /// Start WPS registration
+    ///
+    /// Returns the PIN in use for the PIN method. The outcome is tracked in the
+    /// background and reflected in `status`; registration is cancelled after the
+    /// WPS walk time.
+    pub async fn start_wps(
+        &self,
+        method: &WpsMethod,
+        bssid: Option<&str>,
+    ) -> ServiceResult<Option<String>> {
+        let attempt = self
+            .state_machine
+            .write()
+            .await
+            .start_registration(ConnectionState::WpsActive)?;
+
+        let pin = match self.backend.start_wps(method, bssid).await {
+            Ok(pin) => pin,
+            Err(e) => {
+                self.state_machine.write().await.fail_connect(e.to_string());
+                return Err(e.into());
             }
+        };
+
+        let backend = self.backend.clone();
+        let state_machine = self.state_machine.clone();
+        tokio::spawn(async move {
+            monitor_wps(backend, state_machine, attempt).await;
         });
 
+        Ok(pin)
+    }
+
+    /// Cancel a running WPS registration
+    pub async fn cancel_wps(&self) -> ServiceResult<()> {
+        self.backend.cancel_wps().await?;
+        self.state_machine
+            .write()
+            .await
+            .cancel_registration(ConnectionState::WpsActive);
         Ok(())
     }
 
+    /// Start DPP enrollee, returning the bootstrap URI to show as QR code
+    ///
+    /// A configuration object received from a configurator is applied through
+    /// the regular connect flow. Listening continues until a configuration
+    /// arrives or `stop_dpp` is called.
+    pub async fn start_dpp(&self) -> ServiceResult<String> {
+        let attempt = self
+            .state_machine
+            .write()
+            .await
+            .start_registration(ConnectionState::DppActive)?;
+
+        let uri = match self.backend.start_dpp().await {
+            Ok(uri) => {
+                self.state_machine.write().await.dpp_uri = Some(uri.clone());
+                uri
+            }
+            Err(e) => {
+                self.state_machine.write().await.fail_connect(e.to_string());
+                return Err(e.into());
+            }
+        };
+
+        let backend = self.backend.clone();
+        let state_machine = self.state_machine.clone();
+        tokio::spawn(async move {
+            monitor_dpp(backend, state_machine, attempt).await;
+        });
+
+        Ok(uri)
+    }
+
+    /// Stop DPP enrollee
+    pub async fn stop_dpp(&self) -> ServiceResult<()> {
+        self.backend.stop_dpp().await?;
+        self.state_machine
+            .write()
+            .await
+            .cancel_registration(ConnectionState::DppActive);
+        Ok(())
+    }
+
+    /// Bootstrap URI of the running DPP session
+    pub async fn dpp_uri(&self) -> Option<String> {
+        self.state_machine.read().await.dpp_uri()
+    }
+
     /// Disconnect from current network
     pub async fn disconnect(&self) -> ServiceResult<()> {
         self.backend.disconnect().await?;
@@ -151,16 +310,285 @@ impl<B: WifiBackend> ConnectionService<B> {
         self.state_machine.read().await.state()
     }
 
-    /// Get current connection status
+    /// Get current connection status, including the active regulatory domain
     pub async fn status(&self) -> ConnectionStatus {
-        self.state_machine.read().await.status()
+        let mut status = self.state_machine.read().await.status();
+        status.country = self.backend.get_country().await.ok().flatten();
+        status
+    }
+
+    /// Get the features available through the backend
+    pub async fn capabilities(&self) -> ServiceResult<Capabilities> {
+        Ok(self.backend.capabilities().await?)
+    }
+
+    /// Get the active regulatory domain
+    pub async fn country(&self) -> ServiceResult<Option<String>> {
+        Ok(self.backend.get_country().await?)
+    }
+
+    /// Set the regulatory domain
+    ///
+    /// `country` must already be normalized (see `normalize_country_code`).
+    pub async fn set_country(&self, country: &str, persist: bool) -> ServiceResult<()> {
+        self.backend.set_country(country, persist).await?;
+        Ok(())
+    }
+}
+
+/// Connect with the given credentials and follow the backend until the
+/// connection is up, has failed or timed out
+///
+/// An established connection is watched afterwards, so a lost link is
+/// reflected in the state machine.
+async fn establish<B: WifiBackend>(
+    backend: Arc<B>,
+    state_machine: Arc<RwLock<ConnectionStateMachine>>,
+    attempt: u64,
+    ssid: Ssid,
+    psk: [u8; 32],
+    options: ConnectOptions,
+) {
+    if let Err(e) = backend.connect(&ssid, &psk, &options).await {
+        let mut sm = state_machine.write().await;
+        if sm.attempt == attempt {
+            sm.fail_connect(e.to_string());
+        }
+        return;
+    }
+
+    follow_connection(backend, state_machine, attempt).await;
+}
+
+/// Poll the backend until the connection started by `attempt` is up, has
+/// failed or timed out, then watch the link
+async fn follow_connection<B: WifiBackend>(
+    backend: Arc<B>,
+    state_machine: Arc<RwLock<ConnectionStateMachine>>,
+    attempt: u64,
+) {
+    let deadline = tokio::time::Instant::now() + CONNECT_TIMEOUT;
+    let mut seen_connecting = false;
+    loop {
+        tokio::time::sleep(CONNECT_POLL_INTERVAL).await;
+
+        let status = backend.status().await;
+        let mut sm = state_machine.write().await;
+        if sm.attempt != attempt {
+            // Disconnected or superseded by another operation
+            return;
+        }
+
+        match status {
+            Ok(status) => match (status.state, status.ip_address) {
+                (ConnectionState::Stored, _) => {
+                    sm.store_connect();
+                    return;
+                }
+                (ConnectionState::Connected, Some(ip)) => {
+                    // Registration may have reported credentials before the SSID
+                    if status.ssid.is_some() {
+                        sm.ssid = status.ssid;
+                    }
+                    sm.complete_connect(ip);
+                    break;
+                }
+                (ConnectionState::Failed, _) => {
+                    sm.fail_connect("Association failed".to_string());
+                    return;
+                }
+                // The backend gave up on the network
+                (ConnectionState::Idle, _) if seen_connecting => {
+                    sm.fail_connect("Association failed".to_string());
+                    return;
+                }
+                (ConnectionState::Connecting, _) => seen_connecting = true,
+                // Not started yet, or associated and waiting for DHCP
+                _ => {}
+            },
+            Err(e) => {
+                sm.fail_connect(e.to_string());
+                return;
+            }
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            drop(sm);
+            if let Err(e) = backend.disconnect().await {
+                debug!("Could not abandon timed out connection: {}", e);
+            }
+            let mut sm = state_machine.write().await;
+            if sm.attempt == attempt {
+                sm.fail_connect("Connection timed out".to_string());
+            }
+            return;
+        }
+    }
+
+    watch_link(backend, state_machine, attempt).await;
+}
+
+/// Follow an established connection until it is lost or superseded
+async fn watch_link<B: WifiBackend>(
+    backend: Arc<B>,
+    state_machine: Arc<RwLock<ConnectionStateMachine>>,
+    attempt: u64,
+) {
+    loop {
+        tokio::time::sleep(LINK_POLL_INTERVAL).await;
+
+        let status = backend.status().await;
+        let mut sm = state_machine.write().await;
+        if sm.attempt != attempt {
+            return;
+        }
+
+        match status {
+            Ok(status) => match (status.state, status.ip_address) {
+                (ConnectionState::Connected, Some(ip)) => sm.ip_address = Some(ip),
+                // Roaming or renewing the lease
+                (ConnectionState::Connected | ConnectionState::Connecting, _) => {}
+                _ => {
+                    debug!("Connection lost");
+                    sm.fail_connect("Connection lost".to_string());
+                    return;
+                }
+            },
+            Err(e) => debug!("Link check failed: {}", e),
+        }
+    }
+}
+
+/// Wait for a DPP configuration object and connect with it
+async fn monitor_dpp<B: WifiBackend>(
+    backend: Arc<B>,
+    state_machine: Arc<RwLock<ConnectionStateMachine>>,
+    attempt: u64,
+) {
+    let outcome = loop {
+        tokio::time::sleep(DPP_POLL_INTERVAL).await;
+
+        let config = backend.dpp_config().await;
+        if state_machine.read().await.attempt != attempt {
+            // Stopped or superseded by another operation
+            return;
+        }
+
+        match config {
+            Ok(Some(config)) => break Ok(config),
+            Ok(None) => {}
+            Err(e) => break Err(e),
+        }
+    };
+
+    // The configurator is done, end the enrollee session
+    if let Err(e) = backend.stop_dpp().await {
+        debug!("Could not end DPP session: {}", e);
+    }
+    let config = match outcome {
+        Ok(config) => config,
+        Err(e) => {
+            let mut sm = state_machine.write().await;
+            if sm.attempt == attempt {
+                sm.fail_connect(e.to_string());
+            }
+            return;
+        }
+    };
+
+    debug!("DPP configuration received for SSID: {}", config.ssid);
+    let mut sm = state_machine.write().await;
+    if sm.attempt != attempt {
+        // Stopped while the session was ending
+        return;
+    }
+    let Some(psk) = config.psk() else {
+        sm.fail_connect("DPP configuration without credentials".to_string());
+        return;
+    };
+    sm.credentials_received(Some(config.ssid.clone()));
+    drop(sm);
+    establish(
+        backend,
+        state_machine,
+        attempt,
+        config.ssid,
+        psk,
+        ConnectOptions::default(),
+    )
+    .await;
+}
+
+/// Follow WPS registration until the backend reports an outcome
+async fn monitor_wps<B: WifiBackend>(
+    backend: Arc<B>,
+    state_machine: Arc<RwLock<ConnectionStateMachine>>,
+    attempt: u64,
+) {
+    let deadline = tokio::time::Instant::now() + WPS_TIMEOUT;
+
+    loop {
+        tokio::time::sleep(WPS_POLL_INTERVAL).await;
+
+        // The EAP exchange associates too, so only the registration outcome
+        // tells that credentials were received
+        let result = backend.wps_result().await;
+        let mut sm = state_machine.write().await;
+        if sm.attempt != attempt {
+            // Cancelled or superseded by another operation
+            return;
+        }
+
+        match result {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => {
+                sm.fail_connect(e.to_string());
+                return;
+            }
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            drop(sm);
+            let _ = backend.cancel_wps().await;
+            let mut sm = state_machine.write().await;
+            if sm.attempt == attempt {
+                sm.fail_connect("WPS timed out".to_string());
+            }
+            return;
+        }
+    }
+
+    // Credentials received, the backend is connecting with them
+    let ssid = backend.status().await.ok().and_then(|status| status.ssid);
+    let mut sm = state_machine.write().await;
+    if sm.attempt != attempt {
+        return;
     }
+    sm.credentials_received(ssid);
+    drop(sm);
+
+    follow_connection(backend, state_machine, attempt).await;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::backend::MockWifiBackend;
+    use crate::{
+        backend::{MockWifiBackend, VirtualAp, mock_backend::DEMO_PASSPHRASE},
+        core::types::{DppConfig, passphrase_to_psk},
+    };
+
+    /// Wait until the service reaches `state`
+    async fn wait_for<B: WifiBackend>(service: &ConnectionService<B>, state: ConnectionState) {
+        let reached = tokio::time::timeout(tokio::time::Duration::from_secs(10), async {
+            while service.state().await != state {
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(reached.is_ok(), "{:?} not reached", state);
+    }
 
     #[tokio::test]
     async fn test_connection_state_machine_transitions() {
@@ -168,11 +596,11 @@ mod tests {
         assert_eq!(sm.state(), ConnectionState::Idle);
 
         // Start connection
-        sm.start_connect("TestNet".to_string()).unwrap();
+        sm.start_connect("TestNet".into()).unwrap();
         assert_eq!(sm.state(), ConnectionState::Connecting);
 
         // Cannot start another connection while connecting
-        assert!(sm.start_connect("OtherNet".to_string()).is_err());
+        assert!(sm.start_connect("OtherNet".into()).is_err());
 
         // Complete connection
         sm.complete_connect("192.168.1.100".to_string());
@@ -188,14 +616,14 @@ mod tests {
     #[tokio::test]
     async fn test_connection_state_machine_failure() {
         let mut sm = ConnectionStateMachine::new();
-        sm.start_connect("TestNet".to_string()).unwrap();
+        sm.start_connect("TestNet".into()).unwrap();
         sm.fail_connect("Connection timeout".to_string());
 
         assert_eq!(sm.state(), ConnectionState::Failed);
         assert_eq!(sm.status().ip_address, None);
 
         // Can retry after failure
-        sm.start_connect("TestNet".to_string()).unwrap();
+        sm.start_connect("TestNet".into()).unwrap();
         assert_eq!(sm.state(), ConnectionState::Connecting);
     }
 
@@ -205,16 +633,139 @@ mod tests {
         let service = ConnectionService::new(backend.clone());
 
         let psk = [0u8; 32];
-        service.connect("TestNet", &psk).await.unwrap();
+        service
+            .connect(&"TestNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
 
-        // Wait for connection to complete
-        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        // Connected once the backend reports an address
+        tokio::time::sleep(CONNECT_POLL_INTERVAL * 2).await;
+        assert_eq!(service.state().await, ConnectionState::Connecting);
         backend.complete_connection("192.168.1.100").await;
-        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        wait_for(&service, ConnectionState::Connected).await;
+
+        let status = service.status().await;
+        assert_eq!(status.ip_address, Some("192.168.1.100".into()));
+        assert_eq!(status.ssid, Some("TestNet".into()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connection_service_simulated() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .add_ap(VirtualAp::new(
+                "HomeNet",
+                "02:00:00:00:00:01",
+                2437,
+                "correct-passphrase",
+            ))
+            .await;
+        backend
+            .set_connect_latency(tokio::time::Duration::from_secs(3))
+            .await;
+        backend
+            .set_dhcp_delay(tokio::time::Duration::from_secs(1))
+            .await;
+        let service = ConnectionService::new(backend.clone());
+        let ssid = Ssid::from("HomeNet");
+        let options = ConnectOptions::default();
+
+        // Wrong passphrase fails once association is rejected
+        let wrong = passphrase_to_psk("wrong-passphrase", &ssid);
+        service.connect(&ssid, &wrong, &options).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        assert_eq!(service.state().await, ConnectionState::Connecting);
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        assert_eq!(service.state().await, ConnectionState::Failed);
+
+        // The right one connects after association and DHCP
+        let psk = passphrase_to_psk("correct-passphrase", &ssid);
+        service.connect(&ssid, &psk, &options).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(3500)).await;
+        let status = service.status().await;
+        assert_eq!(status.state, ConnectionState::Connecting);
+        assert_eq!(status.ip_address, None);
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        let status = service.status().await;
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ip_address, Some("192.168.1.100".into()));
+
+        // A dropped link is noticed
+        backend
+            .schedule_disconnect(tokio::time::Duration::from_secs(5))
+            .await;
+        tokio::time::sleep(tokio::time::Duration::from_secs(5) + LINK_POLL_INTERVAL).await;
+        assert_eq!(service.state().await, ConnectionState::Failed);
+    }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_connection_service_demo() {
+        let backend = Arc::new(MockWifiBackend::demo().await);
+        let service = ConnectionService::new(backend);
+        let ssid = Ssid::from("HomeNetwork");
+        let options = ConnectOptions::default();
+
+        let wrong = passphrase_to_psk("not-the-passphrase", &ssid);
+        service.connect(&ssid, &wrong, &options).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_secs(4)).await;
+        assert_eq!(service.state().await, ConnectionState::Failed);
+
+        // Association takes 3 s and DHCP another second
+        let psk = passphrase_to_psk(DEMO_PASSPHRASE, &ssid);
+        service.connect(&ssid, &psk, &options).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(3500)).await;
+        assert_eq!(service.state().await, ConnectionState::Connecting);
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         let status = service.status().await;
         assert_eq!(status.state, ConnectionState::Connected);
-        assert_eq!(status.ssid, Some("TestNet".to_string()));
+        assert_eq!(status.ssid, Some(ssid));
+        assert_eq!(status.ip_address, Some("192.168.1.100".into()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connection_service_timeout() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        // Without virtual APs the mock stays associating
+        service
+            .connect(&"TestNet".into(), &[0u8; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(CONNECT_TIMEOUT - tokio::time::Duration::from_secs(1)).await;
+        assert_eq!(service.state().await, ConnectionState::Connecting);
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        assert_eq!(service.state().await, ConnectionState::Failed);
+        assert_eq!(backend.status().await.unwrap().state, ConnectionState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_connection_service_stored() {
+        use crate::backend::{ConfigFileBackend, ConfigFormat};
+
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Arc::new(ConfigFileBackend::new(
+            ConfigFormat::Iwd,
+            dir.path().to_path_buf(),
+            None,
+        ));
+        let service = ConnectionService::new(backend);
+
+        service
+            .connect(&"TestNet".into(), &[0u8; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
+        wait_for(&service, ConnectionState::Stored).await;
+
+        let status = service.status().await;
+        assert_eq!(status.ssid, Some("TestNet".into()));
+        assert_eq!(status.ip_address, None);
+
+        // Storing another network is allowed
+        service
+            .connect(&"OtherNet".into(), &[0u8; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
     }
 
     #[tokio::test]
@@ -224,13 +775,30 @@ mod tests {
 
         let service = ConnectionService::new(backend);
         let psk = [0u8; 32];
-        service.connect("TestNet", &psk).await.unwrap();
+        service
+            .connect(&"TestNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
 
-        // Wait for connection to fail
-        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        wait_for(&service, ConnectionState::Failed).await;
+    }
 
-        let state = service.state().await;
-        assert_eq!(state, ConnectionState::Failed);
+    #[tokio::test(start_paused = true)]
+    async fn test_connection_service_backend_gives_up() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        // Without virtual APs the mock stays associating until it gives up
+        service
+            .connect(&"TestNet".into(), &[0u8; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(CONNECT_POLL_INTERVAL * 2).await;
+        assert_eq!(service.state().await, ConnectionState::Connecting);
+
+        backend.disconnect().await.unwrap();
+        tokio::time::sleep(CONNECT_POLL_INTERVAL * 2).await;
+        assert_eq!(service.state().await, ConnectionState::Failed);
     }
 
     #[tokio::test]
@@ -239,10 +807,13 @@ mod tests {
         let service = ConnectionService::new(backend.clone());
 
         let psk = [0u8; 32];
-        service.connect("TestNet", &psk).await.unwrap();
-        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        service
+            .connect(&"TestNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
+        tokio::time::sleep(CONNECT_POLL_INTERVAL).await;
         backend.complete_connection("192.168.1.100").await;
-        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        wait_for(&service, ConnectionState::Connected).await;
 
         // Disconnect
         service.disconnect().await.unwrap();
@@ -258,9 +829,239 @@ mod tests {
         let service = ConnectionService::new(backend);
 
         let psk = [0u8; 32];
-        service.connect("TestNet", &psk).await.unwrap();
+        service
+            .connect(&"TestNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
 
         // Try to connect again
-        assert!(service.connect("OtherNet", &psk).await.is_err());
+        assert!(
+            service
+                .connect(&"OtherNet".into(), &psk, &ConnectOptions::default())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wps_pbc_success() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        let pin = service
+            .start_wps(&WpsMethod::PushButton, None)
+            .await
+            .unwrap();
+        assert_eq!(pin, None);
+        assert_eq!(service.state().await, ConnectionState::WpsActive);
+
+        // Another operation cannot start while WPS is active
+        assert!(
+            service
+                .connect(&"OtherNet".into(), &[0u8; 32], &ConnectOptions::default())
+                .await
+                .is_err()
+        );
+
+        backend.complete_wps("WpsNet", "192.168.1.100").await;
+        tokio::time::sleep(WPS_POLL_INTERVAL * 2).await;
+
+        let status = service.status().await;
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some("WpsNet".into()));
+        assert_eq!(status.ip_address, Some("192.168.1.100".to_string()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wps_waits_for_address() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        service
+            .start_wps(&WpsMethod::PushButton, None)
+            .await
+            .unwrap();
+        backend.receive_wps_credentials("WpsNet").await;
+        tokio::time::sleep(WPS_POLL_INTERVAL * 2).await;
+
+        let status = service.status().await;
+        assert_eq!(status.state, ConnectionState::Connecting);
+        assert_eq!(status.ssid, Some("WpsNet".into()));
+        assert_eq!(status.ip_address, None);
+
+        backend.complete_connection("192.168.1.100").await;
+        tokio::time::sleep(CONNECT_POLL_INTERVAL * 2).await;
+        let status = service.status().await;
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ip_address, Some("192.168.1.100".to_string()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wps_failure() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        service
+            .start_wps(&WpsMethod::PushButton, None)
+            .await
+            .unwrap();
+        backend.fail_wps().await;
+        tokio::time::sleep(WPS_POLL_INTERVAL * 2).await;
+
+        assert_eq!(service.state().await, ConnectionState::Failed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wps_ignores_other_association() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        service
+            .start_wps(&WpsMethod::PushButton, None)
+            .await
+            .unwrap();
+        // Associated while registration is running, without credentials
+        backend.complete_connection("192.168.1.100").await;
+        tokio::time::sleep(WPS_POLL_INTERVAL * 2).await;
+        assert_eq!(service.state().await, ConnectionState::WpsActive);
+
+        backend.complete_wps("WpsNet", "192.168.1.101").await;
+        tokio::time::sleep(WPS_POLL_INTERVAL + CONNECT_POLL_INTERVAL * 2).await;
+        let status = service.status().await;
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some("WpsNet".into()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wps_pin_generated() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend);
+
+        let pin = service
+            .start_wps(&WpsMethod::Pin(None), None)
+            .await
+            .unwrap();
+        assert_eq!(pin, Some("12345670".to_string()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wps_timeout() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        service
+            .start_wps(&WpsMethod::PushButton, None)
+            .await
+            .unwrap();
+        tokio::time::sleep(WPS_TIMEOUT + WPS_POLL_INTERVAL * 2).await;
+
+        let state = service.state().await;
+        assert_eq!(state, ConnectionState::Failed);
+        assert_eq!(backend.wps_method().await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wps_cancel() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        service
+            .start_wps(&WpsMethod::PushButton, None)
+            .await
+            .unwrap();
+        service.cancel_wps().await.unwrap();
+        assert_eq!(service.state().await, ConnectionState::Idle);
+
+        // A late success report must not be picked up by the cancelled monitor
+        backend.complete_wps("WpsNet", "192.168.1.100").await;
+        tokio::time::sleep(WPS_POLL_INTERVAL * 2).await;
+        assert_eq!(service.state().await, ConnectionState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_wps_backend_failure() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend.set_connect_failure(true).await;
+        let service = ConnectionService::new(backend);
+
+        assert!(
+            service
+                .start_wps(&WpsMethod::PushButton, None)
+                .await
+                .is_err()
+        );
+        assert_eq!(service.state().await, ConnectionState::Failed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dpp_enrollee() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        backend
+            .add_ap(VirtualAp::new(
+                "DppNet",
+                "02:00:00:00:00:01",
+                2437,
+                "secret-passphrase",
+            ))
+            .await;
+
+        let uri = service.start_dpp().await.unwrap();
+        assert!(uri.starts_with("DPP:"));
+        assert_eq!(service.state().await, ConnectionState::DppActive);
+        assert_eq!(service.dpp_uri().await, Some(uri));
+
+        // Still listening without a configurator
+        tokio::time::sleep(DPP_POLL_INTERVAL * 4).await;
+        assert_eq!(service.state().await, ConnectionState::DppActive);
+
+        backend
+            .deliver_dpp_config(DppConfig {
+                ssid: "DppNet".into(),
+                passphrase: Some("secret-passphrase".to_string()),
+                psk: None,
+            })
+            .await;
+        tokio::time::sleep(DPP_POLL_INTERVAL + CONNECT_POLL_INTERVAL * 2).await;
+
+        // The received configuration goes through the normal connect flow
+        let status = service.status().await;
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.ssid, Some("DppNet".into()));
+        assert!(backend.last_connect_options().await.is_some());
+        assert_eq!(service.dpp_uri().await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dpp_stop() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        service.start_dpp().await.unwrap();
+        service.stop_dpp().await.unwrap();
+        assert_eq!(service.state().await, ConnectionState::Idle);
+        assert!(!backend.dpp_listening().await);
+
+        // WPS cancel does not affect a DPP session and vice versa
+        service.start_dpp().await.unwrap();
+        service.cancel_wps().await.unwrap();
+        assert_eq!(service.state().await, ConnectionState::DppActive);
+    }
+
+    #[tokio::test]
+    async fn test_country_in_status() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ConnectionService::new(backend.clone());
+
+        assert_eq!(service.country().await.unwrap(), None);
+        assert_eq!(service.status().await.country, None);
+
+        service.set_country("DE", true).await.unwrap();
+        assert_eq!(service.country().await.unwrap(), Some("DE".to_string()));
+        assert_eq!(service.status().await.country, Some("DE".to_string()));
+        // The backend leaves the country to the service
+        assert_eq!(backend.status().await.unwrap().country, None);
+        assert!(backend.country_persisted().await);
     }
 }
@@ -2,7 +2,7 @@
 
 use thiserror::Error;
 
-use crate::core::types::ScanState;
+use crate::core::{scan_policy::ScanRefusal, types::ScanState};
 
 /// Result type for WiFi backend operations
 pub type WifiResult<T> = Result<T, WifiError>;
@@ -22,9 +22,21 @@ pub enum WifiError {
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
 
+    #[error("WPS failed: {0}")]
+    WpsFailed(String),
+
+    #[error("DPP failed: {0}")]
+    DppFailed(String),
+
+    #[error("Regulatory domain error: {0}")]
+    RegulatoryError(String),
+
     #[error("Backend unavailable: {0}")]
     BackendUnavailable(String),
 
+    #[error("BSS not found: {0}")]
+    BssNotFound(String),
+
     #[error("Invalid SSID: {0}")]
     InvalidSsid(String),
 
@@ -36,6 +48,15 @@ pub enum WifiError {
 
     #[error("wpa_supplicant error: {0}")]
     WpaSupplicantError(String),
+
+    #[error("NetworkManager error: {0}")]
+    NetworkManagerError(String),
+
+    #[error("iwd error: {0}")]
+    IwdError(String),
+
+    #[error("Not supported by this backend: {0}")]
+    NotSupported(String),
 }
 
 /// Errors related to core service operations
@@ -50,6 +71,9 @@ pub enum ServiceError {
     #[error("Operation already in progress")]
     OperationInProgress,
 
+    #[error("Scan refused: {0}")]
+    ScanRefused(#[from] ScanRefusal),
+
     #[error("No scan results available")]
     NoScanResults,
 
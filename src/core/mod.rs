@@ -3,6 +3,14 @@
 pub mod authorization;
 pub mod connector;
 pub mod error;
+pub mod netif;
+pub mod ranking;
+pub mod scan_policy;
+pub mod scan_view;
 pub mod scanner;
 pub mod service;
+pub mod survey;
 pub mod types;
+
+#[cfg(test)]
+mod test_support;
@@ -0,0 +1,370 @@
+//! Network interface enumeration via rtnetlink
+//!
+//! Reports every interface of the device, not only the managed wireless ones,
+//! so clients can tell whether it is already online via Ethernet or a modem.
+
+use std::{
+    collections::HashSet,
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use futures::TryStreamExt;
+use netlink_packet_route::{
+    ARPHRD_ETHER, ARPHRD_LOOPBACK, ARPHRD_NONE, ARPHRD_PPP, ARPHRD_RAWIP, AddressMessage,
+    LinkMessage, RT_TABLE_MAIN, RTN_UNICAST, RouteMessage,
+    nlas::{
+        address::Nla as AddressNla,
+        link::{Info, InfoKind, Nla as LinkNla, State},
+        route::Nla as RouteNla,
+    },
+};
+use rtnetlink::IpVersion;
+
+use crate::core::{
+    error::{ServiceResult, WifiError},
+    types::{InterfaceKind, NetworkInterface, OperState},
+};
+
+/// List all network interfaces with their addresses and default routes
+pub async fn list_interfaces() -> ServiceResult<Vec<NetworkInterface>> {
+    let (connection, handle, _) = rtnetlink::new_connection().map_err(netlink_error)?;
+    let connection = tokio::spawn(connection);
+
+    let result = async {
+        let links: Vec<LinkMessage> = handle.link().get().execute().try_collect().await?;
+        let addresses: Vec<AddressMessage> = handle.address().get().execute().try_collect().await?;
+        let mut routes: Vec<RouteMessage> = handle
+            .route()
+            .get(IpVersion::V4)
+            .execute()
+            .try_collect()
+            .await?;
+        routes.extend(
+            handle
+                .route()
+                .get(IpVersion::V6)
+                .execute()
+                .try_collect::<Vec<_>>()
+                .await?,
+        );
+        Ok::<_, rtnetlink::Error>((links, addresses, routes))
+    }
+    .await;
+    connection.abort();
+
+    let (links, addresses, routes) = result.map_err(netlink_error)?;
+    Ok(collect_interfaces(
+        &links,
+        &addresses,
+        &routes,
+        sysfs_devtype,
+    ))
+}
+
+fn netlink_error(e: impl std::fmt::Display) -> WifiError {
+    WifiError::InterfaceError(format!("netlink: {}", e))
+}
+
+/// `DEVTYPE` from the interface's uevent, distinguishing wlan and wwan devices
+fn sysfs_devtype(name: &str) -> Option<String> {
+    let uevent = fs::read_to_string(format!("/sys/class/net/{}/uevent", name)).ok()?;
+    uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("DEVTYPE="))
+        .map(str::to_string)
+}
+
+/// Combine link, address and route dumps into interface descriptions
+fn collect_interfaces(
+    links: &[LinkMessage],
+    addresses: &[AddressMessage],
+    routes: &[RouteMessage],
+    devtype: impl Fn(&str) -> Option<String>,
+) -> Vec<NetworkInterface> {
+    let default_routes: HashSet<u32> = routes.iter().filter_map(default_route_oif).collect();
+
+    links
+        .iter()
+        .filter_map(|link| {
+            let index = link.header.index;
+            let name = link.nlas.iter().find_map(|nla| match nla {
+                LinkNla::IfName(name) => Some(name.clone()),
+                _ => None,
+            })?;
+
+            let mac = link.nlas.iter().find_map(|nla| match nla {
+                LinkNla::Address(mac) => format_mac(mac),
+                _ => None,
+            });
+            let state = link
+                .nlas
+                .iter()
+                .find_map(|nla| match nla {
+                    LinkNla::OperState(state) => Some(oper_state(state)),
+                    _ => None,
+                })
+                .unwrap_or(OperState::Unknown);
+            let info_kind = link.nlas.iter().find_map(|nla| match nla {
+                LinkNla::Info(infos) => infos.iter().find_map(|info| match info {
+                    Info::Kind(kind) => Some(kind.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            });
+            let kind = interface_kind(
+                link.header.link_layer_type,
+                info_kind.as_ref(),
+                devtype(&name).as_deref(),
+            );
+
+            let addresses = addresses
+                .iter()
+                .filter(|address| address.header.index == index)
+                .filter_map(format_address)
+                .collect();
+
+            Some(NetworkInterface {
+                name,
+                kind,
+                mac,
+                state,
+                addresses,
+                default_route: default_routes.contains(&index),
+            })
+        })
+        .collect()
+}
+
+/// Output interface of a default unicast route in the main table
+fn default_route_oif(route: &RouteMessage) -> Option<u32> {
+    let table = route
+        .nlas
+        .iter()
+        .find_map(|nla| match nla {
+            RouteNla::Table(table) => Some(*table),
+            _ => None,
+        })
+        .unwrap_or(route.header.table.into());
+
+    if route.header.destination_prefix_length != 0
+        || route.header.kind != RTN_UNICAST
+        || table != u32::from(RT_TABLE_MAIN)
+    {
+        return None;
+    }
+
+    route.nlas.iter().find_map(|nla| match nla {
+        RouteNla::Oif(index) => Some(*index),
+        _ => None,
+    })
+}
+
+fn interface_kind(
+    link_layer_type: u16,
+    info_kind: Option<&InfoKind>,
+    devtype: Option<&str>,
+) -> InterfaceKind {
+    match (devtype, info_kind) {
+        (Some("wlan"), _) => return InterfaceKind::Wifi,
+        (Some("wwan"), _) => return InterfaceKind::Cellular,
+        (Some("bridge"), _) | (_, Some(InfoKind::Bridge)) => return InterfaceKind::Bridge,
+        (_, Some(_)) => return InterfaceKind::Virtual,
+        _ => {}
+    }
+
+    match link_layer_type {
+        ARPHRD_LOOPBACK => InterfaceKind::Loopback,
+        ARPHRD_ETHER => InterfaceKind::Ethernet,
+        ARPHRD_PPP | ARPHRD_RAWIP | ARPHRD_NONE => InterfaceKind::Cellular,
+        _ => InterfaceKind::Other,
+    }
+}
+
+fn oper_state(state: &State) -> OperState {
+    match state {
+        State::Up => OperState::Up,
+        State::Dormant => OperState::Dormant,
+        State::Down | State::LowerLayerDown | State::NotPresent => OperState::Down,
+        _ => OperState::Unknown,
+    }
+}
+
+/// Format a 6-byte hardware address, ignoring the all-zero loopback address
+fn format_mac(bytes: &[u8]) -> Option<String> {
+    if bytes.len() != 6 || bytes.iter().all(|b| *b == 0) {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// Format an address in CIDR notation, preferring the local address of
+/// point-to-point links over the peer address
+fn format_address(message: &AddressMessage) -> Option<String> {
+    let bytes = message
+        .nlas
+        .iter()
+        .find_map(|nla| match nla {
+            AddressNla::Local(bytes) => Some(bytes),
+            _ => None,
+        })
+        .or_else(|| {
+            message.nlas.iter().find_map(|nla| match nla {
+                AddressNla::Address(bytes) => Some(bytes),
+                _ => None,
+            })
+        })?;
+
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes.as_slice()).ok()?)),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes.as_slice()).ok()?)),
+        _ => return None,
+    };
+    Some(format!("{}/{}", ip, message.header.prefix_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netlink_packet_route::{AF_INET, AF_INET6};
+
+    fn link(index: u32, name: &str, link_layer_type: u16, nlas: Vec<LinkNla>) -> LinkMessage {
+        let mut message = LinkMessage::default();
+        message.header.index = index;
+        message.header.link_layer_type = link_layer_type;
+        message.nlas = vec![LinkNla::IfName(name.to_string())];
+        message.nlas.extend(nlas);
+        message
+    }
+
+    fn address(index: u32, family: u16, prefix_len: u8, ip: IpAddr) -> AddressMessage {
+        let bytes = match ip {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        let mut message = AddressMessage::default();
+        message.header.family = family as u8;
+        message.header.prefix_len = prefix_len;
+        message.header.index = index;
+        message.nlas = vec![AddressNla::Address(bytes)];
+        message
+    }
+
+    fn route(oif: u32, prefix_len: u8, table: u8) -> RouteMessage {
+        let mut message = RouteMessage::default();
+        message.header.destination_prefix_length = prefix_len;
+        message.header.table = table;
+        message.header.kind = RTN_UNICAST;
+        message.nlas = vec![RouteNla::Oif(oif)];
+        message
+    }
+
+    #[test]
+    fn test_collect_interfaces() {
+        let links = vec![
+            link(
+                1,
+                "lo",
+                ARPHRD_LOOPBACK,
+                vec![
+                    LinkNla::Address(vec![0; 6]),
+                    LinkNla::OperState(State::Unknown),
+                ],
+            ),
+            link(
+                2,
+                "eth0",
+                ARPHRD_ETHER,
+                vec![
+                    LinkNla::Address(vec![0x02, 0xfc, 0, 0, 0, 1]),
+                    LinkNla::OperState(State::LowerLayerDown),
+                ],
+            ),
+            link(
+                3,
+                "wlan0",
+                ARPHRD_ETHER,
+                vec![
+                    LinkNla::Address(vec![0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]),
+                    LinkNla::OperState(State::Up),
+                ],
+            ),
+            link(4, "wwan0", ARPHRD_NONE, vec![]),
+            link(
+                5,
+                "br0",
+                ARPHRD_ETHER,
+                vec![LinkNla::Info(vec![Info::Kind(InfoKind::Bridge)])],
+            ),
+        ];
+        let addresses = vec![
+            address(1, AF_INET, 8, Ipv4Addr::LOCALHOST.into()),
+            address(3, AF_INET, 24, Ipv4Addr::new(192, 168, 1, 5).into()),
+            address(3, AF_INET6, 64, "fe80::1".parse().unwrap()),
+        ];
+        let routes = vec![
+            route(3, 0, RT_TABLE_MAIN),
+            route(3, 24, RT_TABLE_MAIN),
+            route(2, 0, 100),
+        ];
+        let devtype = |name: &str| (name == "wlan0").then(|| "wlan".to_string());
+
+        let interfaces = collect_interfaces(&links, &addresses, &routes, devtype);
+        let kinds: Vec<_> = interfaces
+            .iter()
+            .map(|i| (i.name.as_str(), i.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("lo", InterfaceKind::Loopback),
+                ("eth0", InterfaceKind::Ethernet),
+                ("wlan0", InterfaceKind::Wifi),
+                ("wwan0", InterfaceKind::Cellular),
+                ("br0", InterfaceKind::Bridge),
+            ]
+        );
+
+        assert_eq!(interfaces[0].mac, None);
+        assert_eq!(interfaces[0].addresses, ["127.0.0.1/8"]);
+        assert_eq!(interfaces[1].state, OperState::Down);
+        // Default routes of other tables don't count
+        assert!(!interfaces[1].default_route);
+
+        let wlan0 = &interfaces[2];
+        assert_eq!(wlan0.mac.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(wlan0.state, OperState::Up);
+        assert_eq!(wlan0.addresses, ["192.168.1.5/24", "fe80::1/64"]);
+        assert!(wlan0.default_route);
+        assert!(wlan0.is_online());
+        assert_eq!(
+            wlan0.compact(),
+            "wlan0 wifi up aa:bb:cc:dd:ee:ff default 192.168.1.5/24,fe80::1/64"
+        );
+        assert_eq!(interfaces[3].compact(), "wwan0 cellular unknown - - -");
+    }
+
+    #[test]
+    fn test_point_to_point_address() {
+        let mut message = address(4, AF_INET, 32, Ipv4Addr::new(10, 0, 0, 1).into());
+        message.nlas.push(AddressNla::Local(
+            Ipv4Addr::new(10, 64, 3, 7).octets().to_vec(),
+        ));
+        assert_eq!(format_address(&message).as_deref(), Some("10.64.3.7/32"));
+    }
+
+    #[tokio::test]
+    async fn test_list_interfaces() {
+        // Every Linux network namespace has a loopback interface
+        let interfaces = list_interfaces().await.unwrap();
+        let lo = interfaces.iter().find(|i| i.name == "lo").unwrap();
+        assert_eq!(lo.kind, InterfaceKind::Loopback);
+        assert!(lo.addresses.iter().any(|a| a == "127.0.0.1/8"));
+    }
+}
@@ -0,0 +1,219 @@
+//! Network recommendation ranking
+//!
+//! Installers tend to pick the first entry carrying the right name, which is
+//! often a weak 2.4 GHz BSS of a multi-band network. Every BSS gets a score
+//! from its signal, band, security and channel congestion, and the best BSS
+//! of each SSID is recommended.
+
+use crate::core::types::{Band, Rank, Security, WifiNetwork};
+
+/// Points for the signal, scaled linearly from -90 dBm (none) to -50 dBm
+const MAX_SIGNAL_POINTS: i32 = 50;
+
+/// Points for a clear channel, two are lost per overlapping BSS
+const MAX_CHANNEL_POINTS: u8 = 10;
+
+/// Check whether two BSSes interfere, given their frequencies in MHz
+///
+/// 2.4 GHz channels are 22 MHz wide and overlap unless they are at least
+/// five channels apart; on the other bands only the same channel counts.
+pub fn channels_overlap(a: u32, b: u32) -> bool {
+    match (Band::from_frequency(a), Band::from_frequency(b)) {
+        (Some(Band::Band2_4Ghz), Some(Band::Band2_4Ghz)) => a.abs_diff(b) < 25,
+        (Some(_), Some(_)) => a == b,
+        _ => false,
+    }
+}
+
+/// Score all BSSes and recommend the best one of each SSID
+///
+/// Hidden BSSes are scored but never recommended.
+pub fn rank(networks: &mut [WifiNetwork]) {
+    let ranks: Vec<Rank> = networks
+        .iter()
+        .enumerate()
+        .map(|(i, network)| {
+            let neighbours = networks
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| {
+                    *j != i && channels_overlap(network.frequency, other.frequency)
+                })
+                .count();
+            score(network, neighbours)
+        })
+        .collect();
+
+    for (network, rank) in networks.iter_mut().zip(ranks) {
+        network.rank = Some(rank);
+    }
+
+    // Best BSS per SSID, the stronger one on equal scores
+    let mut best: Vec<usize> = Vec::new();
+    for i in 0..networks.len() {
+        if networks[i].ssid.is_hidden() {
+            continue;
+        }
+        match best
+            .iter_mut()
+            .find(|b| networks[**b].ssid == networks[i].ssid)
+        {
+            Some(b) => {
+                let key = |n: &WifiNetwork| (n.rank.as_ref().map_or(0, |r| r.score), n.rssi);
+                if key(&networks[i]) > key(&networks[*b]) {
+                    *b = i;
+                }
+            }
+            None => best.push(i),
+        }
+    }
+
+    for i in 0..networks.len() {
+        let better = best
+            .iter()
+            .find(|b| networks[**b].ssid == networks[i].ssid)
+            .filter(|b| **b != i && !networks[i].ssid.is_hidden())
+            .map(|b| networks[*b].mac.clone());
+        let recommended = best.contains(&i);
+        if let Some(rank) = networks[i].rank.as_mut() {
+            rank.recommended = recommended;
+            if let Some(mac) = better {
+                rank.reason.push_str(&format!("; {} is better", mac));
+            }
+        }
+    }
+}
+
+/// Score a BSS that shares its channel with `neighbours` other BSSes
+fn score(network: &WifiNetwork, neighbours: usize) -> Rank {
+    let mut reasons = Vec::new();
+
+    let signal =
+        ((i32::from(network.rssi) + 90) * MAX_SIGNAL_POINTS / 40).clamp(0, MAX_SIGNAL_POINTS) as u8;
+    let quality = match network.rssi {
+        -55.. => "Excellent",
+        -67.. => "Good",
+        -75.. => "Fair",
+        _ => "Weak",
+    };
+    reasons.push(format!("{} signal ({} dBm)", quality, network.rssi));
+
+    let band = match Band::from_frequency(network.frequency) {
+        Some(Band::Band2_4Ghz) => {
+            reasons.push("2.4 GHz".to_string());
+            5
+        }
+        Some(Band::Band5Ghz) => {
+            reasons.push("5 GHz".to_string());
+            15
+        }
+        Some(Band::Band6Ghz) => {
+            reasons.push("6 GHz".to_string());
+            20
+        }
+        None => 10,
+    };
+
+    let (security, label) = match network.security {
+        Security::Open => (0, "unencrypted"),
+        Security::Wep => (0, "insecure WEP"),
+        Security::Owe => (8, "OWE"),
+        Security::Wpa => (8, "WPA"),
+        Security::Wpa2 => (15, "WPA2"),
+        Security::Enterprise => (15, "enterprise"),
+        Security::Wpa3 => (20, "WPA3"),
+    };
+    reasons.push(label.to_string());
+
+    let channel = if network.frequency == 0 {
+        MAX_CHANNEL_POINTS / 2
+    } else {
+        match neighbours {
+            0 => reasons.push("clear channel".to_string()),
+            1 => reasons.push(format!(
+                "channel {} shared with 1 other network",
+                network.channel
+            )),
+            n => reasons.push(format!(
+                "channel {} shared with {} other networks",
+                network.channel, n
+            )),
+        }
+        MAX_CHANNEL_POINTS.saturating_sub(neighbours.min(5) as u8 * 2)
+    };
+
+    Rank {
+        score: signal + band + security + channel,
+        recommended: false,
+        reason: reasons.join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::bss;
+
+    #[test]
+    fn test_channels_overlap() {
+        assert!(channels_overlap(2412, 2412));
+        assert!(channels_overlap(2412, 2432));
+        assert!(!channels_overlap(2412, 2437));
+        assert!(channels_overlap(5180, 5180));
+        assert!(!channels_overlap(5180, 5200));
+        assert!(!channels_overlap(2472, 5180));
+        assert!(!channels_overlap(0, 0));
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut networks = vec![
+            bss("Brand", "aa:00:00:00:00:01", 2437, -60, Security::Wpa2),
+            bss("Neighbour", "aa:00:00:00:00:02", 2437, -70, Security::Wpa2),
+            bss("Brand", "aa:00:00:00:00:03", 5180, -62, Security::Wpa2),
+            bss("Cafe", "aa:00:00:00:00:04", 2462, -50, Security::Open),
+            bss("", "aa:00:00:00:00:05", 5745, -40, Security::Wpa3),
+        ];
+        rank(&mut networks);
+        let rank = |i: usize| networks[i].rank.clone().unwrap();
+
+        // The 5 GHz BSS wins despite the slightly weaker signal
+        assert_eq!(rank(2).score, 35 + 15 + 15 + 10);
+        assert!(rank(2).recommended);
+        assert_eq!(
+            rank(2).reason,
+            "Good signal (-62 dBm), 5 GHz, WPA2, clear channel"
+        );
+
+        assert_eq!(rank(0).score, 37 + 5 + 15 + 8);
+        assert!(!rank(0).recommended);
+        assert_eq!(
+            rank(0).reason,
+            "Good signal (-60 dBm), 2.4 GHz, WPA2, channel 6 shared with 1 other network; \
+             aa:00:00:00:00:03 is better"
+        );
+
+        // The only BSS of an SSID is its best one
+        assert!(rank(1).recommended);
+        assert!(rank(3).recommended);
+        assert_eq!(
+            rank(3).reason,
+            "Excellent signal (-50 dBm), 2.4 GHz, unencrypted, clear channel"
+        );
+
+        assert!(!rank(4).recommended);
+        assert_eq!(rank(4).score, 50 + 15 + 20 + 10);
+    }
+
+    #[test]
+    fn test_rank_without_frequency() {
+        let mut networks = vec![bss("Net", "aa:00:00:00:00:01", 0, -95, Security::Wep)];
+        networks[0].channel = 0;
+        rank(&mut networks);
+
+        let rank = networks[0].rank.as_ref().unwrap();
+        assert_eq!(rank.score, 10 + 5);
+        assert_eq!(rank.reason, "Weak signal (-95 dBm), insecure WEP");
+        assert!(rank.recommended);
+    }
+}
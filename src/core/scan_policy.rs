@@ -0,0 +1,165 @@
+//! Scan rate limiting
+//!
+//! Every scan takes the radio off channel for a while, which interrupts
+//! traffic on an established link. The policy keeps clients from scanning
+//! back to back.
+
+use std::{collections::HashMap, time::Duration};
+
+use serde::Serialize;
+use thiserror::Error;
+use tokio::time::Instant;
+
+use crate::core::types::SessionId;
+
+/// Limits applied to new scans
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanPolicy {
+    /// Minimum time between the start of two scans
+    pub min_interval: Duration,
+    /// Scans a client session may start, `None` for no limit
+    pub session_quota: Option<u32>,
+    /// Refuse scans while the interface is connected
+    pub defer_while_connected: bool,
+}
+
+/// Why a scan was not started
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ScanRefusal {
+    #[error("Minimum scan interval not reached, retry in {retry_after_ms} ms")]
+    Cooldown { retry_after_ms: u64 },
+
+    #[error("Scan quota of {quota} per session exhausted")]
+    Quota { quota: u32 },
+
+    #[error("Scans are deferred while connected")]
+    Connected,
+}
+
+/// Tracks started scans against a `ScanPolicy`
+#[derive(Debug, Default)]
+pub struct ScanLimiter {
+    policy: ScanPolicy,
+    last_scan: Option<Instant>,
+    sessions: HashMap<SessionId, u32>,
+}
+
+impl ScanLimiter {
+    pub fn policy(&self) -> &ScanPolicy {
+        &self.policy
+    }
+
+    /// Replace the policy, keeping the scans counted so far
+    pub fn set_policy(&mut self, policy: ScanPolicy) {
+        self.policy = policy;
+    }
+
+    /// Check whether `session` may start a scan now
+    ///
+    /// Scans without a session (background scans, library callers) are not
+    /// subject to the quota.
+    pub fn check(&self, session: Option<SessionId>, connected: bool) -> Result<(), ScanRefusal> {
+        if connected && self.policy.defer_while_connected {
+            return Err(ScanRefusal::Connected);
+        }
+
+        // The cooldown passes by itself, so it is reported before the quota
+        if let Some(last_scan) = self.last_scan {
+            let elapsed = last_scan.elapsed();
+            if elapsed < self.policy.min_interval {
+                let retry_after = self.policy.min_interval - elapsed;
+                return Err(ScanRefusal::Cooldown {
+                    retry_after_ms: retry_after.as_millis().max(1) as u64,
+                });
+            }
+        }
+
+        if let (Some(quota), Some(session)) = (self.policy.session_quota, session) {
+            if self.sessions.get(&session).copied().unwrap_or(0) >= quota {
+                return Err(ScanRefusal::Quota { quota });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count a scan started by `session`
+    pub fn record(&mut self, session: Option<SessionId>) {
+        self.last_scan = Some(Instant::now());
+        if let Some(session) = session {
+            *self.sessions.entry(session).or_default() += 1;
+        }
+    }
+
+    /// Forget the scans of a closed session
+    pub fn end_session(&mut self, session: SessionId) {
+        self.sessions.remove(&session);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_limiter() {
+        let mut limiter = ScanLimiter::default();
+        limiter.set_policy(ScanPolicy {
+            min_interval: Duration::from_secs(10),
+            session_quota: Some(2),
+            defer_while_connected: true,
+        });
+        let session = SessionId::new();
+
+        assert_eq!(limiter.check(Some(session), false), Ok(()));
+        assert_eq!(
+            limiter.check(Some(session), true),
+            Err(ScanRefusal::Connected)
+        );
+        limiter.record(Some(session));
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert_eq!(
+            limiter.check(None, false),
+            Err(ScanRefusal::Cooldown {
+                retry_after_ms: 6000
+            })
+        );
+
+        tokio::time::advance(Duration::from_secs(6)).await;
+        limiter.record(Some(session));
+        // Over quota inside the interval, the cooldown comes first
+        assert!(matches!(
+            limiter.check(Some(session), false),
+            Err(ScanRefusal::Cooldown { .. })
+        ));
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(
+            limiter.check(Some(session), false),
+            Err(ScanRefusal::Quota { quota: 2 })
+        );
+
+        // Other sessions and scans without a session have their own budget
+        assert_eq!(limiter.check(Some(SessionId::new()), false), Ok(()));
+        assert_eq!(limiter.check(None, false), Ok(()));
+
+        limiter.end_session(session);
+        assert_eq!(limiter.check(Some(session), false), Ok(()));
+    }
+
+    #[test]
+    fn test_scan_refusal_serialization() {
+        let refusal = ScanRefusal::Cooldown {
+            retry_after_ms: 1500,
+        };
+        assert_eq!(
+            serde_json::to_string(&refusal).unwrap(),
+            r#"{"reason":"cooldown","retry_after_ms":1500}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ScanRefusal::Connected).unwrap(),
+            r#"{"reason":"connected"}"#
+        );
+    }
+}
@@ -0,0 +1,391 @@
+//! Views over scan results shared by all transports
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    ranking,
+    types::{
+        Band, GroupedBss, NetworkGroup, Rank, ScanResultsQuery, ScanSortKey, Ssid, WifiNetwork,
+    },
+};
+
+/// Scan results shaped by a `ScanResultsQuery`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ScanResultsView {
+    /// One entry per BSS
+    Networks(Vec<WifiNetwork>),
+    /// One entry per SSID
+    Groups(Vec<NetworkGroup>),
+}
+
+/// One page of a `ScanResultsView`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanResultsPage {
+    pub view: ScanResultsView,
+    /// Number of entries matching the filters, before offset and limit
+    pub total: usize,
+}
+
+/// Apply `query` to scan results
+///
+/// Filters select BSSes before grouping; sorting and paging apply to the
+/// returned entries (groups if grouped). Ranking happens before filtering,
+/// so filtered BSSes still count towards channel congestion.
+pub fn apply(mut networks: Vec<WifiNetwork>, query: &ScanResultsQuery) -> ScanResultsPage {
+    if query.rank {
+        ranking::rank(&mut networks);
+    }
+    let mut networks: Vec<_> = networks
+        .into_iter()
+        .filter(|network| query.matches(network))
+        .collect();
+
+    if query.group {
+        let mut groups = group_by_ssid(&networks);
+        if let Some(key) = query.sort {
+            groups.sort_by(|a, b| {
+                a.hidden.cmp(&b.hidden).then_with(|| {
+                    let channel = |group: &NetworkGroup| group.bssids[0].channel;
+                    compare(
+                        key,
+                        (&a.ssid, a.rssi, channel(a)),
+                        (&b.ssid, b.rssi, channel(b)),
+                    )
+                })
+            });
+        } else if query.rank {
+            groups.sort_by(|a, b| {
+                a.hidden
+                    .cmp(&b.hidden)
+                    .then(score(&b.rank).cmp(&score(&a.rank)))
+            });
+        }
+        let total = groups.len();
+        ScanResultsPage {
+            view: ScanResultsView::Groups(page(groups, query)),
+            total,
+        }
+    } else {
+        if let Some(key) = query.sort {
+            networks.sort_by(|a, b| {
+                compare(
+                    key,
+                    (&a.ssid, a.rssi, a.channel),
+                    (&b.ssid, b.rssi, b.channel),
+                )
+            });
+        } else if query.rank {
+            networks.sort_by(|a, b| score(&b.rank).cmp(&score(&a.rank)));
+        }
+        let total = networks.len();
+        ScanResultsPage {
+            view: ScanResultsView::Networks(page(networks, query)),
+            total,
+        }
+    }
+}
+
+/// Compare `(ssid, rssi, channel)` entries by `key`
+fn compare(key: ScanSortKey, a: (&Ssid, i16, u16), b: (&Ssid, i16, u16)) -> Ordering {
+    let strongest = b.1.cmp(&a.1);
+    match key {
+        ScanSortKey::Rssi => strongest,
+        ScanSortKey::Ssid => {
+            a.0.to_string()
+                .to_lowercase()
+                .cmp(&b.0.to_string().to_lowercase())
+                .then(strongest)
+        }
+        ScanSortKey::Channel => a.2.cmp(&b.2).then(strongest),
+    }
+}
+
+fn score(rank: &Option<Rank>) -> u8 {
+    rank.as_ref().map_or(0, |rank| rank.score)
+}
+
+fn page<T>(entries: Vec<T>, query: &ScanResultsQuery) -> Vec<T> {
+    entries
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Set the `connected` and `saved` markers of scan results
+///
+/// Hidden BSSes are never marked: their SSID is unknown.
+pub fn mark(networks: &mut [WifiNetwork], connected: Option<&Ssid>, saved: &[Ssid]) {
+    for network in networks
+        .iter_mut()
+        .filter(|network| !network.ssid.is_hidden())
+    {
+        network.connected = connected == Some(&network.ssid);
+        network.saved = saved.contains(&network.ssid);
+    }
+}
+
+/// Group BSSes by SSID, strongest group first and hidden networks last
+pub fn group_by_ssid(networks: &[WifiNetwork]) -> Vec<NetworkGroup> {
+    let mut groups: Vec<NetworkGroup> = Vec::new();
+
+    for network in networks {
+        let hidden = network.ssid.is_hidden();
+        let ssid = if hidden {
+            Ssid::default()
+        } else {
+            network.ssid.clone()
+        };
+
+        let bss = GroupedBss {
+            mac: network.mac.clone(),
+            channel: network.channel,
+            frequency: network.frequency,
+            rssi: network.rssi,
+            age_secs: network.age_secs,
+        };
+
+        match groups
+            .iter_mut()
+            .find(|group| group.hidden == hidden && group.ssid == ssid)
+        {
+            Some(group) => {
+                if network.rssi > group.rssi {
+                    group.rssi = network.rssi;
+                    group.security = network.security;
+                }
+                if score(&network.rank) > score(&group.rank) {
+                    group.rank = network.rank.clone();
+                }
+                group.connected |= network.connected;
+                group.saved |= network.saved;
+                group.bssids.push(bss);
+            }
+            None => groups.push(NetworkGroup {
+                ssid,
+                hidden,
+                rssi: network.rssi,
+                security: network.security,
+                bands: Vec::new(),
+                bssids: vec![bss],
+                connected: network.connected,
+                saved: network.saved,
+                rank: network.rank.clone(),
+            }),
+        }
+    }
+
+    for group in &mut groups {
+        group.bssids.sort_by(|a, b| b.rssi.cmp(&a.rssi));
+        group.bands = group
+            .bssids
+            .iter()
+            .filter_map(|bss| Band::from_frequency(bss.frequency))
+            .collect();
+        group.bands.sort();
+        group.bands.dedup();
+    }
+    groups.sort_by(|a, b| a.hidden.cmp(&b.hidden).then(b.rssi.cmp(&a.rssi)));
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{test_support::bss, types::Security};
+
+    #[test]
+    fn test_group_by_ssid() {
+        let networks = vec![
+            bss(b"Mesh", "aa:00:00:00:00:01", 2437, -70, Security::Wpa2),
+            bss(b"", "aa:00:00:00:00:02", 2412, -40, Security::Wpa2),
+            bss(b"Mesh", "aa:00:00:00:00:03", 5180, -50, Security::Wpa3),
+            bss(b"Cafe", "aa:00:00:00:00:04", 2462, -60, Security::Open),
+            bss(b"\0\0\0", "aa:00:00:00:00:05", 5745, -80, Security::Wpa2),
+            bss(b"Mesh", "aa:00:00:00:00:06", 5500, -65, Security::Wpa2),
+        ];
+
+        let groups = group_by_ssid(&networks);
+        assert_eq!(groups.len(), 3);
+
+        let mesh = &groups[0];
+        assert_eq!(mesh.ssid, "Mesh");
+        assert!(!mesh.hidden);
+        assert_eq!(mesh.rssi, -50);
+        assert_eq!(mesh.security, Security::Wpa3);
+        assert_eq!(mesh.bands, [Band::Band2_4Ghz, Band::Band5Ghz]);
+        let macs: Vec<_> = mesh.bssids.iter().map(|b| b.mac.as_str()).collect();
+        assert_eq!(
+            macs,
+            [
+                "aa:00:00:00:00:03",
+                "aa:00:00:00:00:06",
+                "aa:00:00:00:00:01"
+            ]
+        );
+
+        assert_eq!(groups[1].ssid, "Cafe");
+
+        // Hidden networks come last, however strong
+        let hidden = &groups[2];
+        assert!(hidden.hidden);
+        assert_eq!(hidden.ssid, "");
+        assert_eq!(hidden.rssi, -40);
+        assert_eq!(hidden.bssids.len(), 2);
+    }
+
+    #[test]
+    fn test_mark() {
+        let mut networks = vec![
+            bss(b"Home", "aa:00:00:00:00:01", 2437, -70, Security::Wpa2),
+            bss(b"Home", "aa:00:00:00:00:02", 5180, -50, Security::Wpa2),
+            bss(b"Office", "aa:00:00:00:00:03", 2412, -60, Security::Wpa2),
+            bss(b"", "aa:00:00:00:00:04", 2462, -40, Security::Open),
+        ];
+        let home = Ssid::from("Home");
+        mark(&mut networks, Some(&home), &[home.clone(), Ssid::default()]);
+
+        let markers: Vec<_> = networks.iter().map(|n| (n.connected, n.saved)).collect();
+        assert_eq!(
+            markers,
+            [(true, true), (true, true), (false, false), (false, false)]
+        );
+
+        let groups = group_by_ssid(&networks);
+        assert!(groups[0].connected && groups[0].saved);
+        assert!(!groups[1].connected && !groups[1].saved);
+        let json = serde_json::to_value(&groups[1]).unwrap();
+        assert!(json.get("connected").is_none() && json.get("saved").is_none());
+
+        mark(&mut networks, None, &[]);
+        assert!(networks.iter().all(|n| !n.connected && !n.saved));
+    }
+
+    #[test]
+    fn test_apply() {
+        let networks = vec![bss(b"Net", "aa:00:00:00:00:01", 2437, -70, Security::Wpa2)];
+
+        assert_eq!(
+            apply(networks.clone(), &ScanResultsQuery::default()),
+            ScanResultsPage {
+                view: ScanResultsView::Networks(networks.clone()),
+                total: 1,
+            }
+        );
+        let query = ScanResultsQuery {
+            group: true,
+            ..Default::default()
+        };
+        let ScanResultsView::Groups(groups) = apply(networks, &query).view else {
+            panic!("expected groups");
+        };
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[test]
+    fn test_apply_rank() {
+        let networks = vec![
+            bss(b"Brand", "aa:00:00:00:00:01", 2437, -55, Security::Wpa2),
+            bss(b"Other", "aa:00:00:00:00:02", 2437, -80, Security::Wpa2),
+            bss(b"Brand", "aa:00:00:00:00:03", 5180, -60, Security::Wpa2),
+        ];
+        let query = |json: &str| -> ScanResultsQuery { serde_json::from_str(json).unwrap() };
+
+        // Filtered BSSes still congest the channel
+        let ScanResultsView::Networks(ranked) =
+            apply(networks.clone(), &query(r#"{"rank":true,"min_rssi":-70}"#)).view
+        else {
+            panic!("expected networks");
+        };
+        let macs: Vec<_> = ranked.iter().map(|n| n.mac.as_str()).collect();
+        assert_eq!(macs, ["aa:00:00:00:00:03", "aa:00:00:00:00:01"]);
+        assert!(ranked[0].rank.as_ref().unwrap().recommended);
+        assert!(
+            ranked[1]
+                .rank
+                .as_ref()
+                .unwrap()
+                .reason
+                .contains("shared with 1 other network")
+        );
+
+        let ScanResultsView::Groups(groups) =
+            apply(networks.clone(), &query(r#"{"rank":true,"group":true}"#)).view
+        else {
+            panic!("expected groups");
+        };
+        assert_eq!(groups[0].ssid, "Brand");
+        assert_eq!(groups[0].rank, ranked[0].rank);
+
+        // Unranked results carry no rank
+        let ScanResultsView::Networks(plain) = apply(networks, &query("{}")).view else {
+            panic!("expected networks");
+        };
+        assert!(plain.iter().all(|n| n.rank.is_none()));
+        assert!(!serde_json::to_string(&plain).unwrap().contains("\"rank\""));
+    }
+
+    #[test]
+    fn test_apply_filter_sort_page() {
+        let networks = vec![
+            bss(b"beta", "aa:00:00:00:00:01", 2462, -70, Security::Wpa2),
+            bss(b"Alpha", "aa:00:00:00:00:02", 2412, -50, Security::Wpa2),
+            bss(b"gamma", "aa:00:00:00:00:03", 5180, -60, Security::Open),
+            bss(b"delta", "aa:00:00:00:00:04", 2437, -90, Security::Wpa2),
+        ];
+        let macs = |page: ScanResultsPage| -> Vec<String> {
+            let ScanResultsView::Networks(networks) = page.view else {
+                panic!("expected networks");
+            };
+            networks.into_iter().map(|n| n.mac).collect()
+        };
+        let query = |json: &str| -> ScanResultsQuery { serde_json::from_str(json).unwrap() };
+
+        let page = apply(
+            networks.clone(),
+            &query(r#"{"min_rssi":-80,"sort":"rssi","limit":2}"#),
+        );
+        assert_eq!(page.total, 3);
+        assert_eq!(macs(page), ["aa:00:00:00:00:02", "aa:00:00:00:00:03"]);
+
+        let page = apply(
+            networks.clone(),
+            &query(r#"{"sort":"ssid","offset":1,"limit":2}"#),
+        );
+        assert_eq!(page.total, 4);
+        assert_eq!(macs(page), ["aa:00:00:00:00:01", "aa:00:00:00:00:04"]);
+
+        let page = apply(
+            networks.clone(),
+            &query(r#"{"band":"2.4ghz","sort":"channel"}"#),
+        );
+        assert_eq!(
+            macs(page),
+            [
+                "aa:00:00:00:00:02",
+                "aa:00:00:00:00:04",
+                "aa:00:00:00:00:01"
+            ]
+        );
+
+        let page = apply(networks.clone(), &query(r#"{"offset":10}"#));
+        assert_eq!(page.total, 4);
+        assert!(macs(page).is_empty());
+
+        // Filters apply to BSSes, paging to groups
+        let page = apply(
+            networks,
+            &query(r#"{"group":true,"security":"wpa2","sort":"ssid","limit":1}"#),
+        );
+        assert_eq!(page.total, 3);
+        let ScanResultsView::Groups(groups) = page.view else {
+            panic!("expected groups");
+        };
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].ssid, "Alpha");
+    }
+}
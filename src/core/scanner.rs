@@ -1,24 +1,105 @@
 //! WiFi scanning service with state machine
 
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::{RwLock, watch},
+    task::JoinHandle,
+    time::Instant,
+};
+use tracing::{debug, warn};
 
 use crate::{
     backend::WifiBackend,
     core::{
-        error::{ServiceError, ServiceResult},
-        types::{ScanState, WifiNetwork},
+        error::{ServiceError, ServiceResult, WifiError},
+        scan_policy::{ScanLimiter, ScanPolicy, ScanRefusal},
+        scan_view,
+        survey::{SURVEY_INTERVAL, Survey, SurveyReport},
+        types::{
+            BssDetails, ConnectionState, ScanOptions, ScanState, SessionId, Ssid, WifiNetwork,
+        },
     },
 };
 
+/// Networks not seen for this long are dropped from the results
+pub const SCAN_CACHE_MAX_AGE: Duration = Duration::from_secs(120);
+
+/// Scans still running after this long are aborted and fail
+pub const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Clients count as connected for background scans until they have been
+/// silent this long (BLE reports no disconnects)
+pub const CLIENT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A scan result and when it was last seen
+#[derive(Debug, Clone)]
+struct CachedNetwork {
+    network: WifiNetwork,
+    last_seen: Instant,
+}
+
+/// Outcome of a scan: the merged results or the backend error
+type ScanOutcome = Result<Vec<WifiNetwork>, WifiError>;
+
+/// A scan in progress and the channel its callers wait on
+#[derive(Debug)]
+struct RunningScan {
+    options: ScanOptions,
+    done: watch::Sender<Option<ScanOutcome>>,
+    task: Option<JoinHandle<()>>,
+}
+
+/// Handle to a running scan
+///
+/// Shared by the caller that started the scan and all callers that joined it.
+#[derive(Debug, Clone)]
+pub struct ScanHandle {
+    done: watch::Receiver<Option<ScanOutcome>>,
+    joined: bool,
+    /// Why no scan was started if the cached results are returned
+    refusal: Option<ScanRefusal>,
+}
+
+impl ScanHandle {
+    /// Whether this caller joined a scan started by someone else
+    pub fn joined(&self) -> bool {
+        self.joined
+    }
+
+    /// Whether no scan was started and the cached results are returned
+    pub fn cached(&self) -> bool {
+        self.refusal.is_some()
+    }
+
+    /// Why the scan policy answered from the cache instead of scanning
+    pub fn refusal(&self) -> Option<ScanRefusal> {
+        self.refusal
+    }
+
+    /// Wait for the scan to finish and return the results as of its completion
+    pub async fn wait(mut self) -> ServiceResult<Vec<WifiNetwork>> {
+        match self.done.wait_for(Option::is_some).await {
+            Ok(outcome) => Ok(outcome.clone().expect("outcome is set")?),
+            Err(_) => Err(WifiError::ScanFailed("Scan was abandoned".into()).into()),
+        }
+    }
+}
+
 /// Scan state machine
 ///
-/// Manages the state transitions for WiFi scanning operations
+/// Manages the state transitions for WiFi scanning operations. Results are
+/// kept across scans so they stay readable while a rescan is running.
 #[derive(Debug)]
 struct ScanStateMachine {
     state: ScanState,
-    results: Option<Vec<WifiNetwork>>,
+    results: Option<Vec<CachedNetwork>>,
+    /// SSID the device was connected to when the last scan completed
+    connected: Option<Ssid>,
+    /// SSIDs with stored credentials when the last scan completed
+    saved: Vec<Ssid>,
     error: Option<String>,
+    running: Option<RunningScan>,
+    limiter: ScanLimiter,
 }
 
 impl ScanStateMachine {
@@ -26,16 +107,101 @@ impl ScanStateMachine {
         Self {
             state: ScanState::Idle,
             results: None,
+            connected: None,
+            saved: Vec::new(),
             error: None,
+            running: None,
+            limiter: ScanLimiter::default(),
         }
     }
 
+    /// Start a scan with `options` for `session`, or join the running one if
+    /// it uses the same options
+    ///
+    /// Scans the policy refuses are answered from the cached results if there
+    /// are any.
+    fn start_or_join(
+        &mut self,
+        options: &ScanOptions,
+        session: Option<SessionId>,
+        connected: bool,
+    ) -> ServiceResult<ScanHandle> {
+        if let Some(running) = &self.running {
+            if running.options != *options {
+                return Err(ServiceError::OperationInProgress);
+            }
+            return Ok(ScanHandle {
+                done: running.done.subscribe(),
+                joined: true,
+                refusal: None,
+            });
+        }
+
+        if let Err(refusal) = self.limiter.check(session, connected) {
+            if let Some(results) = self.results() {
+                let (_, receiver) = watch::channel(Some(Ok(results)));
+                return Ok(ScanHandle {
+                    done: receiver,
+                    joined: false,
+                    refusal: Some(refusal),
+                });
+            }
+            return Err(refusal.into());
+        }
+
+        self.start_scan()?;
+        self.limiter.record(session);
+        let (done, receiver) = watch::channel(None);
+        self.running = Some(RunningScan {
+            options: options.clone(),
+            done,
+            task: None,
+        });
+        Ok(ScanHandle {
+            done: receiver,
+            joined: false,
+            refusal: None,
+        })
+    }
+
+    /// Hand the outcome to everyone waiting for the running scan
+    fn finish_running(&mut self, outcome: ScanOutcome) {
+        if let Some(running) = self.running.take() {
+            running.done.send_replace(Some(outcome));
+        }
+    }
+
+    /// Stop the task of the running scan and fail its waiters
+    fn stop_running(&mut self, error: WifiError) {
+        if let Some(task) = self
+            .running
+            .as_mut()
+            .and_then(|running| running.task.take())
+        {
+            task.abort();
+        }
+        self.finish_running(Err(error));
+    }
+
+    /// Cancel the running scan, keeping the previous results
+    fn cancel_scan(&mut self) -> ServiceResult<()> {
+        if self.state != ScanState::Scanning {
+            return Err(ServiceError::InvalidStateTransition {
+                from: self.state,
+                to: ScanState::Idle,
+            });
+        }
+        self.state = ScanState::Idle;
+        self.error = None;
+        self.stop_running(WifiError::ScanFailed("Scan was cancelled".into()));
+        Ok(())
+    }
+
     /// Start a scan operation
     fn start_scan(&mut self) -> ServiceResult<()> {
         match self.state {
             ScanState::Idle | ScanState::Finished | ScanState::Error => {
                 self.state = ScanState::Scanning;
-                self.results = None;
                 self.error = None;
                 Ok(())
             }
@@ -43,42 +209,88 @@ impl ScanStateMachine {
         }
     }
 
-    /// Mark scan as completed with results
+    /// Mark scan as completed and merge its results into the previous ones
+    ///
+    /// Networks of this scan come first, in backend order, followed by those
+    /// seen within `SCAN_CACHE_MAX_AGE`.
     fn complete_scan(&mut self, networks: Vec<WifiNetwork>) {
+        let now = Instant::now();
+        let mut merged: Vec<CachedNetwork> = networks
+            .into_iter()
+            .map(|network| CachedNetwork {
+                network,
+                last_seen: now,
+            })
+            .collect();
+
+        for cached in self.results.take().unwrap_or_default() {
+            let seen = merged.iter().any(|n| n.network.mac == cached.network.mac);
+            if !seen && now.duration_since(cached.last_seen) < SCAN_CACHE_MAX_AGE {
+                merged.push(cached);
+            }
+        }
+
         self.state = ScanState::Finished;
-        self.results = Some(networks);
+        self.results = Some(merged);
         self.error = None;
+        if self.running.is_some() {
+            let results = self.results().unwrap_or_default();
+            self.finish_running(Ok(results));
+        }
     }
 
-    /// Mark scan as failed
-    fn fail_scan(&mut self, error: String) {
+    /// Mark scan as failed, keeping the previous results
+    fn fail_scan(&mut self, error: WifiError) {
         self.state = ScanState::Error;
-        self.error = Some(error);
-        self.results = None;
+        self.error = Some(error.to_string());
+        self.finish_running(Err(error));
     }
 
     /// Reset to idle state
     fn reset(&mut self) {
         self.state = ScanState::Idle;
         self.results = None;
+        self.connected = None;
+        self.saved.clear();
         self.error = None;
+        self.stop_running(WifiError::ScanFailed("Scan was reset".into()));
     }
 
     fn state(&self) -> ScanState {
         self.state
     }
 
-    fn results(&self) -> Option<&[WifiNetwork]> {
-        self.results.as_deref()
+    /// Results with their current age and markers, `None` before the first
+    /// successful scan
+    fn results(&self) -> Option<Vec<WifiNetwork>> {
+        let results = self.results.as_ref()?;
+        let mut networks: Vec<WifiNetwork> = results
+            .iter()
+            .map(|cached| WifiNetwork {
+                age_secs: cached.last_seen.elapsed().as_secs() as u32,
+                ..cached.network.clone()
+            })
+            .collect();
+        scan_view::mark(&mut networks, self.connected.as_ref(), &self.saved);
+        Some(networks)
     }
 }
 
+/// Periodic scans while a client is connected
+#[derive(Debug, Default)]
+struct BackgroundScan {
+    interval: Option<Duration>,
+    last_activity: Option<Instant>,
+    task: Option<JoinHandle<()>>,
+}
+
 /// WiFi scanning service
 ///
 /// Coordinates scanning operations using the WiFi backend
 pub struct ScanService<B: WifiBackend> {
     backend: Arc<B>,
     state_machine: Arc<RwLock<ScanStateMachine>>,
+    background: Arc<RwLock<BackgroundScan>>,
 }
 
 impl<B: WifiBackend> ScanService<B> {
@@ -87,47 +299,312 @@ impl<B: WifiBackend> ScanService<B> {
         Self {
             backend,
             state_machine: Arc::new(RwLock::new(ScanStateMachine::new())),
+            background: Arc::new(RwLock::new(BackgroundScan::default())),
         }
     }
 
     /// Start a WiFi scan
     ///
-    /// Returns an error if a scan is already in progress
-    pub async fn start_scan(&self) -> ServiceResult<()> {
-        // Check and update state
-        self.state_machine.write().await.start_scan()?;
+    /// `options` must be normalized (see `ScanOptions::normalized`). Only
+    /// BSSes on the scanned frequencies are taken from the backend results.
+    ///
+    /// If a scan with the same options is already running, the caller joins
+    /// it instead of starting another one. Returns an error if a scan with
+    /// different options is in progress or the scan policy refuses the scan
+    /// while nothing is cached; otherwise the cached results are returned.
+    pub async fn start_scan(&self, options: &ScanOptions) -> ServiceResult<ScanHandle> {
+        self.start_session_scan(None, options).await
+    }
 
-        // Perform scan in background
-        let backend = self.backend.clone();
-        let state_machine = self.state_machine.clone();
+    /// Start a WiFi scan on behalf of a client session
+    ///
+    /// Like `start_scan`, but the scan counts against the session quota.
+    pub async fn start_session_scan(
+        &self,
+        session: Option<SessionId>,
+        options: &ScanOptions,
+    ) -> ServiceResult<ScanHandle> {
+        Self::spawn_scan(
+            self.backend.clone(),
+            self.state_machine.clone(),
+            options.clone(),
+            session,
+        )
+        .await
+    }
+
+    async fn spawn_scan(
+        backend: Arc<B>,
+        state_machine: Arc<RwLock<ScanStateMachine>>,
+        options: ScanOptions,
+        session: Option<SessionId>,
+    ) -> ServiceResult<ScanHandle> {
+        let defer = state_machine
+            .read()
+            .await
+            .limiter
+            .policy()
+            .defer_while_connected;
+        let connected = defer
+            && backend
+                .status()
+                .await
+                .is_ok_and(|status| status.state == ConnectionState::Connected);
+
+        // Check and update state
+        let mut machine = state_machine.write().await;
+        let handle = machine.start_or_join(&options, session, connected)?;
+        if handle.joined() || handle.cached() {
+            return Ok(handle);
+        }
 
-        tokio::spawn(async move {
-            match backend.scan().await {
-                Ok(networks) => {
-                    state_machine.write().await.complete_scan(networks);
+        // Perform scan in background; the task cannot finish before it is
+        // registered since the state machine is still locked
+        let shared = state_machine.clone();
+        let task = tokio::spawn(async move {
+            match tokio::time::timeout(SCAN_TIMEOUT, backend.scan(&options)).await {
+                Ok(Ok(mut networks)) => {
+                    networks.retain(|network| options.admits(network));
+                    let (connected, saved) = Self::markers(&backend).await;
+                    let mut machine = shared.write().await;
+                    machine.connected = connected;
+                    machine.saved = saved;
+                    machine.complete_scan(networks);
                 }
-                Err(e) => {
-                    state_machine.write().await.fail_scan(e.to_string());
+                Ok(Err(e)) => {
+                    shared.write().await.fail_scan(e);
+                }
+                Err(_) => {
+                    warn!("Scan timed out after {:?}", SCAN_TIMEOUT);
+                    shared
+                        .write()
+                        .await
+                        .fail_scan(WifiError::ScanFailed(format!(
+                            "Scan timed out after {}s",
+                            SCAN_TIMEOUT.as_secs()
+                        )));
+                    if let Err(e) = backend.abort_scan().await {
+                        debug!("Could not abort timed out scan: {}", e);
+                    }
                 }
             }
         });
+        if let Some(running) = machine.running.as_mut() {
+            running.task = Some(task);
+        }
 
+        Ok(handle)
+    }
+
+    /// Cancel the running scan
+    ///
+    /// Waiters of the scan fail, the previous results are kept and the state
+    /// returns to `Idle`. Returns an error if no scan is running.
+    pub async fn cancel_scan(&self) -> ServiceResult<()> {
+        self.state_machine.write().await.cancel_scan()?;
+        if let Err(e) = self.backend.abort_scan().await {
+            debug!("Backend did not abort the scan: {}", e);
+        }
         Ok(())
     }
 
+    /// Limit the scans clients and background scans may start
+    pub async fn set_policy(&self, policy: ScanPolicy) {
+        self.state_machine.write().await.limiter.set_policy(policy);
+    }
+
+    /// Forget the scans counted for a closed client session
+    pub async fn end_session(&self, session: SessionId) {
+        self.state_machine
+            .write()
+            .await
+            .limiter
+            .end_session(session);
+    }
+
+    /// Scan every `interval` while a client is connected (`None` disables)
+    pub async fn set_background_interval(&self, interval: Option<Duration>) {
+        let mut background = self.background.write().await;
+        background.interval = interval;
+        if let Some(task) = background.task.take() {
+            task.abort();
+        }
+    }
+
+    /// Record client activity, starting background scans if enabled
+    ///
+    /// Background scans stop once no client was active for
+    /// `CLIENT_IDLE_TIMEOUT`.
+    pub async fn client_active(&self) {
+        let mut background = self.background.write().await;
+        background.last_activity = Some(Instant::now());
+
+        let Some(interval) = background.interval else {
+            return;
+        };
+        if background
+            .task
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+        {
+            return;
+        }
+
+        let backend = self.backend.clone();
+        let state_machine = self.state_machine.clone();
+        let shared = self.background.clone();
+        background.task = Some(tokio::spawn(async move {
+            loop {
+                let idle = shared
+                    .read()
+                    .await
+                    .last_activity
+                    .is_none_or(|at| at.elapsed() >= CLIENT_IDLE_TIMEOUT);
+                if idle {
+                    break;
+                }
+
+                // A scan the client started is just as good
+                let _ = Self::spawn_scan(
+                    backend.clone(),
+                    state_machine.clone(),
+                    ScanOptions::default(),
+                    None,
+                )
+                .await;
+                tokio::time::sleep(interval).await;
+            }
+        }));
+    }
+
     /// Get the current scan state
     pub async fn state(&self) -> ScanState {
         self.state_machine.read().await.state()
     }
 
+    /// Connected and saved SSIDs for marking scan results
+    ///
+    /// If the backend cannot tell, the markers stay unset.
+    async fn markers(backend: &B) -> (Option<Ssid>, Vec<Ssid>) {
+        let connected = match backend.status().await {
+            Ok(status) if status.state == ConnectionState::Connected => status.ssid,
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Connection status unavailable for scan results: {}", e);
+                None
+            }
+        };
+        let saved = backend.saved_networks().await.unwrap_or_else(|e| {
+            debug!("Saved networks unavailable for scan results: {}", e);
+            Vec::new()
+        });
+        (connected, saved)
+    }
+
     /// Get scan results (if available)
+    ///
+    /// The results of previous scans remain available while a scan is
+    /// running or after it failed. Networks the device was connected to or
+    /// had credentials for when the last scan completed are marked; the
+    /// backend is only asked once per scan.
     pub async fn results(&self) -> ServiceResult<Vec<WifiNetwork>> {
-        let sm = self.state_machine.read().await;
-        sm.results()
-            .map(|r| r.to_vec())
+        self.state_machine
+            .read()
+            .await
+            .results()
             .ok_or(ServiceError::NoScanResults)
     }
 
+    /// Run a site survey of `scans` scans
+    ///
+    /// The scans are ordinary full scans on behalf of `session`, so they
+    /// count against its quota and can be joined by other callers. Scans
+    /// answered from the cache inside the minimum interval are retried once
+    /// the interval has passed. Noise is read from the strongest BSS of each
+    /// channel where the backend reports it.
+    pub async fn survey(
+        &self,
+        session: Option<SessionId>,
+        scans: u32,
+    ) -> ServiceResult<SurveyReport> {
+        let mut survey = Survey::default();
+        let mut noise_supported = true;
+
+        for i in 0..scans {
+            if i > 0 {
+                tokio::time::sleep(SURVEY_INTERVAL).await;
+            }
+            let networks = loop {
+                let handle = self
+                    .start_session_scan(session, &ScanOptions::default())
+                    .await?;
+                match handle.refusal() {
+                    None => break handle.wait().await?,
+                    Some(ScanRefusal::Cooldown { .. }) => {}
+                    // Cached results are no survey scan
+                    Some(refusal) => return Err(refusal.into()),
+                }
+                let interval = self
+                    .state_machine
+                    .read()
+                    .await
+                    .limiter
+                    .policy()
+                    .min_interval;
+                debug!("Survey scan inside the minimum interval, retrying");
+                tokio::time::sleep(interval).await;
+            };
+
+            // Results are merged across scans; this scan's BSSes were just seen
+            let seen: Vec<_> = networks
+                .into_iter()
+                .filter(|network| network.age_secs == 0)
+                .collect();
+            survey.add_scan(&seen);
+
+            if noise_supported {
+                noise_supported = self.sample_noise(&mut survey, &seen).await;
+            }
+        }
+
+        Ok(survey.report())
+    }
+
+    /// Add the noise of each channel in `networks` to `survey`
+    ///
+    /// Returns false if the backend does not provide BSS details.
+    async fn sample_noise(&self, survey: &mut Survey, networks: &[WifiNetwork]) -> bool {
+        let mut strongest: Vec<&WifiNetwork> = Vec::new();
+        for network in networks.iter().filter(|network| network.frequency != 0) {
+            match strongest
+                .iter_mut()
+                .find(|other| other.frequency == network.frequency)
+            {
+                Some(other) if other.rssi < network.rssi => *other = network,
+                Some(_) => {}
+                None => strongest.push(network),
+            }
+        }
+
+        for network in strongest {
+            match self.backend.network_details(&network.mac).await {
+                Ok(details) => {
+                    if let Some(noise) = details.noise {
+                        survey.add_noise(network.frequency, network.channel, noise);
+                    }
+                }
+                Err(WifiError::NotSupported(_)) => return false,
+                Err(e) => debug!("No noise reading for {}: {}", network.mac, e),
+            }
+        }
+        true
+    }
+
+    /// Get detailed information about a BSS seen in the last scan
+    pub async fn network_details(&self, bssid: &str) -> ServiceResult<BssDetails> {
+        Ok(self.backend.network_details(bssid).await?)
+    }
+
     /// Reset the scan state to idle
     pub async fn reset(&self) {
         self.state_machine.write().await.reset();
@@ -157,6 +634,7 @@ mod tests {
             mac: "aa:bb:cc:dd:ee:ff".into(),
             channel: 6,
             rssi: -65,
+            ..Default::default()
         }];
         sm.complete_scan(networks.clone());
         assert_eq!(sm.state(), ScanState::Finished);
@@ -172,7 +650,7 @@ mod tests {
     async fn test_scan_state_machine_error() {
         let mut sm = ScanStateMachine::new();
         sm.start_scan().unwrap();
-        sm.fail_scan("Test error".into());
+        sm.fail_scan(WifiError::ScanFailed("Test error".into()));
 
         assert_eq!(sm.state(), ScanState::Error);
         assert!(sm.results().is_none());
@@ -187,13 +665,14 @@ mod tests {
                 mac: "aa:bb:cc:dd:ee:ff".into(),
                 channel: 6,
                 rssi: -65,
+                ..Default::default()
             }])
             .await;
 
         let service = ScanService::new(backend);
 
         // Start scan
-        service.start_scan().await.unwrap();
+        service.start_scan(&ScanOptions::default()).await.unwrap();
 
         // Wait for scan to complete
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
@@ -204,13 +683,45 @@ mod tests {
         assert_eq!(results[0].ssid, "TestNetwork");
     }
 
+    #[tokio::test]
+    async fn test_scan_service_frequency_filter() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![
+                WifiNetwork {
+                    mac: "aa:bb:cc:dd:ee:01".into(),
+                    frequency: 2437,
+                    ..Default::default()
+                },
+                WifiNetwork {
+                    mac: "aa:bb:cc:dd:ee:02".into(),
+                    frequency: 5180,
+                    ..Default::default()
+                },
+            ])
+            .await;
+
+        let service = ScanService::new(backend);
+        let options = ScanOptions {
+            freq_list: Some(vec![5180]),
+            ..Default::default()
+        };
+        service.start_scan(&options).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        // BSSes on other channels were not scanned, whatever the backend reports
+        let results = service.results().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].mac, "aa:bb:cc:dd:ee:02");
+    }
+
     #[tokio::test]
     async fn test_scan_service_failure() {
         let backend = Arc::new(MockWifiBackend::new());
         backend.set_scan_failure(true).await;
 
         let service = ScanService::new(backend);
-        service.start_scan().await.unwrap();
+        service.start_scan(&ScanOptions::default()).await.unwrap();
 
         // Wait for scan to fail
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
@@ -219,12 +730,420 @@ mod tests {
         assert!(service.results().await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_scan_service_network_details() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![WifiNetwork {
+                ssid: "TestNetwork".into(),
+                mac: "aa:bb:cc:dd:ee:ff".into(),
+                channel: 6,
+                rssi: -65,
+                ..Default::default()
+            }])
+            .await;
+
+        let service = ScanService::new(backend);
+
+        let details = service.network_details("aa:bb:cc:dd:ee:ff").await.unwrap();
+        assert_eq!(details.ssid, "TestNetwork");
+        assert!(service.network_details("00:11:22:33:44:55").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_scan_service_operation_in_progress() {
         let backend = Arc::new(MockWifiBackend::new());
         let service = ScanService::new(backend);
 
-        service.start_scan().await.unwrap();
-        assert!(service.start_scan().await.is_err());
+        service.start_scan(&ScanOptions::default()).await.unwrap();
+
+        // A scan with other options cannot be joined
+        let options = ScanOptions {
+            passive: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.start_scan(&options).await,
+            Err(ServiceError::OperationInProgress)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_service_join() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![network("aa:bb:cc:dd:ee:01", -50)])
+            .await;
+        backend.set_scan_latency(Duration::from_secs(2)).await;
+        let service = ScanService::new(backend.clone());
+
+        let first = service.start_scan(&ScanOptions::default()).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let second = service.start_scan(&ScanOptions::default()).await.unwrap();
+        assert!(!first.joined());
+        assert!(second.joined());
+
+        let (first, second) = tokio::join!(first.wait(), second.wait());
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(service.state().await, ScanState::Finished);
+
+        // The scan is over, the next caller starts a new one whose failure
+        // reaches everyone who joined it
+        backend.set_scan_failure(true).await;
+        let first = service.start_scan(&ScanOptions::default()).await.unwrap();
+        assert!(!first.joined());
+        let second = service.start_scan(&ScanOptions::default()).await.unwrap();
+        assert!(matches!(
+            second.wait().await,
+            Err(ServiceError::Backend(WifiError::ScanFailed(_)))
+        ));
+        assert!(first.wait().await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_timeout() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend.set_scan_latency(Duration::from_secs(3600)).await;
+        let service = ScanService::new(backend.clone());
+
+        let handle = service.start_scan(&ScanOptions::default()).await.unwrap();
+        tokio::time::sleep(SCAN_TIMEOUT - Duration::from_secs(1)).await;
+        assert_eq!(service.state().await, ScanState::Scanning);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(service.state().await, ScanState::Error);
+        assert!(handle.wait().await.is_err());
+        assert_eq!(backend.scan_aborts().await, 1);
+
+        // The next scan is accepted
+        backend.set_scan_latency(Duration::ZERO).await;
+        service.start_scan(&ScanOptions::default()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(service.state().await, ScanState::Finished);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_scan() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![network("aa:bb:cc:dd:ee:01", -50)])
+            .await;
+        let service = ScanService::new(backend.clone());
+        assert!(matches!(
+            service.cancel_scan().await,
+            Err(ServiceError::InvalidStateTransition { .. })
+        ));
+
+        service.start_scan(&ScanOptions::default()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        backend.set_scan_latency(Duration::from_secs(5)).await;
+        let handle = service.start_scan(&ScanOptions::default()).await.unwrap();
+        service.cancel_scan().await.unwrap();
+        assert_eq!(service.state().await, ScanState::Idle);
+        assert!(handle.wait().await.is_err());
+        assert_eq!(backend.scan_aborts().await, 1);
+
+        // The aborted task does not report back, the old results remain
+        backend.set_scan_results(vec![]).await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(service.state().await, ScanState::Idle);
+        assert_eq!(service.results().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_policy() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![network("aa:bb:cc:dd:ee:01", -50)])
+            .await;
+        let service = ScanService::new(backend.clone());
+        service
+            .set_policy(ScanPolicy {
+                min_interval: Duration::from_secs(30),
+                session_quota: Some(2),
+                defer_while_connected: true,
+            })
+            .await;
+        let session = SessionId::new();
+        let options = ScanOptions::default();
+
+        backend.set_scan_latency(Duration::from_secs(1)).await;
+        let handle = service
+            .start_session_scan(Some(session), &options)
+            .await
+            .unwrap();
+        assert!(!handle.cached());
+
+        // Joining a running scan is always allowed
+        let joined = service.start_scan(&options).await.unwrap();
+        assert!(joined.joined());
+        handle.wait().await.unwrap();
+
+        // Inside the minimum interval the cache answers
+        let cached = service
+            .start_session_scan(Some(session), &options)
+            .await
+            .unwrap();
+        assert!(cached.cached());
+        assert_eq!(cached.wait().await.unwrap().len(), 1);
+        assert_eq!(service.state().await, ScanState::Finished);
+
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        let handle = service
+            .start_session_scan(Some(session), &options)
+            .await
+            .unwrap();
+        handle.wait().await.unwrap();
+
+        // Over quota the cache answers too
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        let cached = service
+            .start_session_scan(Some(session), &options)
+            .await
+            .unwrap();
+        assert_eq!(cached.refusal(), Some(ScanRefusal::Quota { quota: 2 }));
+        service.end_session(session).await;
+        service
+            .start_session_scan(Some(session), &options)
+            .await
+            .unwrap()
+            .wait()
+            .await
+            .unwrap();
+
+        // No scans while connected, only cached results
+        backend
+            .connect(&"TestNetwork".into(), &[0; 32], &Default::default())
+            .await
+            .unwrap();
+        backend.complete_connection("192.168.1.100").await;
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        let cached = service.start_scan(&options).await.unwrap();
+        assert_eq!(cached.refusal(), Some(ScanRefusal::Connected));
+        assert_eq!(cached.wait().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_policy_connected_without_cache() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = ScanService::new(backend.clone());
+        service
+            .set_policy(ScanPolicy {
+                defer_while_connected: true,
+                ..Default::default()
+            })
+            .await;
+        backend
+            .connect(&"TestNetwork".into(), &[0; 32], &Default::default())
+            .await
+            .unwrap();
+        backend.complete_connection("192.168.1.100").await;
+
+        assert!(matches!(
+            service.start_scan(&ScanOptions::default()).await,
+            Err(ServiceError::ScanRefused(ScanRefusal::Connected))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_policy_cooldown_without_cache() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend.set_scan_failure(true).await;
+        let service = ScanService::new(backend.clone());
+        service
+            .set_policy(ScanPolicy {
+                min_interval: Duration::from_secs(10),
+                ..Default::default()
+            })
+            .await;
+
+        let handle = service.start_scan(&ScanOptions::default()).await.unwrap();
+        assert!(handle.wait().await.is_err());
+
+        // Nothing cached to serve, the scan is refused
+        let result = service.start_scan(&ScanOptions::default()).await;
+        assert!(matches!(
+            result,
+            Err(ServiceError::ScanRefused(ScanRefusal::Cooldown { .. }))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_survey() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let bss = |mac: &str, channel: u16, frequency: u32, rssi: i16| WifiNetwork {
+            frequency,
+            channel,
+            ..network(mac, rssi)
+        };
+        backend
+            .set_scan_results(vec![
+                bss("aa:bb:cc:dd:ee:01", 6, 2437, -50),
+                bss("aa:bb:cc:dd:ee:02", 6, 2437, -70),
+                bss("aa:bb:cc:dd:ee:03", 11, 2462, -60),
+            ])
+            .await;
+        let mut details = backend.network_details("aa:bb:cc:dd:ee:01").await.unwrap();
+        details.noise = Some(-91);
+        backend.set_network_details(details).await;
+
+        // Scans inside the minimum interval wait for it instead of using the cache
+        let service = ScanService::new(backend.clone());
+        service
+            .set_policy(ScanPolicy {
+                min_interval: Duration::from_secs(5),
+                ..Default::default()
+            })
+            .await;
+        let started = Instant::now();
+        let report = service.survey(None, 3).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(10));
+
+        assert_eq!(report.scans, 3);
+        assert_eq!(report.channels.len(), 2);
+        let ch6 = &report.channels[0];
+        assert_eq!((ch6.channel, ch6.bss_count), (6, 2));
+        assert_eq!((ch6.max_rssi, ch6.avg_rssi), (-50, -60));
+        assert_eq!(ch6.overlapping, 0);
+        assert_eq!(ch6.noise, Some(-91));
+        assert_eq!(report.channels[1].noise, None);
+
+        // Scan failures end the survey
+        backend.set_scan_failure(true).await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(service.survey(None, 2).await.is_err());
+    }
+
+    fn network(mac: &str, rssi: i16) -> WifiNetwork {
+        WifiNetwork {
+            ssid: "TestNetwork".into(),
+            mac: mac.into(),
+            channel: 6,
+            rssi,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_results_kept_and_merged() {
+        let mut sm = ScanStateMachine::new();
+        sm.start_scan().unwrap();
+        sm.complete_scan(vec![
+            network("aa:aa:aa:aa:aa:01", -60),
+            network("aa:aa:aa:aa:aa:02", -70),
+        ]);
+
+        // Rescans and failures don't hide the previous results
+        tokio::time::advance(Duration::from_secs(30)).await;
+        sm.start_scan().unwrap();
+        assert_eq!(sm.results().unwrap().len(), 2);
+        sm.fail_scan(WifiError::ScanFailed("busy".into()));
+        assert_eq!(sm.results().unwrap().len(), 2);
+
+        sm.start_scan().unwrap();
+        sm.complete_scan(vec![
+            network("aa:aa:aa:aa:aa:02", -50),
+            network("aa:aa:aa:aa:aa:03", -80),
+        ]);
+        tokio::time::advance(Duration::from_secs(5)).await;
+
+        let results: Vec<_> = sm
+            .results()
+            .unwrap()
+            .into_iter()
+            .map(|n| (n.mac, n.rssi, n.age_secs))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("aa:aa:aa:aa:aa:02".to_string(), -50, 5),
+                ("aa:aa:aa:aa:aa:03".to_string(), -80, 5),
+                ("aa:aa:aa:aa:aa:01".to_string(), -60, 35),
+            ]
+        );
+
+        // Networks missing for too long expire with the next scan
+        tokio::time::advance(SCAN_CACHE_MAX_AGE).await;
+        sm.start_scan().unwrap();
+        sm.complete_scan(vec![network("aa:aa:aa:aa:aa:03", -75)]);
+        assert_eq!(sm.results().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_markers_once_per_scan() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![WifiNetwork {
+                ssid: "Home".into(),
+                mac: "aa:00:00:00:00:01".to_string(),
+                ..Default::default()
+            }])
+            .await;
+        backend.set_saved_networks(vec![Ssid::from("Home")]).await;
+        let service = ScanService::new(backend.clone());
+        let saved = |networks: Vec<WifiNetwork>| networks[0].saved;
+
+        let networks = service
+            .start_scan(&ScanOptions::default())
+            .await
+            .unwrap()
+            .wait()
+            .await
+            .unwrap();
+        assert!(saved(networks));
+        for _ in 0..3 {
+            assert!(saved(service.results().await.unwrap()));
+        }
+        assert_eq!(backend.saved_queries().await, 1);
+
+        // Markers follow the backend with the next scan
+        backend.set_saved_networks(vec![]).await;
+        assert!(saved(service.results().await.unwrap()));
+        service
+            .start_scan(&ScanOptions::default())
+            .await
+            .unwrap()
+            .wait()
+            .await
+            .unwrap();
+        assert!(!saved(service.results().await.unwrap()));
+        assert_eq!(backend.saved_queries().await, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_background_scans() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![network("aa:bb:cc:dd:ee:ff", -65)])
+            .await;
+        let service = ScanService::new(backend.clone());
+
+        // Disabled by default
+        service.client_active().await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(service.state().await, ScanState::Idle);
+
+        service
+            .set_background_interval(Some(Duration::from_secs(10)))
+            .await;
+        service.client_active().await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(service.state().await, ScanState::Finished);
+
+        // Rescans pick up changes while the client is around
+        backend
+            .set_scan_results(vec![network("aa:bb:cc:dd:ee:ff", -40)])
+            .await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(service.results().await.unwrap()[0].rssi, -40);
+
+        // ... and stop once it went quiet
+        tokio::time::sleep(CLIENT_IDLE_TIMEOUT).await;
+        backend
+            .set_scan_results(vec![network("aa:bb:cc:dd:ee:ff", -90)])
+            .await;
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        assert_eq!(service.results().await.unwrap()[0].rssi, -40);
     }
 }
@@ -8,34 +8,184 @@ use crate::{
         authorization::AuthorizationService,
         connector::ConnectionService,
         error::ServiceResult,
-        scanner::ScanService,
-        types::{ConnectionStatus, ScanState, WifiNetwork},
+        netif,
+        scanner::{ScanHandle, ScanService},
+        types::{
+            BssDetails, Capabilities, ConnectOptions, ConnectionStatus, NetworkInterface,
+            ScanOptions, ScanState, Ssid, WifiNetwork, WpsMethod,
+        },
     },
 };
 
+/// Scan and connect services of one wireless interface
+pub struct InterfaceService<B: WifiBackend> {
+    /// Interface name (empty for the unnamed interface of `WifiCommissioningService::new`)
+    pub name: String,
+    pub scanner: Arc<ScanService<B>>,
+    pub connector: Arc<ConnectionService<B>>,
+}
+
+impl<B: WifiBackend> InterfaceService<B> {
+    /// Create the services for interface `name` on top of `backend`
+    pub fn new(name: String, backend: Arc<B>) -> Self {
+        Self::from_parts(
+            name,
+            Arc::new(ScanService::new(backend.clone())),
+            Arc::new(ConnectionService::new(backend)),
+        )
+    }
+
+    /// Combine existing scan and connect services
+    pub fn from_parts(
+        name: String,
+        scanner: Arc<ScanService<B>>,
+        connector: Arc<ConnectionService<B>>,
+    ) -> Self {
+        Self {
+            name,
+            scanner,
+            connector,
+        }
+    }
+
+    /// Start a WiFi scan, or join the running one (see `ScanService::start_scan`)
+    pub async fn start_scan(&self, options: &ScanOptions) -> ServiceResult<ScanHandle> {
+        self.scanner.start_scan(options).await
+    }
+
+    /// Cancel the running scan (see `ScanService::cancel_scan`)
+    pub async fn cancel_scan(&self) -> ServiceResult<()> {
+        self.scanner.cancel_scan().await
+    }
+
+    /// Get scan state
+    pub async fn scan_state(&self) -> ScanState {
+        self.scanner.state().await
+    }
+
+    /// Get scan results
+    pub async fn scan_results(&self) -> Option<Vec<WifiNetwork>> {
+        self.scanner.results().await.ok()
+    }
+
+    /// Get detailed information about a BSS
+    pub async fn network_details(&self, bssid: &str) -> ServiceResult<BssDetails> {
+        self.scanner.network_details(bssid).await
+    }
+
+    /// Connect to a WiFi network
+    pub async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> ServiceResult<()> {
+        self.connector.connect(ssid, psk, options).await
+    }
+
+    /// Start WPS registration, returning the PIN in use for the PIN method
+    pub async fn start_wps(
+        &self,
+        method: &WpsMethod,
+        bssid: Option<&str>,
+    ) -> ServiceResult<Option<String>> {
+        self.connector.start_wps(method, bssid).await
+    }
+
+    /// Cancel a running WPS registration
+    pub async fn cancel_wps(&self) -> ServiceResult<()> {
+        self.connector.cancel_wps().await
+    }
+
+    /// Start DPP enrollee, returning the bootstrap URI
+    pub async fn start_dpp(&self) -> ServiceResult<String> {
+        self.connector.start_dpp().await
+    }
+
+    /// Stop DPP enrollee
+    pub async fn stop_dpp(&self) -> ServiceResult<()> {
+        self.connector.stop_dpp().await
+    }
+
+    /// Bootstrap URI of the running DPP session
+    pub async fn dpp_uri(&self) -> Option<String> {
+        self.connector.dpp_uri().await
+    }
+
+    /// Get the features available through the backend
+    pub async fn capabilities(&self) -> ServiceResult<Capabilities> {
+        self.connector.capabilities().await
+    }
+
+    /// Get the active regulatory domain
+    pub async fn country(&self) -> ServiceResult<Option<String>> {
+        self.connector.country().await
+    }
+
+    /// Set the regulatory domain, optionally persisting it
+    pub async fn set_country(&self, country: &str, persist: bool) -> ServiceResult<()> {
+        self.connector.set_country(country, persist).await
+    }
+
+    /// Disconnect from current network
+    pub async fn disconnect(&self) -> ServiceResult<()> {
+        self.connector.disconnect().await
+    }
+
+    /// Get connection status
+    pub async fn connection_status(&self) -> ConnectionStatus {
+        self.connector.status().await
+    }
+}
+
 /// Main WiFi commissioning service facade
 ///
-/// Orchestrates all service components (authorization, scan, connect)
+/// Orchestrates all service components: authorization, shared by all
+/// transports, and scan/connect per managed interface. Operations without an
+/// explicit interface go to the first (default) one.
 pub struct WifiCommissioningService<B: WifiBackend> {
     pub authorization: Arc<AuthorizationService>,
-    pub scanner: Arc<ScanService<B>>,
-    pub connector: Arc<ConnectionService<B>>,
+    interfaces: Vec<Arc<InterfaceService<B>>>,
 }
 
 impl<B: WifiBackend> WifiCommissioningService<B> {
-    /// Create a new WiFi commissioning service
+    /// Create a WiFi commissioning service for a single, unnamed interface
     pub fn new(backend: Arc<B>, secret: String) -> Self {
-        let authorization = Arc::new(AuthorizationService::new(secret));
-        let scanner = Arc::new(ScanService::new(backend.clone()));
-        let connector = Arc::new(ConnectionService::new(backend));
+        Self::with_interfaces(vec![(String::new(), backend)], secret)
+    }
+
+    /// Create a WiFi commissioning service managing several interfaces
+    ///
+    /// The first interface is the default. `interfaces` must not be empty.
+    pub fn with_interfaces(interfaces: Vec<(String, Arc<B>)>, secret: String) -> Self {
+        assert!(!interfaces.is_empty(), "No interface to manage");
 
         Self {
-            authorization,
-            scanner,
-            connector,
+            authorization: Arc::new(AuthorizationService::new(secret)),
+            interfaces: interfaces
+                .into_iter()
+                .map(|(name, backend)| Arc::new(InterfaceService::new(name, backend)))
+                .collect(),
         }
     }
 
+    /// All managed interfaces, the default first
+    pub fn interfaces(&self) -> &[Arc<InterfaceService<B>>] {
+        &self.interfaces
+    }
+
+    /// Look up an interface by name, or the default interface for `None`
+    pub fn interface(&self, name: Option<&str>) -> Option<&Arc<InterfaceService<B>>> {
+        match name {
+            Some(name) => self.interfaces.iter().find(|iface| iface.name == name),
+            None => self.interfaces.first(),
+        }
+    }
+
+    fn default_interface(&self) -> &InterfaceService<B> {
+        &self.interfaces[0]
+    }
+
     /// Authorize a session
     pub async fn authorize(&self, hash: &[u8; 32]) -> ServiceResult<()> {
         self.authorization.authorize(hash).await
@@ -46,34 +196,49 @@ impl<B: WifiBackend> WifiCommissioningService<B> {
         self.authorization.is_authorized().await
     }
 
-    /// Start a WiFi scan
-    pub async fn start_scan(&self) -> ServiceResult<()> {
-        self.scanner.start_scan().await
+    /// List all network interfaces of the device, managed or not
+    pub async fn list_interfaces(&self) -> ServiceResult<Vec<NetworkInterface>> {
+        netif::list_interfaces().await
     }
 
-    /// Get scan state
+    /// Start a WiFi scan on the default interface
+    pub async fn start_scan(&self, options: &ScanOptions) -> ServiceResult<ScanHandle> {
+        self.default_interface().start_scan(options).await
+    }
+
+    /// Cancel the running scan of the default interface
+    pub async fn cancel_scan(&self) -> ServiceResult<()> {
+        self.default_interface().cancel_scan().await
+    }
+
+    /// Get scan state of the default interface
     pub async fn scan_state(&self) -> ScanState {
-        self.scanner.state().await
+        self.default_interface().scan_state().await
     }
 
-    /// Get scan results
+    /// Get scan results of the default interface
     pub async fn scan_results(&self) -> Option<Vec<WifiNetwork>> {
-        self.scanner.results().await.ok()
+        self.default_interface().scan_results().await
     }
 
-    /// Connect to a WiFi network
-    pub async fn connect(&self, ssid: &str, psk: &[u8; 32]) -> ServiceResult<()> {
-        self.connector.connect(ssid, psk).await
+    /// Connect the default interface to a WiFi network
+    pub async fn connect(
+        &self,
+        ssid: &Ssid,
+        psk: &[u8; 32],
+        options: &ConnectOptions,
+    ) -> ServiceResult<()> {
+        self.default_interface().connect(ssid, psk, options).await
     }
 
-    /// Disconnect from current network
+    /// Disconnect the default interface
     pub async fn disconnect(&self) -> ServiceResult<()> {
-        self.connector.disconnect().await
+        self.default_interface().disconnect().await
     }
 
-    /// Get connection status
+    /// Get connection status of the default interface
     pub async fn connection_status(&self) -> ConnectionStatus {
-        self.connector.status().await
+        self.default_interface().connection_status().await
     }
 }
 
@@ -115,17 +280,18 @@ mod tests {
         let backend = Arc::new(MockWifiBackend::new());
         backend
             .set_scan_results(vec![WifiNetwork {
-                ssid: "TestNet".to_string(),
+                ssid: "TestNet".into(),
                 mac: "aa:bb:cc:dd:ee:ff".to_string(),
                 channel: 6,
                 rssi: -65,
+                ..Default::default()
             }])
             .await;
 
         let service = WifiCommissioningService::new(backend, "test".to_string());
 
         // Start scan
-        service.start_scan().await.unwrap();
+        service.start_scan(&ScanOptions::default()).await.unwrap();
         assert_eq!(service.scan_state().await, ScanState::Scanning);
 
         // Wait for scan to complete
@@ -146,16 +312,19 @@ mod tests {
         let service = WifiCommissioningService::new(backend.clone(), "test".to_string());
 
         let psk = [0u8; 32];
-        service.connect("TestNet", &psk).await.unwrap();
+        service
+            .connect(&"TestNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
 
         // Wait for connection
         tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
         backend.complete_connection("192.168.1.100").await;
-        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+        tokio::time::sleep(crate::core::connector::CONNECT_POLL_INTERVAL * 2).await;
 
         let status = service.connection_status().await;
         assert_eq!(status.state, ConnectionState::Connected);
-        assert_eq!(status.ssid, Some("TestNet".to_string()));
+        assert_eq!(status.ssid, Some("TestNet".into()));
     }
 
     #[tokio::test]
@@ -164,7 +333,10 @@ mod tests {
         let service = WifiCommissioningService::new(backend.clone(), "test".to_string());
 
         let psk = [0u8; 32];
-        service.connect("TestNet", &psk).await.unwrap();
+        service
+            .connect(&"TestNet".into(), &psk, &ConnectOptions::default())
+            .await
+            .unwrap();
         tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
         backend.complete_connection("192.168.1.100").await;
         tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
@@ -0,0 +1,215 @@
+//! Site survey
+//!
+//! Field engineers check how crowded each channel is before placing a device.
+//! A survey runs several scans and aggregates the BSSes seen into
+//! per-channel statistics.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    ranking::channels_overlap,
+    types::{Band, WifiNetwork},
+};
+
+/// Scans of a survey if the client does not ask for a number
+pub const DEFAULT_SURVEY_SCANS: u32 = 3;
+
+/// Upper bound for the scans of a survey
+pub const MAX_SURVEY_SCANS: u32 = 10;
+
+/// Pause between the scans of a survey
+pub const SURVEY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Statistics of one channel over all scans of a survey
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelStats {
+    /// Channel number (0 if the backend doesn't report it)
+    pub channel: u16,
+    /// Center frequency in MHz (0 if the backend doesn't report it)
+    pub frequency: u32,
+    /// Band of the channel (omitted if the frequency is unknown)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub band: Option<Band>,
+    /// Distinct BSSes seen on the channel
+    pub bss_count: usize,
+    /// Strongest signal seen on the channel in dBm
+    pub max_rssi: i16,
+    /// Average signal of all sightings in dBm
+    pub avg_rssi: i16,
+    /// Distinct BSSes on neighbouring channels that overlap this one
+    pub overlapping: usize,
+    /// Average noise floor in dBm (if reported)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise: Option<i16>,
+}
+
+impl ChannelStats {
+    /// Single line representation for BLE
+    ///
+    /// Space-separated channel, frequency, BSS count, strongest and average
+    /// RSSI, overlapping BSSes and noise (`-` if unknown), e.g.
+    /// `6 2437 4 -41 -63 2 -92`.
+    pub fn compact(&self) -> String {
+        let noise = self
+            .noise
+            .map_or_else(|| "-".to_string(), |noise| noise.to_string());
+        format!(
+            "{} {} {} {} {} {} {}",
+            self.channel,
+            self.frequency,
+            self.bss_count,
+            self.max_rssi,
+            self.avg_rssi,
+            self.overlapping,
+            noise
+        )
+    }
+}
+
+/// Result of a site survey
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SurveyReport {
+    /// Scans the statistics are aggregated from
+    pub scans: u32,
+    /// Channels with at least one BSS, lowest frequency first
+    pub channels: Vec<ChannelStats>,
+}
+
+/// Sightings on one channel
+#[derive(Debug, Default)]
+struct ChannelSamples {
+    bssids: BTreeSet<String>,
+    rssi: Vec<i16>,
+    noise: Vec<i16>,
+}
+
+/// Collects the scans of a running survey
+#[derive(Debug, Default)]
+pub struct Survey {
+    scans: u32,
+    /// Samples by frequency and channel
+    channels: BTreeMap<(u32, u16), ChannelSamples>,
+}
+
+impl Survey {
+    /// Add the BSSes seen by one scan
+    pub fn add_scan(&mut self, networks: &[WifiNetwork]) {
+        self.scans += 1;
+        for network in networks {
+            let samples = self
+                .channels
+                .entry((network.frequency, network.channel))
+                .or_default();
+            samples.bssids.insert(network.mac.clone());
+            samples.rssi.push(network.rssi);
+        }
+    }
+
+    /// Add a noise reading of a channel
+    pub fn add_noise(&mut self, frequency: u32, channel: u16, noise: i16) {
+        self.channels
+            .entry((frequency, channel))
+            .or_default()
+            .noise
+            .push(noise);
+    }
+
+    /// Aggregate the scans added so far
+    pub fn report(&self) -> SurveyReport {
+        let channels = self
+            .channels
+            .iter()
+            .filter(|(_, samples)| !samples.rssi.is_empty())
+            .map(|(&(frequency, channel), samples)| {
+                let overlapping: BTreeSet<&String> = self
+                    .channels
+                    .iter()
+                    .filter(|((other, _), _)| {
+                        *other != frequency && channels_overlap(frequency, *other)
+                    })
+                    .flat_map(|(_, other)| &other.bssids)
+                    .collect();
+
+                ChannelStats {
+                    channel,
+                    frequency,
+                    band: Band::from_frequency(frequency),
+                    bss_count: samples.bssids.len(),
+                    max_rssi: samples.rssi.iter().copied().max().unwrap_or_default(),
+                    avg_rssi: average(&samples.rssi),
+                    overlapping: overlapping.len(),
+                    noise: (!samples.noise.is_empty()).then(|| average(&samples.noise)),
+                }
+            })
+            .collect();
+
+        SurveyReport {
+            scans: self.scans,
+            channels,
+        }
+    }
+}
+
+/// Rounded average of dBm values
+fn average(values: &[i16]) -> i16 {
+    let sum: i32 = values.iter().map(|&value| i32::from(value)).sum();
+    (f64::from(sum) / values.len() as f64).round() as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{test_support::bss, types::Security};
+
+    #[test]
+    fn test_survey() {
+        let mut survey = Survey::default();
+        survey.add_scan(&[
+            bss("Net", "aa:00:00:00:00:01", 2437, -40, Security::Wpa2),
+            bss("Net", "aa:00:00:00:00:02", 2437, -70, Security::Wpa2),
+            bss("Net", "aa:00:00:00:00:03", 2412, -60, Security::Wpa2),
+            bss("Net", "aa:00:00:00:00:04", 5180, -55, Security::Wpa2),
+        ]);
+        survey.add_noise(2437, 6, -92);
+        survey.add_scan(&[
+            bss("Net", "aa:00:00:00:00:01", 2437, -45, Security::Wpa2),
+            bss("Net", "aa:00:00:00:00:05", 2427, -80, Security::Wpa2),
+            bss("Net", "aa:00:00:00:00:04", 5180, -57, Security::Wpa2),
+        ]);
+        survey.add_noise(2437, 6, -95);
+
+        let report = survey.report();
+        assert_eq!(report.scans, 2);
+        let channels: Vec<_> = report.channels.iter().map(|c| c.channel).collect();
+        assert_eq!(channels, [1, 4, 6, 36]);
+
+        let ch6 = &report.channels[2];
+        assert_eq!(ch6.band, Some(Band::Band2_4Ghz));
+        assert_eq!(ch6.bss_count, 2);
+        assert_eq!(ch6.max_rssi, -40);
+        assert_eq!(ch6.avg_rssi, -52);
+        // Channel 4 overlaps channel 6, channel 1 does not
+        assert_eq!(ch6.overlapping, 1);
+        assert_eq!(ch6.noise, Some(-94));
+        assert_eq!(ch6.compact(), "6 2437 2 -40 -52 1 -94");
+
+        // Channel 4 overlaps both channel 1 and channel 6
+        assert_eq!(report.channels[1].overlapping, 3);
+
+        let ch36 = &report.channels[3];
+        assert_eq!(
+            (ch36.bss_count, ch36.avg_rssi, ch36.overlapping),
+            (1, -56, 0)
+        );
+        assert_eq!(ch36.compact(), "36 5180 1 -55 -56 0 -");
+
+        let json = serde_json::to_value(ch36).unwrap();
+        assert_eq!(json["band"], "5ghz");
+        assert!(json.get("noise").is_none());
+    }
+}
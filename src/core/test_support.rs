@@ -0,0 +1,25 @@
+//! Shared fixtures for core tests
+
+use crate::{
+    backend::WpactrlBackend,
+    core::types::{Security, Ssid, WifiNetwork},
+};
+
+/// A BSS as reported by a scan, on the channel of `frequency`
+pub fn bss(
+    ssid: impl AsRef<[u8]>,
+    mac: &str,
+    frequency: u32,
+    rssi: i16,
+    security: Security,
+) -> WifiNetwork {
+    WifiNetwork {
+        ssid: Ssid::from(ssid.as_ref()),
+        mac: mac.to_string(),
+        channel: WpactrlBackend::frequency_to_channel(&frequency.to_string()),
+        frequency,
+        rssi,
+        security,
+        ..Default::default()
+    }
+}
@@ -1,18 +1,673 @@
 //! Domain types for WiFi commissioning
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// WiFi network name
+///
+/// SSIDs are up to 32 arbitrary octets and are not guaranteed to be UTF-8.
+/// In JSON an SSID is a plain string when it is valid UTF-8 and falls back
+/// to `{"hex": "<hex-encoded octets>"}` otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ssid(Vec<u8>);
+
+impl Ssid {
+    /// Maximum SSID length in octets
+    pub const MAX_LEN: usize = 32;
+
+    /// Create an SSID from raw octets
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Self(bytes.into())
+    }
+
+    /// Create an SSID from its hex representation
+    pub fn from_hex(hex_str: &str) -> Result<Self, hex::FromHexError> {
+        hex::decode(hex_str).map(Self)
+    }
+
+    /// Raw SSID octets
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// SSID as string slice, if it is valid UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    /// Hex representation of the SSID octets
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    /// Length in octets
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check for an empty (hidden) SSID
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Check for a hidden SSID, broadcast empty or as zero octets
+    pub fn is_hidden(&self) -> bool {
+        self.0.iter().all(|b| *b == 0)
+    }
+}
+
+impl From<&str> for Ssid {
+    fn from(value: &str) -> Self {
+        Self(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Ssid {
+    fn from(value: String) -> Self {
+        Self(value.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for Ssid {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&[u8]> for Ssid {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl PartialEq<str> for Ssid {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for Ssid {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl std::fmt::Display for Ssid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.0))
+    }
+}
+
+/// JSON representation of an [`Ssid`]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SsidRepr<'a> {
+    Text(std::borrow::Cow<'a, str>),
+    Hex { hex: String },
+}
+
+impl Serialize for Ssid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.as_str() {
+            Some(text) => SsidRepr::Text(text.into()),
+            None => SsidRepr::Hex { hex: self.to_hex() },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ssid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match SsidRepr::deserialize(deserializer)? {
+            SsidRepr::Text(text) => Ok(text.into_owned().into()),
+            SsidRepr::Hex { hex } => Ssid::from_hex(&hex).map_err(serde::de::Error::custom),
+        }
+    }
+}
 
 /// Represents a discovered WiFi network
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct WifiNetwork {
     /// Network SSID
-    pub ssid: String,
+    pub ssid: Ssid,
     /// MAC address (BSSID)
     pub mac: String,
     /// Channel number
     pub channel: u16,
+    /// Frequency in MHz (0 if the backend doesn't report it)
+    #[serde(default)]
+    pub frequency: u32,
     /// Signal strength in dBm
     pub rssi: i16,
+    #[serde(default)]
+    pub security: Security,
+    /// Seconds since the BSS was last seen (results are kept across scans)
+    #[serde(default)]
+    pub age_secs: u32,
+    /// The device is connected to this SSID (omitted if not)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub connected: bool,
+    /// The device has credentials for this SSID (omitted if not)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub saved: bool,
+    /// Recommendation score (only for ranked queries)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<Rank>,
+}
+
+/// Recommendation score of a BSS (see `core::ranking`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rank {
+    /// 0 (worst) to 100 (best)
+    pub score: u8,
+    /// Best BSS of its SSID
+    #[serde(default)]
+    pub recommended: bool,
+    /// Human-readable summary of the score
+    pub reason: String,
+}
+
+/// Security of a BSS, from weakest to strongest
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Security {
+    #[default]
+    Open,
+    /// Opportunistic Wireless Encryption (Enhanced Open)
+    Owe,
+    Wep,
+    /// WPA-Personal
+    Wpa,
+    /// WPA2-Personal, including WPA2/WPA3 transition mode
+    Wpa2,
+    /// WPA3-Personal (SAE only)
+    Wpa3,
+    /// WPA2/WPA3-Enterprise (802.1X)
+    Enterprise,
+}
+
+impl Security {
+    /// Classify wpa_supplicant style flags (e.g. `WPA2-PSK+SAE-CCMP`, `WEP`)
+    pub fn from_flags<S: AsRef<str>>(flags: &[S]) -> Self {
+        let tokens = |flag: &str| -> Vec<String> {
+            flag.split(['-', '+', '/'])
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let (mut eap, mut rsn_psk, mut wpa_psk, mut sae, mut wep, mut owe) =
+            (false, false, false, false, false, false);
+        for flag in flags {
+            let flag = flag.as_ref();
+            let tokens = tokens(flag);
+            let has = |token: &str| tokens.iter().any(|t| t == token);
+            eap |= has("EAP");
+            sae |= has("SAE");
+            wep |= flag == "WEP";
+            owe |= has("OWE") && !has("TRANS");
+            if has("PSK") {
+                match tokens.first().map(String::as_str) {
+                    Some("WPA") => wpa_psk = true,
+                    _ => rsn_psk = true,
+                }
+            }
+        }
+
+        if eap {
+            Security::Enterprise
+        } else if rsn_psk {
+            Security::Wpa2
+        } else if sae {
+            Security::Wpa3
+        } else if wpa_psk {
+            Security::Wpa
+        } else if wep {
+            Security::Wep
+        } else if owe {
+            Security::Owe
+        } else {
+            Security::Open
+        }
+    }
+}
+
+/// One SSID of the grouped scan results view
+///
+/// Mesh and multi-band networks show up as a single entry. All hidden
+/// networks share one group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkGroup {
+    /// Network SSID (empty for the hidden group)
+    pub ssid: Ssid,
+    /// Group of networks not broadcasting their SSID
+    #[serde(default)]
+    pub hidden: bool,
+    /// Best signal strength in dBm
+    pub rssi: i16,
+    /// Security of the strongest BSS
+    pub security: Security,
+    /// Bands the SSID is seen on
+    pub bands: Vec<Band>,
+    /// BSSes of the SSID, strongest first
+    pub bssids: Vec<GroupedBss>,
+    /// The device is connected to this SSID (omitted if not)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub connected: bool,
+    /// The device has credentials for this SSID (omitted if not)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub saved: bool,
+    /// Rank of the best BSS (only for ranked queries)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<Rank>,
+}
+
+/// BSS within a `NetworkGroup`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupedBss {
+    /// MAC address (BSSID)
+    pub mac: String,
+    pub channel: u16,
+    /// Frequency in MHz
+    pub frequency: u32,
+    /// Signal strength in dBm
+    pub rssi: i16,
+    /// Seconds since the BSS was last seen
+    pub age_secs: u32,
+}
+
+/// Detailed information about a single BSS
+///
+/// Goes beyond the scan result summary and is intended for diagnosing
+/// unusual access points in the field.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BssDetails {
+    /// MAC address (BSSID)
+    pub bssid: String,
+    /// Network SSID
+    pub ssid: Ssid,
+    /// Frequency in MHz
+    pub frequency: u32,
+    /// Channel number
+    pub channel: u16,
+    /// Signal strength in dBm
+    pub rssi: i16,
+    /// Noise level in dBm (if reported)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noise: Option<i16>,
+    /// Beacon interval in time units (1024 µs)
+    pub beacon_interval: u16,
+    /// IEEE 802.11 capability information field
+    pub capabilities: u16,
+    /// Supported and extended supported rates in kbit/s
+    pub supported_rates: Vec<u32>,
+    /// Advertises HT (802.11n) capabilities
+    pub ht: bool,
+    /// Advertises VHT (802.11ac) capabilities
+    pub vht: bool,
+    /// Advertises HE (802.11ax) capabilities
+    pub he: bool,
+    /// WPS state advertised by the AP (if WPS is supported)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wps: Option<WpsState>,
+    /// Security and mode flags (e.g. `WPA2-PSK-CCMP`, `ESS`)
+    pub flags: Vec<String>,
+    /// Seconds since the BSS was last seen
+    pub age_secs: u32,
+    /// Raw information elements (hex-encoded)
+    pub ies: String,
+}
+
+/// WPS state advertised by an access point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WpsState {
+    /// WPS supported, no registration in progress
+    Available,
+    /// Push-button registration active
+    PushButton,
+    /// PIN registration active
+    Pin,
+    /// Enrollee authorized by the registrar
+    Authorized,
+}
+
+/// WiFi frequency band
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Band {
+    #[serde(rename = "2.4ghz")]
+    Band2_4Ghz,
+    #[serde(rename = "5ghz")]
+    Band5Ghz,
+    #[serde(rename = "6ghz")]
+    Band6Ghz,
+}
+
+impl Band {
+    /// Determine the band of a frequency in MHz
+    pub fn from_frequency(freq: u32) -> Option<Self> {
+        match freq {
+            2401..=2495 => Some(Band::Band2_4Ghz),
+            5150..=5925 => Some(Band::Band5Ghz),
+            5926..=7125 => Some(Band::Band6Ghz),
+            _ => None,
+        }
+    }
+
+    /// Channel center frequencies (MHz) of the band
+    pub fn frequencies(self) -> Vec<u32> {
+        match self {
+            Band::Band2_4Ghz => (2412..=2472).step_by(5).chain([2484]).collect(),
+            Band::Band5Ghz => (5180..=5320)
+                .step_by(20)
+                .chain((5500..=5720).step_by(20))
+                .chain((5745..=5885).step_by(20))
+                .collect(),
+            Band::Band6Ghz => (5955..=7115).step_by(20).collect(),
+        }
+    }
+}
+
+/// How scan results are presented
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanResultsQuery {
+    /// Group BSSes by SSID (see `NetworkGroup`)
+    #[serde(default)]
+    pub group: bool,
+    /// Drop BSSes weaker than this (dBm)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_rssi: Option<i16>,
+    /// Only BSSes with this security
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security: Option<Security>,
+    /// Only BSSes on this band
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub band: Option<Band>,
+    /// Only SSIDs containing this text (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+    /// Score BSSes and recommend the best one of each SSID
+    #[serde(default)]
+    pub rank: bool,
+    /// Sort order (backend order, or best score first if ranked, if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ScanSortKey>,
+    /// Number of entries to skip
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of entries to return
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl ScanResultsQuery {
+    /// Check whether a BSS passes the filters
+    pub fn matches(&self, network: &WifiNetwork) -> bool {
+        self.min_rssi.is_none_or(|min| network.rssi >= min)
+            && self
+                .security
+                .is_none_or(|security| network.security == security)
+            && self
+                .band
+                .is_none_or(|band| Band::from_frequency(network.frequency) == Some(band))
+            && self.ssid.as_deref().is_none_or(|text| {
+                network
+                    .ssid
+                    .to_string()
+                    .to_lowercase()
+                    .contains(&text.to_lowercase())
+            })
+    }
+}
+
+/// Sort key for scan results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanSortKey {
+    /// Strongest first
+    Rssi,
+    /// Alphabetical, case-insensitive
+    Ssid,
+    /// Lowest channel first
+    Channel,
+}
+
+/// Optional preferences restricting which BSS a connection may use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectOptions {
+    /// Only associate with this BSS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bssid: Option<String>,
+    /// Only use these frequencies (MHz)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freq_list: Option<Vec<u32>>,
+    /// Only use this band
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub band: Option<Band>,
+}
+
+impl ConnectOptions {
+    /// Validate the options and bring them into canonical form
+    ///
+    /// The BSSID is lowercased and the band is folded into the frequency
+    /// list: without an explicit list all frequencies of the band are used,
+    /// otherwise the list is restricted to the band.
+    pub fn normalized(&self) -> Result<Self, String> {
+        let bssid = match &self.bssid {
+            Some(bssid) => {
+                Some(normalize_bssid(bssid).ok_or_else(|| format!("Invalid BSSID: {}", bssid))?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            bssid,
+            freq_list: fold_band(self.freq_list.as_deref(), self.band)?,
+            band: self.band,
+        })
+    }
+}
+
+/// Validate a frequency list and restrict it to `band`
+fn fold_band(freq_list: Option<&[u32]>, band: Option<Band>) -> Result<Option<Vec<u32>>, String> {
+    if let Some(freq) = freq_list
+        .into_iter()
+        .flatten()
+        .find(|freq| Band::from_frequency(**freq).is_none())
+    {
+        return Err(format!("Invalid frequency: {} MHz", freq));
+    }
+
+    match (freq_list, band) {
+        (Some(freqs), Some(band)) => {
+            let freqs: Vec<u32> = freqs
+                .iter()
+                .copied()
+                .filter(|freq| Band::from_frequency(*freq) == Some(band))
+                .collect();
+            if freqs.is_empty() {
+                return Err("No frequency of freq_list lies in the requested band".to_string());
+            }
+            Ok(Some(freqs))
+        }
+        (Some(freqs), None) => Ok(Some(freqs.to_vec())),
+        (None, Some(band)) => Ok(Some(band.frequencies())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Parameters narrowing down a scan
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanOptions {
+    /// Probe for these SSIDs (needed to find hidden networks)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ssids: Vec<Ssid>,
+    /// Only scan these frequencies (MHz)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freq_list: Option<Vec<u32>>,
+    /// Only scan this band
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub band: Option<Band>,
+    /// Listen for beacons without sending probe requests
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub passive: bool,
+    /// Expected time spent on each channel (ms), a hint for backends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dwell_ms: Option<u32>,
+}
+
+impl ScanOptions {
+    /// Maximum number of SSIDs probed in one scan (wpa_supplicant's limit)
+    pub const MAX_SSIDS: usize = 16;
+    /// Upper bound for `dwell_ms`
+    pub const MAX_DWELL_MS: u32 = 1000;
+
+    /// Validate the options and bring them into canonical form
+    ///
+    /// The band is folded into the frequency list like in
+    /// `ConnectOptions::normalized`.
+    pub fn normalized(&self) -> Result<Self, String> {
+        if self.ssids.len() > Self::MAX_SSIDS {
+            return Err(format!(
+                "At most {} SSIDs can be probed, got {}",
+                Self::MAX_SSIDS,
+                self.ssids.len()
+            ));
+        }
+        if let Some(ssid) = self
+            .ssids
+            .iter()
+            .find(|ssid| ssid.is_empty() || ssid.len() > Ssid::MAX_LEN)
+        {
+            return Err(format!(
+                "SSID must be 1 to {} octets, got {}",
+                Ssid::MAX_LEN,
+                ssid.len()
+            ));
+        }
+        if self.passive && !self.ssids.is_empty() {
+            return Err("Probing SSIDs requires an active scan".to_string());
+        }
+        if let Some(dwell) = self
+            .dwell_ms
+            .filter(|dwell| !(1..=Self::MAX_DWELL_MS).contains(dwell))
+        {
+            return Err(format!(
+                "Dwell time must be 1 to {} ms, got {}",
+                Self::MAX_DWELL_MS,
+                dwell
+            ));
+        }
+
+        Ok(Self {
+            ssids: self.ssids.clone(),
+            freq_list: fold_band(self.freq_list.as_deref(), self.band)?,
+            band: self.band,
+            passive: self.passive,
+            dwell_ms: self.dwell_ms,
+        })
+    }
+
+    /// Check whether a BSS lies on the scanned frequencies
+    ///
+    /// BSSes with unknown frequency are always admitted.
+    pub fn admits(&self, network: &WifiNetwork) -> bool {
+        network.frequency == 0
+            || self
+                .freq_list
+                .as_ref()
+                .is_none_or(|freqs| freqs.contains(&network.frequency))
+    }
+}
+
+/// Credentials received from a DPP configurator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DppConfig {
+    pub ssid: Ssid,
+    /// WPA passphrase (8..63 characters)
+    pub passphrase: Option<String>,
+    /// Raw PSK, sent by configurators instead of a passphrase
+    pub psk: Option<[u8; 32]>,
+}
+
+impl DppConfig {
+    /// PSK to connect with, derived from the passphrase if necessary
+    pub fn psk(&self) -> Option<[u8; 32]> {
+        self.psk.or_else(|| {
+            self.passphrase
+                .as_deref()
+                .map(|passphrase| passphrase_to_psk(passphrase, &self.ssid))
+        })
+    }
+}
+
+/// Derive a WPA PSK from a passphrase (PBKDF2-HMAC-SHA1, 4096 rounds)
+pub fn passphrase_to_psk(passphrase: &str, ssid: &Ssid) -> [u8; 32] {
+    let mut psk = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(passphrase.as_bytes(), ssid.as_bytes(), 4096, &mut psk);
+    psk
+}
+
+/// WPS registration method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WpsMethod {
+    /// Push-button configuration
+    PushButton,
+    /// PIN method; the backend generates a PIN if none is given
+    Pin(Option<String>),
+}
+
+impl WpsMethod {
+    /// Validate the PIN of the PIN method
+    ///
+    /// Accepts 4-digit PINs and 8-digit PINs with a valid checksum digit.
+    pub fn validate(&self) -> Result<(), String> {
+        let WpsMethod::Pin(Some(pin)) = self else {
+            return Ok(());
+        };
+
+        let digits: Vec<u32> = pin.chars().filter_map(|c| c.to_digit(10)).collect();
+        if digits.len() != pin.len() || !matches!(digits.len(), 4 | 8) {
+            return Err(format!("WPS PIN must be 4 or 8 digits, got {:?}", pin));
+        }
+
+        if digits.len() == 8 {
+            let checksum: u32 = digits
+                .iter()
+                .enumerate()
+                .map(|(i, digit)| if i % 2 == 0 { digit * 3 } else { *digit })
+                .sum();
+            if checksum % 10 != 0 {
+                return Err(format!("Invalid WPS PIN checksum: {}", pin));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Normalize a BSSID to lowercase colon-separated form
+///
+/// Returns `None` if the value is not a valid MAC address.
+pub fn normalize_bssid(value: &str) -> Option<String> {
+    let octets: Vec<&str> = value.split(':').collect();
+    let valid = octets.len() == 6
+        && octets
+            .iter()
+            .all(|octet| octet.len() == 2 && octet.chars().all(|c| c.is_ascii_hexdigit()));
+
+    valid.then(|| value.to_ascii_lowercase())
+}
+
+/// Normalize a regulatory country code to upper case
+///
+/// Accepts ISO 3166-1 alpha-2 codes and `00` (world regulatory domain).
+/// Returns `None` for anything else.
+pub fn normalize_country_code(value: &str) -> Option<String> {
+    let valid =
+        value == "00" || (value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic()));
+
+    valid.then(|| value.to_ascii_uppercase())
 }
 
 /// WiFi scan state machine states
@@ -55,6 +710,10 @@ pub enum ConnectionState {
     Connecting = 1,
     Connected = 2,
     Failed = 3,
+    WpsActive = 4,
+    DppActive = 5,
+    /// Credentials stored, pending activation (offline provisioning)
+    Stored = 6,
 }
 
 impl TryFrom<u8> for ConnectionState {
@@ -66,6 +725,9 @@ impl TryFrom<u8> for ConnectionState {
             1 => Ok(ConnectionState::Connecting),
             2 => Ok(ConnectionState::Connected),
             3 => Ok(ConnectionState::Failed),
+            4 => Ok(ConnectionState::WpsActive),
+            5 => Ok(ConnectionState::DppActive),
+            6 => Ok(ConnectionState::Stored),
             _ => Err(()),
         }
     }
@@ -83,9 +745,130 @@ pub struct ConnectionStatus {
     /// Current connection state
     pub state: ConnectionState,
     /// Connected network SSID (if connected)
-    pub ssid: Option<String>,
+    pub ssid: Option<Ssid>,
     /// Assigned IP address (if connected)
     pub ip_address: Option<String>,
+    /// Active regulatory domain (ISO 3166-1 alpha-2, `00` for world)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+/// Features a backend supports
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// WPA3-Personal (SAE)
+    pub sae: bool,
+    /// WPA2/WPA3-Enterprise (802.1X)
+    pub enterprise: bool,
+    /// WPS push-button and PIN registration
+    pub wps: bool,
+    /// DPP enrollee (Easy Connect)
+    pub dpp: bool,
+    /// 6 GHz band
+    pub band_6ghz: bool,
+    /// Credentials survive a restart
+    pub persistence: bool,
+    /// Setting the regulatory domain
+    pub set_country: bool,
+}
+
+impl Capabilities {
+    /// Bit positions of the compact (BLE) representation
+    pub const SAE: u16 = 1 << 0;
+    pub const ENTERPRISE: u16 = 1 << 1;
+    pub const WPS: u16 = 1 << 2;
+    pub const DPP: u16 = 1 << 3;
+    pub const BAND_6GHZ: u16 = 1 << 4;
+    pub const PERSISTENCE: u16 = 1 << 5;
+    pub const SET_COUNTRY: u16 = 1 << 6;
+}
+
+impl From<&Capabilities> for u16 {
+    fn from(caps: &Capabilities) -> Self {
+        [
+            (caps.sae, Capabilities::SAE),
+            (caps.enterprise, Capabilities::ENTERPRISE),
+            (caps.wps, Capabilities::WPS),
+            (caps.dpp, Capabilities::DPP),
+            (caps.band_6ghz, Capabilities::BAND_6GHZ),
+            (caps.persistence, Capabilities::PERSISTENCE),
+            (caps.set_country, Capabilities::SET_COUNTRY),
+        ]
+        .into_iter()
+        .filter(|(supported, _)| *supported)
+        .fold(0, |bits, (_, bit)| bits | bit)
+    }
+}
+
+/// Kind of a network interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterfaceKind {
+    Loopback,
+    Ethernet,
+    Wifi,
+    /// WWAN modem (LTE/5G) or point-to-point link
+    Cellular,
+    Bridge,
+    /// Software device (VLAN, tunnel, veth, ...)
+    Virtual,
+    Other,
+}
+
+/// Operational state of a network interface (RFC 2863, simplified)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperState {
+    Up,
+    /// Link is up but waiting for an external event (e.g. 802.1X)
+    Dormant,
+    Down,
+    /// Not reported by the driver, common for loopback and modems
+    Unknown,
+}
+
+/// Network interface as reported by the kernel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: InterfaceKind,
+    /// Hardware address (absent for links without one)
+    pub mac: Option<String>,
+    pub state: OperState,
+    /// Addresses in CIDR notation
+    pub addresses: Vec<String>,
+    /// Owns a default route of the main routing table
+    pub default_route: bool,
+}
+
+impl NetworkInterface {
+    /// Whether traffic can leave the device through this interface
+    pub fn is_online(&self) -> bool {
+        self.default_route && matches!(self.state, OperState::Up | OperState::Unknown)
+    }
+
+    /// Compact (BLE) representation: space-separated name, type, state, MAC,
+    /// `default` flag and comma-separated addresses, `-` for absent values
+    pub fn compact(&self) -> String {
+        let kind = serde_json::to_value(self.kind).unwrap_or_default();
+        let state = serde_json::to_value(self.state).unwrap_or_default();
+        let addresses = if self.addresses.is_empty() {
+            "-".to_string()
+        } else {
+            self.addresses.join(",")
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            self.name,
+            kind.as_str().unwrap_or("-"),
+            state.as_str().unwrap_or("-"),
+            self.mac.as_deref().unwrap_or("-"),
+            if self.default_route { "default" } else { "-" },
+            addresses
+        )
+    }
 }
 
 /// Authorization state
@@ -127,3 +910,282 @@ impl std::fmt::Display for SessionId {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_options_normalized() {
+        let options: ScanOptions =
+            serde_json::from_str(r#"{"ssids":["Hidden"],"band":"2.4ghz","dwell_ms":50}"#).unwrap();
+        let options = options.normalized().unwrap();
+        assert_eq!(options.ssids, [Ssid::from("Hidden")]);
+        assert_eq!(options.freq_list, Some(Band::Band2_4Ghz.frequencies()));
+        assert_eq!(options.dwell_ms, Some(50));
+
+        let options = ScanOptions {
+            freq_list: Some(vec![2412, 5180]),
+            band: Some(Band::Band5Ghz),
+            ..Default::default()
+        };
+        assert_eq!(options.normalized().unwrap().freq_list, Some(vec![5180]));
+
+        for invalid in [
+            r#"{"ssids":[""]}"#,
+            r#"{"ssids":["Hidden"],"passive":true}"#,
+            r#"{"freq_list":[1234]}"#,
+            r#"{"freq_list":[2412],"band":"6ghz"}"#,
+            r#"{"dwell_ms":0}"#,
+            r#"{"dwell_ms":5000}"#,
+        ] {
+            let options: ScanOptions = serde_json::from_str(invalid).unwrap();
+            assert!(options.normalized().is_err(), "{}", invalid);
+        }
+        let options = ScanOptions {
+            ssids: vec![Ssid::from("x"); ScanOptions::MAX_SSIDS + 1],
+            ..Default::default()
+        };
+        assert!(options.normalized().is_err());
+    }
+
+    #[test]
+    fn test_scan_options_admits() {
+        let options = ScanOptions {
+            freq_list: Some(vec![2412]),
+            ..Default::default()
+        };
+        let network = |frequency| WifiNetwork {
+            frequency,
+            ..Default::default()
+        };
+        assert!(options.admits(&network(2412)));
+        assert!(options.admits(&network(0)));
+        assert!(!options.admits(&network(5180)));
+        assert!(ScanOptions::default().admits(&network(5180)));
+    }
+
+    #[test]
+    fn test_scan_results_query_matches() {
+        let network = WifiNetwork {
+            ssid: "Office-5G".into(),
+            frequency: 5180,
+            rssi: -60,
+            security: Security::Wpa2,
+            ..Default::default()
+        };
+
+        assert!(ScanResultsQuery::default().matches(&network));
+        let query: ScanResultsQuery = serde_json::from_str(
+            r#"{"min_rssi":-60,"security":"wpa2","band":"5ghz","ssid":"office"}"#,
+        )
+        .unwrap();
+        assert!(query.matches(&network));
+
+        for query in [
+            r#"{"min_rssi":-59}"#,
+            r#"{"security":"wpa3"}"#,
+            r#"{"band":"2.4ghz"}"#,
+            r#"{"ssid":"home"}"#,
+        ] {
+            let query: ScanResultsQuery = serde_json::from_str(query).unwrap();
+            assert!(!query.matches(&network), "{:?}", query);
+        }
+    }
+
+    #[test]
+    fn test_security_from_flags() {
+        assert_eq!(Security::from_flags::<&str>(&[]), Security::Open);
+        assert_eq!(Security::from_flags(&["ESS"]), Security::Open);
+        assert_eq!(Security::from_flags(&["WEP", "ESS"]), Security::Wep);
+        assert_eq!(Security::from_flags(&["WPA-PSK-TKIP"]), Security::Wpa);
+        assert_eq!(
+            Security::from_flags(&["WPA-PSK-CCMP+TKIP", "WPA2-PSK-CCMP+TKIP", "ESS"]),
+            Security::Wpa2
+        );
+        assert_eq!(
+            Security::from_flags(&["WPA2-PSK+SAE-CCMP", "ESS"]),
+            Security::Wpa2
+        );
+        assert_eq!(Security::from_flags(&["WPA2-SAE-CCMP"]), Security::Wpa3);
+        assert_eq!(
+            Security::from_flags(&["WPA2-EAP-CCMP"]),
+            Security::Enterprise
+        );
+        assert_eq!(Security::from_flags(&["RSN-OWE-CCMP"]), Security::Owe);
+        assert_eq!(Security::from_flags(&["OWE-TRANS"]), Security::Open);
+    }
+
+    #[test]
+    fn test_ssid_utf8_serializes_as_string() {
+        let ssid = Ssid::from("MyWiFi💩");
+        assert_eq!(serde_json::to_string(&ssid).unwrap(), r#""MyWiFi💩""#);
+
+        let deserialized: Ssid = serde_json::from_str(r#""MyWiFi💩""#).unwrap();
+        assert_eq!(deserialized, ssid);
+    }
+
+    #[test]
+    fn test_ssid_non_utf8_serializes_as_hex() {
+        let ssid = Ssid::new(vec![0x43, 0x61, 0x66, 0xe9]);
+        let json = serde_json::to_string(&ssid).unwrap();
+        assert_eq!(json, r#"{"hex":"436166e9"}"#);
+
+        let deserialized: Ssid = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, ssid);
+        assert_eq!(ssid.to_string(), "Caf\u{fffd}");
+    }
+
+    #[test]
+    fn test_normalize_bssid() {
+        assert_eq!(
+            normalize_bssid("AA:BB:CC:dd:ee:ff"),
+            Some("aa:bb:cc:dd:ee:ff".to_string())
+        );
+        assert_eq!(normalize_bssid("aa:bb:cc:dd:ee"), None);
+        assert_eq!(normalize_bssid("aa:bb:cc:dd:ee:gg"), None);
+        assert_eq!(normalize_bssid("aabbccddeeff"), None);
+    }
+
+    #[test]
+    fn test_band_from_frequency() {
+        assert_eq!(Band::from_frequency(2412), Some(Band::Band2_4Ghz));
+        assert_eq!(Band::from_frequency(2484), Some(Band::Band2_4Ghz));
+        assert_eq!(Band::from_frequency(5180), Some(Band::Band5Ghz));
+        assert_eq!(Band::from_frequency(5955), Some(Band::Band6Ghz));
+        assert_eq!(Band::from_frequency(900), None);
+
+        assert!(Band::Band2_4Ghz.frequencies().contains(&2484));
+        assert!(Band::Band5Ghz.frequencies().contains(&5825));
+        assert_eq!(
+            serde_json::to_string(&Band::Band2_4Ghz).unwrap(),
+            r#""2.4ghz""#
+        );
+    }
+
+    #[test]
+    fn test_connect_options_normalized() {
+        let options = ConnectOptions {
+            bssid: Some("AA:BB:CC:DD:EE:FF".to_string()),
+            freq_list: Some(vec![2412, 5180, 5200]),
+            band: Some(Band::Band5Ghz),
+        }
+        .normalized()
+        .unwrap();
+
+        assert_eq!(options.bssid.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(options.freq_list, Some(vec![5180, 5200]));
+
+        // Band alone expands to all channels of the band
+        let options = ConnectOptions {
+            band: Some(Band::Band2_4Ghz),
+            ..Default::default()
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(options.freq_list.unwrap().len(), 14);
+    }
+
+    #[test]
+    fn test_connect_options_invalid() {
+        let invalid_bssid = ConnectOptions {
+            bssid: Some("aa:bb".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid_bssid.normalized().is_err());
+
+        let invalid_freq = ConnectOptions {
+            freq_list: Some(vec![1234]),
+            ..Default::default()
+        };
+        assert!(invalid_freq.normalized().is_err());
+
+        let band_mismatch = ConnectOptions {
+            freq_list: Some(vec![2412]),
+            band: Some(Band::Band5Ghz),
+            ..Default::default()
+        };
+        assert!(band_mismatch.normalized().is_err());
+    }
+
+    #[test]
+    fn test_passphrase_to_psk() {
+        // IEEE 802.11i test vector
+        let psk = passphrase_to_psk("password", &"IEEE".into());
+        assert_eq!(
+            hex::encode(psk),
+            "f42c6fc52df0ebef9ebb4b90b38a5f902e83fe1b135a70e23aed762e9710a12e"
+        );
+
+        let config = DppConfig {
+            ssid: "IEEE".into(),
+            passphrase: Some("password".to_string()),
+            psk: None,
+        };
+        assert_eq!(config.psk(), Some(psk));
+
+        let config = DppConfig {
+            ssid: "IEEE".into(),
+            passphrase: None,
+            psk: None,
+        };
+        assert_eq!(config.psk(), None);
+    }
+
+    #[test]
+    fn test_normalize_country_code() {
+        assert_eq!(normalize_country_code("de"), Some("DE".to_string()));
+        assert_eq!(normalize_country_code("US"), Some("US".to_string()));
+        assert_eq!(normalize_country_code("00"), Some("00".to_string()));
+        assert_eq!(normalize_country_code("D"), None);
+        assert_eq!(normalize_country_code("DEU"), None);
+        assert_eq!(normalize_country_code("1A"), None);
+    }
+
+    #[test]
+    fn test_wps_pin_validation() {
+        assert!(WpsMethod::PushButton.validate().is_ok());
+        assert!(WpsMethod::Pin(None).validate().is_ok());
+        assert!(
+            WpsMethod::Pin(Some("12345670".to_string()))
+                .validate()
+                .is_ok()
+        );
+        assert!(WpsMethod::Pin(Some("1234".to_string())).validate().is_ok());
+
+        // Wrong checksum, wrong length, non-digits
+        assert!(
+            WpsMethod::Pin(Some("12345678".to_string()))
+                .validate()
+                .is_err()
+        );
+        assert!(
+            WpsMethod::Pin(Some("123456".to_string()))
+                .validate()
+                .is_err()
+        );
+        assert!(
+            WpsMethod::Pin(Some("1234567a".to_string()))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_ssid_invalid_hex_rejected() {
+        assert!(serde_json::from_str::<Ssid>(r#"{"hex":"zz"}"#).is_err());
+    }
+
+    #[test]
+    fn test_capabilities_bits() {
+        assert_eq!(u16::from(&Capabilities::default()), 0);
+
+        let caps = Capabilities {
+            sae: true,
+            dpp: true,
+            set_country: true,
+            ..Default::default()
+        };
+        assert_eq!(u16::from(&caps), 0b100_1001);
+    }
+}
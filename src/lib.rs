@@ -12,5 +12,5 @@ pub mod transport;
 
 pub use core::{
     error::{ServiceError, TransportError, WifiError},
-    types::{ConnectionState, ConnectionStatus, ScanState, WifiNetwork},
+    types::{ConnectionState, ConnectionStatus, ScanState, Ssid, WifiNetwork},
 };
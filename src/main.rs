@@ -1,13 +1,16 @@
 //! WiFi Commissioning Service - Main Entry Point
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use clap::Parser;
 use tracing::{error, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use wifi_commissioning_service::{
-    backend::WpactrlBackend,
-    config::CliArgs,
+    backend::{
+        ConfigFileBackend, ConfigFormat, IwdBackend, MockWifiBackend, NetworkManagerBackend,
+        WifiBackend, WpactrlBackend, mock_backend::DEMO_PASSPHRASE,
+    },
+    config::{BackendKind, CliArgs},
     core::service::WifiCommissioningService,
     transport::{ble::BleAdapter, unix_socket::UnixSocketServer},
 };
@@ -24,7 +27,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .init();
 
     // Parse CLI arguments
-    let args = CliArgs::parse();
+    let args = CliArgs::parse().resolved();
     info!(?args, "Starting WiFi commissioning service");
 
     // Validate configuration
@@ -38,18 +41,95 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Err("BLE secret not provided".into());
     }
 
-    // Create WiFi backend
-    let backend = Arc::new(WpactrlBackend::new(args.interface.clone()));
-    info!("WiFi backend initialized for interface: {}", args.interface);
+    // Create one WiFi backend per interface
+    match args.backend {
+        BackendKind::Wpactrl => {
+            let backends = backends(&args, |iface| WpactrlBackend::new(iface.to_string()));
+            run(backends, args).await
+        }
+        BackendKind::NetworkManager => {
+            let backends = backends(&args, |iface| NetworkManagerBackend::new(iface.to_string()));
+            run(backends, args).await
+        }
+        BackendKind::Iwd => {
+            let backends = backends(&args, |iface| IwdBackend::new(iface.to_string()));
+            run(backends, args).await
+        }
+        BackendKind::ConfigFile => {
+            // iwd profiles are shared, but one wpa_supplicant file per interface
+            if args.config_path.is_some()
+                && args.config_format == ConfigFormat::WpaSupplicant
+                && args.interfaces.len() > 1
+            {
+                error!("--config-path needs a single interface with the wpa_supplicant format");
+                return Err("Ambiguous config path".into());
+            }
+            let backends = backends(&args, |iface| {
+                let path = args
+                    .config_path
+                    .clone()
+                    .unwrap_or_else(|| args.config_format.default_path(iface));
+                info!("Storing credentials for {} in {}", iface, path.display());
+                ConfigFileBackend::new(args.config_format, path, args.scan_cache.clone())
+            });
+            run(backends, args).await
+        }
+        BackendKind::Mock => {
+            info!(
+                "Simulating WiFi networks, all use the passphrase \"{}\"",
+                DEMO_PASSPHRASE
+            );
+            let mut backends = Vec::new();
+            for iface in &args.interfaces {
+                backends.push((iface.clone(), Arc::new(MockWifiBackend::demo().await)));
+            }
+            run(backends, args).await
+        }
+    }
+}
+
+/// Create a backend for each configured interface
+fn backends<B>(args: &CliArgs, mut create: impl FnMut(&str) -> B) -> Vec<(String, Arc<B>)> {
+    args.interfaces
+        .iter()
+        .map(|iface| (iface.clone(), Arc::new(create(iface))))
+        .collect()
+}
+
+/// Run the service and its transports on top of one backend per interface
+async fn run<B: WifiBackend + 'static>(
+    backends: Vec<(String, Arc<B>)>,
+    args: CliArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!(
+        "{:?} backend initialized for interfaces: {}",
+        args.backend,
+        args.interfaces.join(", ")
+    );
 
     // Create WiFi commissioning service
     let secret = args
         .ble_secret
         .clone()
         .unwrap_or_else(|| "default".to_string());
-    let service = Arc::new(WifiCommissioningService::new(backend, secret));
+    let service = Arc::new(WifiCommissioningService::with_interfaces(backends, secret));
     info!("WiFi commissioning service created");
 
+    let policy = args.scan_policy();
+    for interface in service.interfaces() {
+        interface.scanner.set_policy(policy.clone()).await;
+    }
+
+    if args.background_scan > 0 {
+        let interval = Duration::from_secs(args.background_scan);
+        for interface in service.interfaces() {
+            interface
+                .scanner
+                .set_background_interval(Some(interval))
+                .await;
+        }
+    }
+
     // Start configured transports
     let mut tasks = Vec::new();
 
@@ -59,8 +139,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         let server = UnixSocketServer::new(
             args.socket_path.clone(),
-            service.scanner.clone(),
-            service.connector.clone(),
+            service.interfaces().to_vec(),
             service.authorization.clone(),
         );
 
@@ -128,8 +207,8 @@ async fn shutdown_signal() {
     std::future::pending::<()>().await
 }
 
-async fn start_ble_transport(
-    service: Arc<WifiCommissioningService<WpactrlBackend>>,
+async fn start_ble_transport<B: WifiBackend + 'static>(
+    service: Arc<WifiCommissioningService<B>>,
 ) -> Result<tokio::task::JoinHandle<()>, Box<dyn std::error::Error>> {
     use wifi_commissioning_service::transport::ble::GattServer;
 
@@ -1,19 +1,37 @@
 //! JSON-RPC 2.0 message envelope
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-use crate::protocol::{notification::Notification, request::Request, response::Response};
+use crate::{
+    core::scan_policy::ScanRefusal,
+    protocol::{notification::Notification, request::Request, response::Response},
+};
 
 /// JSON-RPC 2.0 request wrapper
+///
+/// Every method accepts an optional `interface` member in `params` naming the
+/// interface the request applies to. It is split off before the method's own
+/// params are parsed, so `"params": {"interface": "wlan1"}` is valid for
+/// methods without params.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "Value", into = "Value")]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
-    #[serde(flatten)]
     pub request: Request,
+    pub interface: Option<String>,
     pub id: RequestId,
 }
 
+/// Wire shape of a request without the `interface` param
+#[derive(Serialize, Deserialize)]
+struct RawJsonRpcRequest {
+    jsonrpc: String,
+    #[serde(flatten)]
+    request: Request,
+    id: RequestId,
+}
+
 /// JSON-RPC 2.0 response wrapper
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct JsonRpcResponse {
@@ -64,6 +82,7 @@ impl JsonRpcError {
     pub const INVALID_STATE: i32 = -32002;
     pub const BACKEND_ERROR: i32 = -32003;
     pub const TIMEOUT: i32 = -32004;
+    pub const SCAN_REFUSED: i32 = -32005;
 
     pub fn parse_error() -> Self {
         Self {
@@ -113,6 +132,15 @@ impl JsonRpcError {
         }
     }
 
+    /// Scan refused by the scan policy, `data` holds the reason
+    pub fn scan_refused(refusal: &ScanRefusal) -> Self {
+        Self {
+            code: Self::SCAN_REFUSED,
+            message: refusal.to_string(),
+            data: serde_json::to_value(refusal).ok(),
+        }
+    }
+
     pub fn invalid_state(message: impl Into<String>) -> Self {
         Self {
             code: Self::INVALID_STATE,
@@ -143,9 +171,69 @@ impl JsonRpcRequest {
         Self {
             jsonrpc: "2.0".to_string(),
             request,
+            interface: None,
             id,
         }
     }
+
+    /// Direct the request to a specific interface
+    pub fn with_interface(mut self, interface: impl Into<String>) -> Self {
+        self.interface = Some(interface.into());
+        self
+    }
+}
+
+impl TryFrom<Value> for JsonRpcRequest {
+    type Error = String;
+
+    fn try_from(mut value: Value) -> Result<Self, Self::Error> {
+        let mut interface = None;
+        if let Some(object) = value.as_object_mut() {
+            if let Some(Value::Object(params)) = object.get_mut("params") {
+                interface = match params.remove("interface") {
+                    None => None,
+                    Some(Value::String(name)) => Some(name),
+                    Some(other) => return Err(format!("Invalid interface: {}", other)),
+                };
+                if params.is_empty() {
+                    object.remove("params");
+                }
+            }
+        }
+
+        let raw: RawJsonRpcRequest = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok(Self {
+            jsonrpc: raw.jsonrpc,
+            request: raw.request,
+            interface,
+            id: raw.id,
+        })
+    }
+}
+
+impl From<JsonRpcRequest> for Value {
+    fn from(request: JsonRpcRequest) -> Self {
+        let raw = RawJsonRpcRequest {
+            jsonrpc: request.jsonrpc,
+            request: request.request,
+            id: request.id,
+        };
+        let mut value = serde_json::to_value(raw).expect("request serializes to JSON");
+
+        if let (Some(interface), Some(object)) = (request.interface, value.as_object_mut()) {
+            let params = object
+                .entry("params")
+                .or_insert_with(|| Value::Object(Map::new()));
+            if params.is_null() {
+                *params = Value::Object(Map::new());
+            }
+            if let Value::Object(params) = params {
+                params.insert("interface".to_string(), Value::String(interface));
+            }
+        }
+
+        value
+    }
 }
 
 impl JsonRpcResponse {
@@ -187,7 +275,7 @@ mod tests {
 
     #[test]
     fn test_jsonrpc_request_serialization() {
-        let request = JsonRpcRequest::new(Request::Scan, RequestId::Number(1));
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(1));
         let json = serde_json::to_string(&request).unwrap();
 
         assert!(json.contains(r#""jsonrpc":"2.0""#));
@@ -198,9 +286,48 @@ mod tests {
         assert_eq!(deserialized, request);
     }
 
+    #[test]
+    fn test_jsonrpc_request_interface() {
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"scan","params":{"interface":"wlan1"},"id":1}"#,
+        )
+        .unwrap();
+        assert_eq!(request.request, Request::Scan(None));
+        assert_eq!(request.interface.as_deref(), Some("wlan1"));
+
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"get_network_details","params":{"bssid":"aa:bb:cc:dd:ee:ff","interface":"wlan1"},"id":2}"#,
+        )
+        .unwrap();
+        assert!(matches!(request.request, Request::GetNetworkDetails(_)));
+        assert_eq!(request.interface.as_deref(), Some("wlan1"));
+
+        assert!(
+            serde_json::from_str::<JsonRpcRequest>(
+                r#"{"jsonrpc":"2.0","method":"scan","params":{"interface":1},"id":3}"#
+            )
+            .is_err()
+        );
+
+        // Round trip, including methods with optional params
+        for request in [Request::Scan(None), Request::WpsPbc(None)] {
+            let request =
+                JsonRpcRequest::new(request, RequestId::Number(4)).with_interface("wlan1");
+            let json = serde_json::to_string(&request).unwrap();
+            assert!(json.contains(r#""interface":"wlan1""#));
+            assert_eq!(
+                serde_json::from_str::<JsonRpcRequest>(&json).unwrap(),
+                request
+            );
+        }
+    }
+
     #[test]
     fn test_jsonrpc_request_with_string_id() {
-        let request = JsonRpcRequest::new(Request::Scan, RequestId::String("abc-123".to_string()));
+        let request = JsonRpcRequest::new(
+            Request::Scan(None),
+            RequestId::String("abc-123".to_string()),
+        );
         let json = serde_json::to_string(&request).unwrap();
 
         assert!(json.contains(r#""id":"abc-123""#));
@@ -209,7 +336,7 @@ mod tests {
     #[test]
     fn test_jsonrpc_response_success() {
         let response = JsonRpcResponse::success(
-            Response::ScanStarted(ScanStartedResponse::ok(ScanState::Scanning)),
+            Response::ScanStarted(ScanStartedResponse::ok(ScanState::Scanning, false)),
             RequestId::Number(1),
         );
         let json = serde_json::to_string(&response).unwrap();
@@ -8,9 +8,14 @@ pub mod response;
 pub use {
     jsonrpc::{JsonRpcError, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, RequestId},
     notification::{ConnectionStateChangedParams, Notification, ScanStateChangedParams},
-    request::{ConnectParams, Request},
+    request::{
+        ConnectParams, CountryParams, NetworkDetailsParams, Request, SurveyParams, WpsParams,
+        WpsPinParams,
+    },
     response::{
-        ConnectResponse, DisconnectResponse, Response, ScanResultsResponse, ScanStartedResponse,
-        StatusResponse,
+        CapabilitiesResponse, ConnectResponse, CountryResponse, DisconnectResponse, DppResponse,
+        GroupedScanResultsResponse, InterfacesResponse, NetworkDetailsResponse, OkResponse,
+        Response, ScanResultsResponse, ScanStartedResponse, SiteSurveyResponse, StatusResponse,
+        WpsResponse,
     },
 };
@@ -2,20 +2,58 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::types::{ConnectOptions, ScanOptions, ScanResultsQuery, Ssid};
+
 /// Request messages from client to server
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(tag = "method", content = "params")]
 #[serde(rename_all = "snake_case")]
 pub enum Request {
     /// Start a WiFi scan
-    Scan,
+    Scan(Option<ScanOptions>),
+
+    /// Cancel the running scan
+    CancelScan,
 
     /// Get scan results
-    GetScanResults,
+    GetScanResults(Option<ScanResultsQuery>),
+
+    /// Get detailed information about a single BSS
+    GetNetworkDetails(NetworkDetailsParams),
+
+    /// Aggregate repeated scans into per-channel statistics
+    SiteSurvey(Option<SurveyParams>),
 
     /// Connect to a WiFi network
     Connect(ConnectParams),
 
+    /// Start WPS push-button registration
+    WpsPbc(Option<WpsParams>),
+
+    /// Start WPS PIN registration
+    WpsPin(Option<WpsPinParams>),
+
+    /// Cancel a running WPS registration
+    WpsCancel,
+
+    /// Start DPP enrollee and return the bootstrap URI
+    DppStart,
+
+    /// Stop DPP enrollee
+    DppStop,
+
+    /// Get the regulatory domain
+    GetCountry,
+
+    /// Set the regulatory domain
+    SetCountry(CountryParams),
+
+    /// Get the features supported by the backend
+    GetCapabilities,
+
+    /// List all network interfaces of the device and whether it is online
+    ListInterfaces,
+
     /// Disconnect from current network
     Disconnect,
 
@@ -23,14 +61,64 @@ pub enum Request {
     GetStatus,
 }
 
+/// Parameters for get_network_details request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkDetailsParams {
+    /// MAC address of the BSS
+    pub bssid: String,
+}
+
+/// Parameters for site_survey request
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SurveyParams {
+    /// Number of scans (`DEFAULT_SURVEY_SCANS` if absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scans: Option<u32>,
+}
+
+/// Parameters for wps_pbc request
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WpsParams {
+    /// Restrict registration to this AP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bssid: Option<String>,
+}
+
+/// Parameters for wps_pin request
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WpsPinParams {
+    /// Restrict registration to this AP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bssid: Option<String>,
+
+    /// PIN to use (generated by the backend if absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+}
+
+/// Parameters for set_country request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CountryParams {
+    /// ISO 3166-1 alpha-2 country code, or `00` for the world domain
+    pub country: String,
+
+    /// Store the country code in the backend configuration
+    #[serde(default)]
+    pub persist: bool,
+}
+
 /// Parameters for connect request
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ConnectParams {
-    /// Network SSID
-    pub ssid: String,
+    /// Network SSID (string, or `{"hex": "..."}` for non-UTF-8 SSIDs)
+    pub ssid: Ssid,
 
     /// Pre-shared key (hex-encoded 32 bytes = 64 hex chars)
     pub psk: String,
+
+    /// Optional BSSID, frequency list and band preferences
+    #[serde(flatten)]
+    pub options: ConnectOptions,
 }
 
 impl ConnectParams {
@@ -60,26 +148,138 @@ mod tests {
 
     #[test]
     fn test_request_scan_serialization() {
-        let request = Request::Scan;
+        let request = Request::Scan(None);
         let json = serde_json::to_string(&request).unwrap();
-        assert_eq!(json, r#"{"method":"scan"}"#);
+        assert_eq!(json, r#"{"method":"scan","params":null}"#);
 
-        let deserialized: Request = serde_json::from_str(&json).unwrap();
+        let deserialized: Request = serde_json::from_str(r#"{"method":"scan"}"#).unwrap();
         assert_eq!(deserialized, request);
     }
 
+    #[test]
+    fn test_request_scan_options() {
+        let request: Request = serde_json::from_str(
+            r#"{"method":"scan","params":{"ssids":["Hidden"],"band":"5ghz","dwell_ms":40}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            Request::Scan(Some(ScanOptions {
+                ssids: vec!["Hidden".into()],
+                band: Some(crate::core::types::Band::Band5Ghz),
+                dwell_ms: Some(40),
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_request_cancel_scan() {
+        let json = serde_json::to_string(&Request::CancelScan).unwrap();
+        assert_eq!(json, r#"{"method":"cancel_scan"}"#);
+
+        let request: Request = serde_json::from_str(&json).unwrap();
+        assert_eq!(request, Request::CancelScan);
+    }
+
     #[test]
     fn test_request_get_scan_results() {
-        let request = Request::GetScanResults;
+        let request = Request::GetScanResults(None);
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"method":"get_scan_results","params":null}"#);
+
+        let request: Request = serde_json::from_str(r#"{"method":"get_scan_results"}"#).unwrap();
+        assert_eq!(request, Request::GetScanResults(None));
+
+        let request: Request =
+            serde_json::from_str(r#"{"method":"get_scan_results","params":{"group":true}}"#)
+                .unwrap();
+        assert_eq!(
+            request,
+            Request::GetScanResults(Some(ScanResultsQuery {
+                group: true,
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_request_get_network_details() {
+        let request = Request::GetNetworkDetails(NetworkDetailsParams {
+            bssid: "aa:bb:cc:dd:ee:ff".to_string(),
+        });
         let json = serde_json::to_string(&request).unwrap();
-        assert_eq!(json, r#"{"method":"get_scan_results"}"#);
+        assert_eq!(
+            json,
+            r#"{"method":"get_network_details","params":{"bssid":"aa:bb:cc:dd:ee:ff"}}"#
+        );
+
+        let deserialized: Request = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, request);
+    }
+
+    #[test]
+    fn test_request_site_survey() {
+        let request: Request = serde_json::from_str(r#"{"method":"site_survey"}"#).unwrap();
+        assert_eq!(request, Request::SiteSurvey(None));
+
+        let request: Request =
+            serde_json::from_str(r#"{"method":"site_survey","params":{"scans":5}}"#).unwrap();
+        assert_eq!(
+            request,
+            Request::SiteSurvey(Some(SurveyParams { scans: Some(5) }))
+        );
+    }
+
+    #[test]
+    fn test_request_wps() {
+        let request: Request = serde_json::from_str(r#"{"method":"wps_pbc"}"#).unwrap();
+        assert_eq!(request, Request::WpsPbc(None));
+
+        let request: Request = serde_json::from_str(
+            r#"{"method":"wps_pin","params":{"bssid":"aa:bb:cc:dd:ee:ff","pin":"12345670"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            Request::WpsPin(Some(WpsPinParams {
+                bssid: Some("aa:bb:cc:dd:ee:ff".to_string()),
+                pin: Some("12345670".to_string()),
+            }))
+        );
+
+        let request: Request = serde_json::from_str(r#"{"method":"wps_cancel"}"#).unwrap();
+        assert_eq!(request, Request::WpsCancel);
+    }
+
+    #[test]
+    fn test_request_dpp() {
+        let request: Request = serde_json::from_str(r#"{"method":"dpp_start"}"#).unwrap();
+        assert_eq!(request, Request::DppStart);
+
+        let request: Request = serde_json::from_str(r#"{"method":"dpp_stop"}"#).unwrap();
+        assert_eq!(request, Request::DppStop);
+    }
+
+    #[test]
+    fn test_request_set_country() {
+        let request: Request =
+            serde_json::from_str(r#"{"method":"set_country","params":{"country":"de"}}"#).unwrap();
+        assert_eq!(
+            request,
+            Request::SetCountry(CountryParams {
+                country: "de".to_string(),
+                persist: false,
+            })
+        );
     }
 
     #[test]
     fn test_request_connect_serialization() {
         let request = Request::Connect(ConnectParams {
-            ssid: "MyNetwork".to_string(),
+            ssid: "MyNetwork".into(),
             psk: "a".repeat(64),
+            options: ConnectOptions::default(),
         });
 
         let json = serde_json::to_string(&request).unwrap();
@@ -90,6 +290,49 @@ mod tests {
         assert_eq!(deserialized, request);
     }
 
+    #[test]
+    fn test_request_connect_with_options() {
+        let json = format!(
+            r#"{{"method":"connect","params":{{"ssid":"Warehouse","psk":"{}","bssid":"aa:bb:cc:dd:ee:ff","band":"5ghz"}}}}"#,
+            "a".repeat(64)
+        );
+
+        let request: Request = serde_json::from_str(&json).unwrap();
+        let Request::Connect(params) = request else {
+            panic!("expected connect request");
+        };
+        assert_eq!(params.options.bssid.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(
+            params.options.band,
+            Some(crate::core::types::Band::Band5Ghz)
+        );
+        assert_eq!(params.options.freq_list, None);
+
+        // Options are omitted when unset
+        let json = serde_json::to_string(&Request::Connect(ConnectParams {
+            ssid: "Warehouse".into(),
+            psk: "a".repeat(64),
+            options: ConnectOptions::default(),
+        }))
+        .unwrap();
+        assert!(!json.contains("bssid"));
+        assert!(!json.contains("band"));
+    }
+
+    #[test]
+    fn test_request_connect_hex_ssid() {
+        let json = format!(
+            r#"{{"method":"connect","params":{{"ssid":{{"hex":"ff00"}},"psk":"{}"}}}}"#,
+            "a".repeat(64)
+        );
+
+        let request: Request = serde_json::from_str(&json).unwrap();
+        let Request::Connect(params) = request else {
+            panic!("expected connect request");
+        };
+        assert_eq!(params.ssid.as_bytes(), &[0xff, 0x00]);
+    }
+
     #[test]
     fn test_request_disconnect() {
         let request = Request::Disconnect;
@@ -107,8 +350,9 @@ mod tests {
     #[test]
     fn test_connect_params_decode_psk_valid() {
         let params = ConnectParams {
-            ssid: "test".to_string(),
+            ssid: "test".into(),
             psk: "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string(),
+            options: ConnectOptions::default(),
         };
 
         let decoded = params.decode_psk().unwrap();
@@ -120,8 +364,9 @@ mod tests {
     #[test]
     fn test_connect_params_decode_psk_invalid_length() {
         let params = ConnectParams {
-            ssid: "test".to_string(),
+            ssid: "test".into(),
             psk: "abc".to_string(),
+            options: ConnectOptions::default(),
         };
 
         assert!(params.decode_psk().is_err());
@@ -130,8 +375,9 @@ mod tests {
     #[test]
     fn test_connect_params_decode_psk_invalid_hex() {
         let params = ConnectParams {
-            ssid: "test".to_string(),
+            ssid: "test".into(),
             psk: "z".repeat(64),
+            options: ConnectOptions::default(),
         };
 
         assert!(params.decode_psk().is_err());
@@ -2,7 +2,13 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::core::types::{ConnectionState, ConnectionStatus, ScanState, WifiNetwork};
+use crate::core::{
+    survey::SurveyReport,
+    types::{
+        BssDetails, Capabilities, ConnectionState, ConnectionStatus, NetworkGroup,
+        NetworkInterface, ScanState, WifiNetwork,
+    },
+};
 
 /// Response messages from server to client
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -14,12 +20,42 @@ pub enum Response {
     /// Scan results response
     ScanResults(ScanResultsResponse),
 
+    /// Scan results grouped by SSID response
+    GroupedScanResults(GroupedScanResultsResponse),
+
+    /// Network details response
+    NetworkDetails(NetworkDetailsResponse),
+
+    /// Site survey response
+    SiteSurvey(SiteSurveyResponse),
+
+    /// DPP started response
+    Dpp(DppResponse),
+
     /// Connect response
     Connect(ConnectResponse),
 
+    /// WPS started response
+    Wps(WpsResponse),
+
+    /// Country response
+    Country(CountryResponse),
+
+    /// Capabilities response
+    Capabilities(CapabilitiesResponse),
+
+    /// Network interfaces response
+    Interfaces(InterfacesResponse),
+
     /// Disconnect response
     Disconnect(DisconnectResponse),
 
+    /// Response of requests without a result
+    ///
+    /// Serializes like `Disconnect`, so it is never produced by
+    /// deserialization.
+    Ok(OkResponse),
+
     /// Status response
     Status(StatusResponse),
 }
@@ -29,6 +65,13 @@ pub enum Response {
 pub struct ScanStartedResponse {
     pub status: String,
     pub state: ScanState,
+    /// The request joined a scan that was already running
+    #[serde(default)]
+    pub joined: bool,
+    /// No scan was started inside the minimum scan interval, the cached
+    /// results stand in for it
+    #[serde(default)]
+    pub cached: bool,
 }
 
 /// Response for get_scan_results request
@@ -36,6 +79,33 @@ pub struct ScanStartedResponse {
 pub struct ScanResultsResponse {
     pub status: String,
     pub networks: Vec<WifiNetwork>,
+    /// Number of networks matching the filters, before offset and limit
+    #[serde(default)]
+    pub total: usize,
+}
+
+/// Response for get_scan_results request with `group` set
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GroupedScanResultsResponse {
+    pub status: String,
+    pub groups: Vec<NetworkGroup>,
+    /// Number of groups matching the filters, before offset and limit
+    #[serde(default)]
+    pub total: usize,
+}
+
+/// Response for get_network_details request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkDetailsResponse {
+    pub status: String,
+    pub details: BssDetails,
+}
+
+/// Response for site_survey request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SiteSurveyResponse {
+    pub status: String,
+    pub survey: SurveyReport,
 }
 
 /// Response for connect request
@@ -45,12 +115,62 @@ pub struct ConnectResponse {
     pub state: ConnectionState,
 }
 
+/// Response for wps_pbc and wps_pin requests
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WpsResponse {
+    pub status: String,
+    pub state: ConnectionState,
+    /// PIN to enter on the access point (PIN method only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+}
+
+/// Response for dpp_start request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DppResponse {
+    pub status: String,
+    pub state: ConnectionState,
+    /// Bootstrap URI to render as QR code
+    pub uri: String,
+}
+
+/// Response for get_country and set_country requests
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CountryResponse {
+    pub status: String,
+    /// Active regulatory domain (`null` if unset)
+    pub country: Option<String>,
+}
+
+/// Response for get_capabilities request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CapabilitiesResponse {
+    pub status: String,
+    #[serde(flatten)]
+    pub capabilities: Capabilities,
+}
+
+/// Response for list_interfaces request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InterfacesResponse {
+    pub status: String,
+    /// Some interface is up and owns a default route
+    pub online: bool,
+    pub interfaces: Vec<NetworkInterface>,
+}
+
 /// Response for disconnect request
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct DisconnectResponse {
     pub status: String,
 }
 
+/// Response for cancel_scan, wps_cancel and dpp_stop requests
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OkResponse {
+    pub status: String,
+}
+
 /// Response for get_status request
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct StatusResponse {
@@ -60,19 +180,59 @@ pub struct StatusResponse {
 }
 
 impl ScanStartedResponse {
-    pub fn ok(state: ScanState) -> Self {
+    pub fn ok(state: ScanState, joined: bool) -> Self {
         Self {
             status: "ok".to_string(),
             state,
+            joined,
+            cached: false,
+        }
+    }
+
+    pub fn cached(state: ScanState) -> Self {
+        Self {
+            status: "ok".to_string(),
+            state,
+            joined: false,
+            cached: true,
         }
     }
 }
 
 impl ScanResultsResponse {
-    pub fn ok(networks: Vec<WifiNetwork>) -> Self {
+    pub fn ok(networks: Vec<WifiNetwork>, total: usize) -> Self {
         Self {
             status: "ok".to_string(),
             networks,
+            total,
+        }
+    }
+}
+
+impl GroupedScanResultsResponse {
+    pub fn ok(groups: Vec<NetworkGroup>, total: usize) -> Self {
+        Self {
+            status: "ok".to_string(),
+            groups,
+            total,
+        }
+    }
+}
+
+impl NetworkDetailsResponse {
+    pub fn ok(details: BssDetails) -> Self {
+        Self {
+            status: "ok".to_string(),
+            details,
+        }
+    }
+}
+
+impl SiteSurveyResponse {
+    pub fn ok(survey: SurveyReport) -> Self {
+        Self {
+            status: "ok".to_string(),
+            survey,
         }
     }
 }
@@ -86,6 +246,54 @@ impl ConnectResponse {
     }
 }
 
+impl WpsResponse {
+    pub fn ok(state: ConnectionState, pin: Option<String>) -> Self {
+        Self {
+            status: "ok".to_string(),
+            state,
+            pin,
+        }
+    }
+}
+
+impl DppResponse {
+    pub fn ok(state: ConnectionState, uri: String) -> Self {
+        Self {
+            status: "ok".to_string(),
+            state,
+            uri,
+        }
+    }
+}
+
+impl CountryResponse {
+    pub fn ok(country: Option<String>) -> Self {
+        Self {
+            status: "ok".to_string(),
+            country,
+        }
+    }
+}
+
+impl CapabilitiesResponse {
+    pub fn ok(capabilities: Capabilities) -> Self {
+        Self {
+            status: "ok".to_string(),
+            capabilities,
+        }
+    }
+}
+
+impl InterfacesResponse {
+    pub fn ok(interfaces: Vec<NetworkInterface>) -> Self {
+        Self {
+            status: "ok".to_string(),
+            online: interfaces.iter().any(NetworkInterface::is_online),
+            interfaces,
+        }
+    }
+}
+
 impl DisconnectResponse {
     pub fn ok() -> Self {
         Self {
@@ -94,6 +302,14 @@ impl DisconnectResponse {
     }
 }
 
+impl OkResponse {
+    pub fn ok() -> Self {
+        Self {
+            status: "ok".to_string(),
+        }
+    }
+}
+
 impl StatusResponse {
     pub fn ok(connection: ConnectionStatus) -> Self {
         Self {
@@ -109,7 +325,7 @@ mod tests {
 
     #[test]
     fn test_scan_started_response() {
-        let response = ScanStartedResponse::ok(ScanState::Scanning);
+        let response = ScanStartedResponse::ok(ScanState::Scanning, false);
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains(r#""status":"ok""#));
         assert!(json.contains(r#""state":"scanning""#));
@@ -121,13 +337,14 @@ mod tests {
     #[test]
     fn test_scan_results_response() {
         let networks = vec![WifiNetwork {
-            ssid: "TestNet".to_string(),
+            ssid: "TestNet".into(),
             mac: "aa:bb:cc:dd:ee:ff".to_string(),
             channel: 6,
             rssi: -65,
+            ..Default::default()
         }];
 
-        let response = ScanResultsResponse::ok(networks.clone());
+        let response = ScanResultsResponse::ok(networks.clone(), 1);
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains(r#""status":"ok""#));
         assert!(json.contains(r#""TestNet""#));
@@ -137,6 +354,55 @@ mod tests {
         assert_eq!(deserialized.networks[0].ssid, "TestNet");
     }
 
+    #[test]
+    fn test_network_details_response() {
+        let details = BssDetails {
+            bssid: "aa:bb:cc:dd:ee:ff".to_string(),
+            ssid: "TestNet".into(),
+            frequency: 5180,
+            channel: 36,
+            rssi: -60,
+            noise: None,
+            beacon_interval: 100,
+            capabilities: 0x0011,
+            supported_rates: vec![6000, 12000, 24000],
+            ht: true,
+            vht: true,
+            he: false,
+            wps: None,
+            flags: vec!["WPA2-PSK-CCMP".to_string(), "ESS".to_string()],
+            age_secs: 2,
+            ies: "0007546573744e6574".to_string(),
+        };
+
+        let response = Response::NetworkDetails(NetworkDetailsResponse::ok(details));
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains(r#""bssid":"aa:bb:cc:dd:ee:ff""#));
+        assert!(json.contains(r#""supported_rates":[6000,12000,24000]"#));
+        assert!(!json.contains(r#""noise""#));
+        assert!(!json.contains(r#""wps""#));
+
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, response);
+    }
+
+    #[test]
+    fn test_site_survey_response() {
+        let report = SurveyReport {
+            scans: 3,
+            channels: vec![],
+        };
+        let response = Response::SiteSurvey(SiteSurveyResponse::ok(report));
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"status":"ok","survey":{"scans":3,"channels":[]}}"#
+        );
+
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, response);
+    }
+
     #[test]
     fn test_connect_response() {
         let response = ConnectResponse::ok(ConnectionState::Connecting);
@@ -152,12 +418,20 @@ mod tests {
         assert_eq!(json, r#"{"status":"ok"}"#);
     }
 
+    #[test]
+    fn test_ok_response() {
+        let response = Response::Ok(OkResponse::ok());
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(json, r#"{"status":"ok"}"#);
+    }
+
     #[test]
     fn test_status_response() {
         let connection = ConnectionStatus {
             state: ConnectionState::Connected,
-            ssid: Some("MyNetwork".to_string()),
+            ssid: Some("MyNetwork".into()),
             ip_address: Some("192.168.1.100".to_string()),
+            country: Some("DE".to_string()),
         };
 
         let response = StatusResponse::ok(connection);
@@ -166,5 +440,6 @@ mod tests {
         assert!(json.contains(r#""state":"connected""#));
         assert!(json.contains(r#""MyNetwork""#));
         assert!(json.contains(r#""192.168.1.100""#));
+        assert!(json.contains(r#""country":"DE""#));
     }
 }
@@ -2,14 +2,20 @@
 
 use bluer::gatt::local::ReqError;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, task::JoinHandle};
 use tracing::{debug, error, warn};
 
 use crate::{
     backend::WifiBackend,
     core::{
-        service::WifiCommissioningService,
-        types::{ConnectionState, ScanState},
+        error::ServiceError,
+        scan_view,
+        service::{InterfaceService, WifiCommissioningService},
+        survey::{ChannelStats, DEFAULT_SURVEY_SCANS, MAX_SURVEY_SCANS, SurveyReport},
+        types::{
+            ConnectionState, InterfaceKind, ScanOptions, ScanResultsQuery, ScanState, WpsMethod,
+            normalize_country_code,
+        },
     },
     transport::ble::{session::BleSession, uuids::MAX_CHUNK_SIZE},
 };
@@ -19,6 +25,14 @@ pub struct CharacteristicHandler<B: WifiBackend> {
     service: Arc<WifiCommissioningService<B>>,
     session: Arc<RwLock<BleSession>>,
     result_offset: Arc<RwLock<usize>>,
+    /// Scan results payload being read, built when a read starts
+    results_payload: Arc<RwLock<Vec<u8>>>,
+    dpp_uri_offset: Arc<RwLock<usize>>,
+    interfaces_offset: Arc<RwLock<usize>>,
+    /// Outcome of the last site survey (`None` while running or before)
+    survey: Arc<RwLock<Option<Result<SurveyReport, String>>>>,
+    survey_task: Arc<RwLock<Option<JoinHandle<()>>>>,
+    survey_offset: Arc<RwLock<usize>>,
 }
 
 impl<B: WifiBackend> CharacteristicHandler<B> {
@@ -31,6 +45,12 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
             service,
             session,
             result_offset: Arc::new(RwLock::new(0)),
+            results_payload: Arc::new(RwLock::new(Vec::new())),
+            dpp_uri_offset: Arc::new(RwLock::new(0)),
+            interfaces_offset: Arc::new(RwLock::new(0)),
+            survey: Arc::new(RwLock::new(None)),
+            survey_task: Arc::new(RwLock::new(None)),
+            survey_offset: Arc::new(RwLock::new(0)),
         }
     }
 
@@ -40,9 +60,24 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
             warn!("Unauthorized access attempt");
             return Err(ReqError::NotAuthorized);
         }
+        if let Ok(interface) = self.interface().await {
+            interface.scanner.client_active().await;
+        }
         Ok(())
     }
 
+    /// Resolve the interface selected for this session
+    async fn interface(&self) -> Result<Arc<InterfaceService<B>>, ReqError> {
+        let name = self.session.read().await.interface().await;
+        match self.service.interface(name.as_deref()) {
+            Some(interface) => Ok(interface.clone()),
+            None => {
+                error!("Selected interface is not managed: {:?}", name);
+                Err(ReqError::Failed)
+            }
+        }
+    }
+
     /// Handle authorization key write
     pub async fn handle_auth_write(&self, value: Vec<u8>) -> Result<(), ReqError> {
         debug!("Authorization write received ({} bytes)", value.len());
@@ -57,8 +92,18 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
 
         match self.service.authorize(&hash).await {
             Ok(_) => {
-                self.session.write().await.set_authorized(true).await;
-                debug!("Authorization successful");
+                let previous = {
+                    let mut session = self.session.write().await;
+                    let previous = session.restart().await;
+                    session.set_authorized(true).await;
+                    previous
+                };
+                for interface in self.service.interfaces() {
+                    interface.scanner.end_session(previous).await;
+                }
+                *self.result_offset.write().await = 0;
+                *self.survey.write().await = None;
+                debug!("Authorization successful, new client session");
                 Ok(())
             }
             Err(e) => {
@@ -69,6 +114,8 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
     }
 
     /// Handle scan control write
+    ///
+    /// `0x01` starts a scan, optionally followed by `ScanOptions` as JSON.
     pub async fn handle_scan_control_write(&self, value: Vec<u8>) -> Result<(), ReqError> {
         self.check_authorized().await?;
 
@@ -80,17 +127,41 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
 
         match value[0] {
             1 => {
+                let options = Self::parse_scan_options(&value[1..])?;
+
                 // Start scan
                 debug!("Starting scan");
                 *self.result_offset.write().await = 0; // Reset offset
-                match self.service.start_scan().await {
-                    Ok(_) => Ok(()),
+                let session = self.session.read().await.id();
+                let scanner = self.interface().await?.scanner.clone();
+                match scanner.start_session_scan(Some(session), &options).await {
+                    Ok(handle) => {
+                        if handle.cached() {
+                            debug!("Inside the minimum scan interval, serving cached results");
+                        }
+                        Ok(())
+                    }
+                    Err(ServiceError::ScanRefused(refusal)) => {
+                        warn!("Scan refused: {}", refusal);
+                        Err(ReqError::NotPermitted)
+                    }
                     Err(e) => {
                         error!("Scan failed: {}", e);
                         Err(ReqError::Failed)
                     }
                 }
             }
+            2 => {
+                debug!("Cancelling scan");
+                match self.interface().await?.cancel_scan().await {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        warn!("Cannot cancel scan: {}", e);
+                        Err(ReqError::Failed)
+                    }
+                }
+            }
+            3 => self.start_survey(&value[1..]).await,
             _ => {
                 warn!("Invalid scan control value: {}", value[0]);
                 Err(ReqError::InvalidValueLength)
@@ -98,11 +169,60 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
         }
     }
 
+    /// Start a site survey in the background
+    ///
+    /// The optional payload byte is the number of scans.
+    async fn start_survey(&self, payload: &[u8]) -> Result<(), ReqError> {
+        let scans = match payload {
+            [] => DEFAULT_SURVEY_SCANS,
+            [scans] if (1..=MAX_SURVEY_SCANS).contains(&u32::from(*scans)) => u32::from(*scans),
+            _ => {
+                warn!("Invalid site survey payload: {:?}", payload);
+                return Err(ReqError::InvalidValueLength);
+            }
+        };
+
+        let mut task = self.survey_task.write().await;
+        if task.as_ref().is_some_and(|task| !task.is_finished()) {
+            warn!("Site survey already running");
+            return Err(ReqError::InProgress);
+        }
+
+        debug!("Starting site survey ({} scans)", scans);
+        *self.survey.write().await = None;
+        *self.survey_offset.write().await = 0;
+        let session = self.session.read().await.id();
+        let scanner = self.interface().await?.scanner.clone();
+        let outcome = self.survey.clone();
+        *task = Some(tokio::spawn(async move {
+            let result = scanner
+                .survey(Some(session), scans)
+                .await
+                .map_err(|e| e.to_string());
+            *outcome.write().await = Some(result);
+        }));
+        Ok(())
+    }
+
+    /// Parse and normalize the scan options following a start op code
+    fn parse_scan_options(payload: &[u8]) -> Result<ScanOptions, ReqError> {
+        if payload.is_empty() {
+            return Ok(ScanOptions::default());
+        }
+        serde_json::from_slice::<ScanOptions>(payload)
+            .map_err(|e| e.to_string())
+            .and_then(|options| options.normalized())
+            .map_err(|e| {
+                warn!("Invalid scan options: {}", e);
+                ReqError::InvalidValueLength
+            })
+    }
+
     /// Handle scan state read
     pub async fn handle_scan_state_read(&self) -> Result<Vec<u8>, ReqError> {
         self.check_authorized().await?;
 
-        let state = self.service.scan_state().await;
+        let state = self.interface().await?.scan_state().await;
         let state_byte = u8::from(state);
 
         debug!(
@@ -113,50 +233,249 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
         Ok(vec![state_byte])
     }
 
-    /// Handle scan results read (paginated)
-    pub async fn handle_scan_results_read(&self) -> Result<Vec<u8>, ReqError> {
+    /// Handle country code write
+    ///
+    /// Two ASCII characters, optionally followed by `1` to persist the code.
+    pub async fn handle_country_write(&self, value: Vec<u8>) -> Result<(), ReqError> {
         self.check_authorized().await?;
 
-        let results = match self.service.scan_results().await {
-            Some(networks) => networks,
-            None => {
-                debug!("No scan results available");
-                return Ok(vec![]);
-            }
+        debug!("Country write received ({} bytes)", value.len());
+
+        let (code, persist) = match value.as_slice() {
+            [a, b] => ([*a, *b], false),
+            [a, b, flag @ (0 | 1)] => ([*a, *b], *flag == 1),
+            _ => return Err(ReqError::InvalidValueLength),
         };
 
-        // Serialize results to JSON
-        let json = match serde_json::to_string(&results) {
-            Ok(j) => j,
+        let Some(country) = std::str::from_utf8(&code)
+            .ok()
+            .and_then(normalize_country_code)
+        else {
+            error!("Invalid country code: {:?}", code);
+            return Err(ReqError::InvalidValueLength);
+        };
+
+        match self.interface().await?.set_country(&country, persist).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                error!("Failed to set country code: {}", e);
+                Err(ReqError::Failed)
+            }
+        }
+    }
+
+    /// Handle country code read (empty if unset)
+    pub async fn handle_country_read(&self) -> Result<Vec<u8>, ReqError> {
+        self.check_authorized().await?;
+
+        match self.interface().await?.country().await {
+            Ok(country) => Ok(country.map(String::into_bytes).unwrap_or_default()),
+            Err(e) => {
+                error!("Failed to get country code: {}", e);
+                Err(ReqError::Failed)
+            }
+        }
+    }
+
+    /// Handle capabilities read (`u16` little-endian, see `Capabilities`)
+    pub async fn handle_capabilities_read(&self) -> Result<Vec<u8>, ReqError> {
+        self.check_authorized().await?;
+
+        match self.interface().await?.capabilities().await {
+            Ok(capabilities) => Ok(u16::from(&capabilities).to_le_bytes().to_vec()),
             Err(e) => {
-                error!("Failed to serialize scan results: {}", e);
+                error!("Failed to get capabilities: {}", e);
+                Err(ReqError::Failed)
+            }
+        }
+    }
+
+    /// Handle interface selection write
+    ///
+    /// ASCII interface name; empty selects the default interface.
+    pub async fn handle_interface_write(&self, value: Vec<u8>) -> Result<(), ReqError> {
+        self.check_authorized().await?;
+
+        debug!("Interface write received ({} bytes)", value.len());
+
+        let name = String::from_utf8(value).map_err(|_| ReqError::InvalidValueLength)?;
+        let name = (!name.is_empty()).then_some(name);
+        if self.service.interface(name.as_deref()).is_none() {
+            error!("Unknown interface: {:?}", name);
+            return Err(ReqError::Failed);
+        }
+
+        self.session.write().await.set_interface(name).await;
+        // Reads in progress belong to the previous interface
+        *self.result_offset.write().await = 0;
+        self.results_payload.write().await.clear();
+        *self.dpp_uri_offset.write().await = 0;
+        *self.survey.write().await = None;
+        *self.survey_offset.write().await = 0;
+        Ok(())
+    }
+
+    /// Handle interface read
+    ///
+    /// Managed interface names, one per line; the selected one is prefixed with `*`.
+    pub async fn handle_interface_read(&self) -> Result<Vec<u8>, ReqError> {
+        self.check_authorized().await?;
+
+        let selected = self.interface().await?;
+        let names: Vec<String> = self
+            .service
+            .interfaces()
+            .iter()
+            .map(|interface| {
+                if Arc::ptr_eq(interface, &selected) {
+                    format!("*{}", interface.name)
+                } else {
+                    interface.name.clone()
+                }
+            })
+            .collect();
+
+        Ok(names.join("\n").into_bytes())
+    }
+
+    /// Handle network interfaces read (paginated)
+    ///
+    /// One compact line per interface (see `NetworkInterface::compact`),
+    /// loopback omitted.
+    pub async fn handle_network_interfaces_read(&self) -> Result<Vec<u8>, ReqError> {
+        self.check_authorized().await?;
+
+        let interfaces = match self.service.list_interfaces().await {
+            Ok(interfaces) => interfaces,
+            Err(e) => {
+                error!("Failed to list network interfaces: {}", e);
                 return Err(ReqError::Failed);
             }
         };
 
-        let bytes = json.as_bytes();
-        let offset = *self.result_offset.read().await;
+        let lines: Vec<String> = interfaces
+            .iter()
+            .filter(|interface| interface.kind != InterfaceKind::Loopback)
+            .map(|interface| interface.compact())
+            .collect();
+
+        Ok(Self::next_chunk(lines.join("\n").as_bytes(), &self.interfaces_offset).await)
+    }
+
+    /// Handle scan results query write (JSON, empty to reset)
+    pub async fn handle_scan_results_write(&self, value: Vec<u8>) -> Result<(), ReqError> {
+        self.check_authorized().await?;
+
+        let query = if value.is_empty() {
+            ScanResultsQuery::default()
+        } else {
+            match serde_json::from_slice(&value) {
+                Ok(query) => query,
+                Err(e) => {
+                    warn!("Invalid scan results query: {}", e);
+                    return Err(ReqError::InvalidValueLength);
+                }
+            }
+        };
+
+        debug!("Scan results query set: {:?}", query);
+        self.session.read().await.set_results_query(query).await;
+        *self.result_offset.write().await = 0;
+        Ok(())
+    }
+
+    /// Handle scan results read (paginated)
+    ///
+    /// The view is built when a read starts at offset 0; later chunks are
+    /// served from that snapshot, so they fit together.
+    pub async fn handle_scan_results_read(&self) -> Result<Vec<u8>, ReqError> {
+        self.check_authorized().await?;
+
+        if *self.result_offset.read().await == 0 {
+            let results = match self.interface().await?.scan_results().await {
+                Some(networks) => networks,
+                None => {
+                    debug!("No scan results available");
+                    return Ok(vec![]);
+                }
+            };
+
+            let query = self.session.read().await.results_query().await;
+            let view = scan_view::apply(results, &query).view;
+
+            // Serialize results to JSON
+            let json = match serde_json::to_vec(&view) {
+                Ok(j) => j,
+                Err(e) => {
+                    error!("Failed to serialize scan results: {}", e);
+                    return Err(ReqError::Failed);
+                }
+            };
+            *self.results_payload.write().await = json;
+        }
+
+        let payload = self.results_payload.read().await;
+        Ok(Self::next_chunk(&payload, &self.result_offset).await)
+    }
+
+    /// Handle DPP bootstrap URI read (paginated)
+    pub async fn handle_dpp_uri_read(&self) -> Result<Vec<u8>, ReqError> {
+        self.check_authorized().await?;
 
-        if offset >= bytes.len() {
-            debug!("Results read complete, resetting offset");
-            *self.result_offset.write().await = 0;
+        let Some(uri) = self.interface().await?.dpp_uri().await else {
+            debug!("No DPP session active");
             return Ok(vec![]);
+        };
+
+        Ok(Self::next_chunk(uri.as_bytes(), &self.dpp_uri_offset).await)
+    }
+
+    /// Handle site survey read (paginated)
+    ///
+    /// One compact line per channel (see `ChannelStats::compact`), empty
+    /// until the survey has finished.
+    pub async fn handle_survey_read(&self) -> Result<Vec<u8>, ReqError> {
+        self.check_authorized().await?;
+
+        let lines: Vec<String> = match &*self.survey.read().await {
+            Some(Ok(report)) => report.channels.iter().map(ChannelStats::compact).collect(),
+            Some(Err(e)) => {
+                warn!("Site survey failed: {}", e);
+                return Err(ReqError::Failed);
+            }
+            None => {
+                debug!("No site survey available");
+                return Ok(vec![]);
+            }
+        };
+
+        Ok(Self::next_chunk(lines.join("\n").as_bytes(), &self.survey_offset).await)
+    }
+
+    /// Return the chunk at `offset` and advance it; an empty chunk marks the end
+    async fn next_chunk(bytes: &[u8], offset: &RwLock<usize>) -> Vec<u8> {
+        let mut offset = offset.write().await;
+
+        if *offset >= bytes.len() {
+            debug!("Read complete, resetting offset");
+            *offset = 0;
+            return vec![];
         }
 
-        let end = std::cmp::min(offset + MAX_CHUNK_SIZE, bytes.len());
-        let chunk = bytes[offset..end].to_vec();
+        let end = std::cmp::min(*offset + MAX_CHUNK_SIZE, bytes.len());
+        let chunk = bytes[*offset..end].to_vec();
 
         debug!(
-            "Scan results read: offset={}, chunk_size={}, total_size={}",
-            offset,
+            "Chunked read: offset={}, chunk_size={}, total_size={}",
+            *offset,
             chunk.len(),
             bytes.len()
         );
 
         // Update offset for next read
-        *self.result_offset.write().await = end;
+        *offset = end;
 
-        Ok(chunk)
+        chunk
     }
 
     /// Handle SSID write (accumulates partial writes)
@@ -186,6 +505,19 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
         Ok(())
     }
 
+    /// Handle connect options write (accumulates partial writes)
+    pub async fn handle_connect_options_write(&self, value: Vec<u8>) -> Result<(), ReqError> {
+        self.check_authorized().await?;
+
+        debug!("Connect options write received ({} bytes)", value.len());
+        self.session
+            .write()
+            .await
+            .append_connect_options(&value)
+            .await;
+        Ok(())
+    }
+
     /// Handle connect control write
     pub async fn handle_connect_control_write(&self, value: Vec<u8>) -> Result<(), ReqError> {
         self.check_authorized().await?;
@@ -201,13 +533,7 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
                 // Connect
                 debug!("Initiating connection");
 
-                let ssid = match self.session.read().await.get_ssid().await {
-                    Ok(s) => s,
-                    Err(e) => {
-                        error!("Invalid SSID: {}", e);
-                        return Err(ReqError::Failed);
-                    }
-                };
+                let ssid = self.session.read().await.get_ssid().await;
 
                 let psk = match self.session.read().await.get_psk().await {
                     Some(p) => p,
@@ -217,7 +543,22 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
                     }
                 };
 
-                match self.service.connect(&ssid, &psk).await {
+                let options = match self
+                    .session
+                    .read()
+                    .await
+                    .get_connect_options()
+                    .await
+                    .and_then(|options| options.normalized())
+                {
+                    Ok(options) => options,
+                    Err(e) => {
+                        error!("{}", e);
+                        return Err(ReqError::Failed);
+                    }
+                };
+
+                match self.interface().await?.connect(&ssid, &psk, &options).await {
                     Ok(_) => {
                         debug!("Connection initiated for SSID: {}", ssid);
                         // Clear buffers after successful connection initiation
@@ -233,7 +574,7 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
             2 => {
                 // Disconnect
                 debug!("Initiating disconnection");
-                match self.service.disconnect().await {
+                match self.interface().await?.disconnect().await {
                     Ok(_) => Ok(()),
                     Err(e) => {
                         error!("Disconnection failed: {}", e);
@@ -241,6 +582,87 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
                     }
                 }
             }
+            3 | 4 => {
+                // WPS push-button, or PIN given as ASCII digits after the op code.
+                // Generated PINs are not supported here since BLE has no way to
+                // hand them back to the client.
+                let method = if value[0] == 3 {
+                    WpsMethod::PushButton
+                } else {
+                    let pin = String::from_utf8(value[1..].to_vec())
+                        .map_err(|_| ReqError::InvalidValueLength)?;
+                    WpsMethod::Pin(Some(pin))
+                };
+                if let Err(e) = method.validate() {
+                    error!("{}", e);
+                    return Err(ReqError::InvalidValueLength);
+                }
+                let bssid = match self
+                    .session
+                    .read()
+                    .await
+                    .get_connect_options()
+                    .await
+                    .and_then(|options| options.normalized())
+                {
+                    Ok(options) => options.bssid,
+                    Err(e) => {
+                        error!("{}", e);
+                        return Err(ReqError::Failed);
+                    }
+                };
+
+                debug!("Starting WPS registration ({:?})", method);
+                match self
+                    .interface()
+                    .await?
+                    .start_wps(&method, bssid.as_deref())
+                    .await
+                {
+                    Ok(_) => {
+                        self.session.write().await.clear_buffers().await;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        error!("WPS failed: {}", e);
+                        Err(ReqError::Failed)
+                    }
+                }
+            }
+            5 => {
+                // Cancel WPS
+                debug!("Cancelling WPS registration");
+                match self.interface().await?.cancel_wps().await {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("WPS cancel failed: {}", e);
+                        Err(ReqError::Failed)
+                    }
+                }
+            }
+            6 => {
+                // Start DPP enrollee; the URI is read from the DPP URI characteristic
+                debug!("Starting DPP enrollee");
+                *self.dpp_uri_offset.write().await = 0;
+                match self.interface().await?.start_dpp().await {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("DPP failed: {}", e);
+                        Err(ReqError::Failed)
+                    }
+                }
+            }
+            7 => {
+                // Stop DPP enrollee
+                debug!("Stopping DPP enrollee");
+                match self.interface().await?.stop_dpp().await {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        error!("DPP stop failed: {}", e);
+                        Err(ReqError::Failed)
+                    }
+                }
+            }
             _ => {
                 warn!("Invalid connect control value: {}", value[0]);
                 Err(ReqError::InvalidValueLength)
@@ -252,7 +674,7 @@ impl<B: WifiBackend> CharacteristicHandler<B> {
     pub async fn handle_connect_state_read(&self) -> Result<Vec<u8>, ReqError> {
         self.check_authorized().await?;
 
-        let status = self.service.connection_status().await;
+        let status = self.interface().await?.connection_status().await;
         let state_byte = u8::from(status.state);
 
         debug!("Connection state read: {} ({:?})", state_byte, status.state);
@@ -413,7 +835,7 @@ mod tests {
         let result = handler.handle_ssid_write(b"MyNetwork".to_vec()).await;
         assert!(result.is_ok());
 
-        let ssid = handler.session.read().await.get_ssid().await.unwrap();
+        let ssid = handler.session.read().await.get_ssid().await;
         assert_eq!(ssid, "MyNetwork");
     }
 
@@ -427,7 +849,7 @@ mod tests {
         handler.handle_ssid_write(b"Net".to_vec()).await.unwrap();
         handler.handle_ssid_write(b"work".to_vec()).await.unwrap();
 
-        let ssid = handler.session.read().await.get_ssid().await.unwrap();
+        let ssid = handler.session.read().await.get_ssid().await;
         assert_eq!(ssid, "MyNetwork");
     }
 
@@ -443,7 +865,7 @@ mod tests {
             .await;
         assert!(result.is_ok());
 
-        let ssid = handler.session.read().await.get_ssid().await.unwrap();
+        let ssid = handler.session.read().await.get_ssid().await;
         assert_eq!(ssid.len(), 32);
     }
 
@@ -458,7 +880,7 @@ mod tests {
             .await;
         assert!(result.is_ok());
 
-        let ssid = handler.session.read().await.get_ssid().await.unwrap();
+        let ssid = handler.session.read().await.get_ssid().await;
         assert_eq!(ssid, ssid_with_emoji);
     }
 
@@ -506,10 +928,56 @@ mod tests {
         assert!(result.is_ok());
 
         // Verify buffers are cleared after connection
-        assert_eq!(handler.session.read().await.get_ssid().await.unwrap(), "");
+        assert_eq!(handler.session.read().await.get_ssid().await, "");
         assert!(handler.session.read().await.get_psk().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_connect_control_connect_with_options() {
+        let handler = create_test_handler().await;
+        handler.session.write().await.set_authorized(true).await;
+
+        handler
+            .handle_ssid_write(b"Warehouse".to_vec())
+            .await
+            .unwrap();
+        handler.handle_psk_write(vec![42u8; 32]).await.unwrap();
+        handler
+            .handle_connect_options_write(br#"{"bssid":"AA:BB:CC:DD:EE:FF","#.to_vec())
+            .await
+            .unwrap();
+        handler
+            .handle_connect_options_write(br#""band":"2.4ghz"}"#.to_vec())
+            .await
+            .unwrap();
+
+        let result = handler.handle_connect_control_write(vec![1]).await;
+        assert!(result.is_ok());
+        assert_eq!(
+            handler.session.read().await.get_connect_options().await,
+            Ok(Default::default())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_control_invalid_options() {
+        let handler = create_test_handler().await;
+        handler.session.write().await.set_authorized(true).await;
+
+        handler
+            .handle_ssid_write(b"Warehouse".to_vec())
+            .await
+            .unwrap();
+        handler.handle_psk_write(vec![42u8; 32]).await.unwrap();
+        handler
+            .handle_connect_options_write(br#"{"bssid":"nope"}"#.to_vec())
+            .await
+            .unwrap();
+
+        let result = handler.handle_connect_control_write(vec![1]).await;
+        assert!(matches!(result, Err(ReqError::Failed)));
+    }
+
     #[tokio::test]
     async fn test_connect_control_disconnect() {
         let handler = create_test_handler().await;
@@ -574,6 +1042,323 @@ mod tests {
         assert_eq!(offset, 0);
     }
 
+    #[tokio::test]
+    async fn test_scan_control_options() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = Arc::new(WifiCommissioningService::new(
+            backend.clone(),
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        handler.session.write().await.set_authorized(true).await;
+
+        let mut value = vec![1];
+        value.extend_from_slice(br#"{"ssids":["Hidden"],"band":"2.4ghz"}"#);
+        handler.handle_scan_control_write(value).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        let options = backend.last_scan_options().await.unwrap();
+        assert_eq!(options.ssids, [crate::core::types::Ssid::from("Hidden")]);
+        assert_eq!(
+            options.freq_list,
+            Some(crate::core::types::Band::Band2_4Ghz.frequencies())
+        );
+
+        for payload in [&b"{"[..], br#"{"dwell_ms":0}"#] {
+            let mut value = vec![1];
+            value.extend_from_slice(payload);
+            assert!(matches!(
+                handler.handle_scan_control_write(value).await,
+                Err(ReqError::InvalidValueLength)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_control_cancel() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let service = Arc::new(WifiCommissioningService::new(
+            backend.clone(),
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        handler.session.write().await.set_authorized(true).await;
+
+        assert!(matches!(
+            handler.handle_scan_control_write(vec![2]).await,
+            Err(ReqError::Failed)
+        ));
+
+        backend
+            .set_scan_latency(tokio::time::Duration::from_secs(5))
+            .await;
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        handler.handle_scan_control_write(vec![2]).await.unwrap();
+        assert_eq!(handler.service.scan_state().await, ScanState::Idle);
+        assert_eq!(backend.scan_aborts().await, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_site_survey() {
+        use crate::core::types::WifiNetwork;
+
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![WifiNetwork {
+                ssid: "TestNet".into(),
+                mac: "aa:bb:cc:dd:ee:ff".to_string(),
+                channel: 6,
+                frequency: 2437,
+                rssi: -60,
+                ..Default::default()
+            }])
+            .await;
+        let service = Arc::new(WifiCommissioningService::new(
+            backend.clone(),
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        handler.session.write().await.set_authorized(true).await;
+
+        assert!(handler.handle_survey_read().await.unwrap().is_empty());
+        assert!(matches!(
+            handler.handle_scan_control_write(vec![3, 0]).await,
+            Err(ReqError::InvalidValueLength)
+        ));
+
+        handler.handle_scan_control_write(vec![3, 2]).await.unwrap();
+        assert!(matches!(
+            handler.handle_scan_control_write(vec![3]).await,
+            Err(ReqError::InProgress)
+        ));
+        assert!(handler.handle_survey_read().await.unwrap().is_empty());
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+        let summary = handler.handle_survey_read().await.unwrap();
+        assert_eq!(String::from_utf8(summary).unwrap(), "6 2437 1 -60 -60 0 -");
+        assert!(handler.handle_survey_read().await.unwrap().is_empty());
+
+        // A failed survey fails the read
+        backend.set_scan_failure(true).await;
+        handler.handle_scan_control_write(vec![3, 1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        assert!(matches!(
+            handler.handle_survey_read().await,
+            Err(ReqError::Failed)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_control_refused() {
+        // Failed scans leave no results to answer refused scans from
+        let backend = Arc::new(MockWifiBackend::new());
+        backend.set_scan_failure(true).await;
+        let service = Arc::new(WifiCommissioningService::new(
+            backend,
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        handler.session.write().await.set_authorized(true).await;
+        let scanner = handler.service.interface(None).unwrap().scanner.clone();
+        scanner
+            .set_policy(crate::core::scan_policy::ScanPolicy {
+                session_quota: Some(1),
+                ..Default::default()
+            })
+            .await;
+
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        assert!(matches!(
+            handler.handle_scan_control_write(vec![1]).await,
+            Err(ReqError::NotPermitted)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_quota_per_client() {
+        use sha3::{Digest, Sha3_256};
+
+        let backend = Arc::new(MockWifiBackend::new());
+        backend.set_scan_failure(true).await;
+        let service = Arc::new(WifiCommissioningService::new(
+            backend,
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        let scanner = handler.service.interface(None).unwrap().scanner.clone();
+        scanner
+            .set_policy(crate::core::scan_policy::ScanPolicy {
+                session_quota: Some(1),
+                ..Default::default()
+            })
+            .await;
+        let hash = Sha3_256::digest(b"test-secret").to_vec();
+
+        // First phone uses up its quota
+        handler.handle_auth_write(hash.clone()).await.unwrap();
+        let first = handler.session.read().await.id();
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        assert!(matches!(
+            handler.handle_scan_control_write(vec![1]).await,
+            Err(ReqError::NotPermitted)
+        ));
+
+        // The next phone to authorize gets its own quota
+        handler.handle_auth_write(hash).await.unwrap();
+        assert_ne!(handler.session.read().await.id(), first);
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        assert!(matches!(
+            handler.handle_scan_control_write(vec![1]).await,
+            Err(ReqError::NotPermitted)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scan_results_query_write() {
+        use crate::core::types::{NetworkGroup, WifiNetwork};
+
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![
+                WifiNetwork {
+                    ssid: "Net".into(),
+                    mac: "aa:00:00:00:00:01".to_string(),
+                    rssi: -70,
+                    ..Default::default()
+                },
+                WifiNetwork {
+                    ssid: "Net".into(),
+                    mac: "aa:00:00:00:00:02".to_string(),
+                    rssi: -50,
+                    ..Default::default()
+                },
+            ])
+            .await;
+        let service = Arc::new(WifiCommissioningService::new(
+            backend,
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        handler.session.write().await.set_authorized(true).await;
+
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        assert!(matches!(
+            handler.handle_scan_results_write(b"{".to_vec()).await,
+            Err(ReqError::InvalidValueLength)
+        ));
+
+        *handler.result_offset.write().await = 10;
+        handler
+            .handle_scan_results_write(br#"{"group":true}"#.to_vec())
+            .await
+            .unwrap();
+        assert_eq!(*handler.result_offset.read().await, 0);
+
+        let mut json = Vec::new();
+        loop {
+            let chunk = handler.handle_scan_results_read().await.unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            json.extend(chunk);
+        }
+        let groups: Vec<NetworkGroup> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].rssi, -50);
+        assert_eq!(groups[0].bssids.len(), 2);
+
+        // Empty write restores the flat list
+        handler.handle_scan_results_write(vec![]).await.unwrap();
+        assert_eq!(
+            handler.session.read().await.results_query().await,
+            ScanResultsQuery::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_results_read_snapshot() {
+        use crate::core::types::{Ssid, WifiNetwork};
+
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(
+                (1..=4)
+                    .map(|i| WifiNetwork {
+                        ssid: format!("Net{}", i).into(),
+                        mac: format!("aa:00:00:00:00:0{}", i),
+                        rssi: -50 - i,
+                        ..Default::default()
+                    })
+                    .collect(),
+            )
+            .await;
+        backend
+            .connect(&"Net2".into(), &[0; 32], &Default::default())
+            .await
+            .unwrap();
+        backend.complete_connection("192.168.1.100").await;
+        backend
+            .set_saved_networks(vec![Ssid::from("Net2"), Ssid::from("Net4")])
+            .await;
+        let service = Arc::new(WifiCommissioningService::new(
+            backend.clone(),
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        handler.session.write().await.set_authorized(true).await;
+
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        let mut json = handler.handle_scan_results_read().await.unwrap();
+        assert_eq!(json.len(), MAX_CHUNK_SIZE);
+
+        // Later chunks come from the snapshot taken by the first read
+        backend.set_saved_networks(vec![]).await;
+        handler
+            .interface()
+            .await
+            .unwrap()
+            .start_scan(&Default::default())
+            .await
+            .unwrap()
+            .wait()
+            .await
+            .unwrap();
+        loop {
+            let chunk = handler.handle_scan_results_read().await.unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            json.extend(chunk);
+        }
+        let networks: Vec<WifiNetwork> = serde_json::from_slice(&json).unwrap();
+        let markers: Vec<(bool, bool)> = networks.iter().map(|n| (n.connected, n.saved)).collect();
+        assert_eq!(
+            markers,
+            [(false, false), (true, true), (false, false), (false, true)]
+        );
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains(r#""connected":true"#));
+        assert!(json.contains(r#""saved":true"#));
+
+        // The next read starts over with a fresh view
+        let mut json = Vec::new();
+        loop {
+            let chunk = handler.handle_scan_results_read().await.unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            json.extend(chunk);
+        }
+        assert!(!String::from_utf8(json).unwrap().contains(r#""saved""#));
+    }
+
     #[tokio::test]
     async fn test_chunked_ssid_writes() {
         let handler = create_test_handler().await;
@@ -587,7 +1372,226 @@ mod tests {
         handler.handle_ssid_write(chunk1.to_vec()).await.unwrap();
         handler.handle_ssid_write(chunk2.to_vec()).await.unwrap();
 
-        let ssid = handler.session.read().await.get_ssid().await.unwrap();
+        let ssid = handler.session.read().await.get_ssid().await;
         assert_eq!(ssid, full_ssid);
     }
+
+    #[tokio::test]
+    async fn test_connect_control_wps() {
+        let handler = create_test_handler().await;
+        handler.session.write().await.set_authorized(true).await;
+
+        // PIN is mandatory and must be valid
+        assert!(handler.handle_connect_control_write(vec![4]).await.is_err());
+        assert!(
+            handler
+                .handle_connect_control_write(b"\x0412345678".to_vec())
+                .await
+                .is_err()
+        );
+
+        let mut value = vec![4];
+        value.extend_from_slice(b"12345670");
+        assert!(handler.handle_connect_control_write(value).await.is_ok());
+        let state = handler.handle_connect_state_read().await.unwrap();
+        assert_eq!(state, vec![u8::from(ConnectionState::WpsActive)]);
+
+        // Cancel returns to idle
+        assert!(handler.handle_connect_control_write(vec![5]).await.is_ok());
+        let state = handler.handle_connect_state_read().await.unwrap();
+        assert_eq!(state, vec![u8::from(ConnectionState::Idle)]);
+
+        assert!(handler.handle_connect_control_write(vec![3]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_connect_control_dpp() {
+        let handler = create_test_handler().await;
+        handler.session.write().await.set_authorized(true).await;
+
+        // Nothing to read without a DPP session
+        assert!(handler.handle_dpp_uri_read().await.unwrap().is_empty());
+
+        assert!(handler.handle_connect_control_write(vec![6]).await.is_ok());
+        let state = handler.handle_connect_state_read().await.unwrap();
+        assert_eq!(state, vec![u8::from(ConnectionState::DppActive)]);
+
+        let mut uri = Vec::new();
+        loop {
+            let chunk = handler.handle_dpp_uri_read().await.unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            assert!(chunk.len() <= MAX_CHUNK_SIZE);
+            uri.extend(chunk);
+        }
+        assert_eq!(
+            Some(String::from_utf8(uri).unwrap()),
+            handler.interface().await.unwrap().dpp_uri().await
+        );
+
+        assert!(handler.handle_connect_control_write(vec![7]).await.is_ok());
+        let state = handler.handle_connect_state_read().await.unwrap();
+        assert_eq!(state, vec![u8::from(ConnectionState::Idle)]);
+    }
+
+    #[tokio::test]
+    async fn test_country_write() {
+        let handler = create_test_handler().await;
+        handler.session.write().await.set_authorized(true).await;
+
+        assert!(handler.handle_country_read().await.unwrap().is_empty());
+
+        assert!(handler.handle_country_write(b"D".to_vec()).await.is_err());
+        assert!(handler.handle_country_write(b"1A".to_vec()).await.is_err());
+        assert!(
+            handler
+                .handle_country_write(b"de\x02".to_vec())
+                .await
+                .is_err()
+        );
+
+        assert!(
+            handler
+                .handle_country_write(b"de\x01".to_vec())
+                .await
+                .is_ok()
+        );
+        assert_eq!(handler.handle_country_read().await.unwrap(), b"DE".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_read() {
+        let handler = create_test_handler().await;
+        assert!(handler.handle_capabilities_read().await.is_err());
+
+        handler.session.write().await.set_authorized(true).await;
+
+        // Mock default: WPS, DPP, persistence, country
+        let caps = handler.handle_capabilities_read().await.unwrap();
+        assert_eq!(caps, vec![0b0110_1100, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_interface_selection() {
+        let service = Arc::new(WifiCommissioningService::with_interfaces(
+            vec![
+                ("wlan0".to_string(), Arc::new(MockWifiBackend::new())),
+                ("wlan1".to_string(), Arc::new(MockWifiBackend::new())),
+            ],
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        assert!(handler.handle_interface_read().await.is_err());
+
+        handler.session.write().await.set_authorized(true).await;
+        assert_eq!(
+            handler.handle_interface_read().await.unwrap(),
+            b"*wlan0\nwlan1"
+        );
+
+        assert!(matches!(
+            handler.handle_interface_write(b"eth0".to_vec()).await,
+            Err(ReqError::Failed)
+        ));
+        handler
+            .handle_interface_write(b"wlan1".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            handler.handle_interface_read().await.unwrap(),
+            b"wlan0\n*wlan1"
+        );
+
+        // Scans only run on the selected interface
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        let interfaces = handler.service.interfaces();
+        assert_eq!(interfaces[0].scan_state().await, ScanState::Idle);
+        assert_ne!(interfaces[1].scan_state().await, ScanState::Idle);
+
+        // Empty write returns to the default
+        handler.handle_interface_write(Vec::new()).await.unwrap();
+        assert_eq!(
+            handler.handle_interface_read().await.unwrap(),
+            b"*wlan0\nwlan1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interface_switch_restarts_reads() {
+        use crate::core::types::WifiNetwork;
+
+        let networks = |prefix: &str| {
+            (1..=4)
+                .map(|i| WifiNetwork {
+                    ssid: format!("{}{}", prefix, i).into(),
+                    mac: format!("aa:00:00:00:00:0{}", i),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>()
+        };
+        let wlan0 = Arc::new(MockWifiBackend::new());
+        wlan0.set_scan_results(networks("First")).await;
+        let wlan1 = Arc::new(MockWifiBackend::new());
+        wlan1.set_scan_results(networks("Second")).await;
+        let service = Arc::new(WifiCommissioningService::with_interfaces(
+            vec![("wlan0".to_string(), wlan0), ("wlan1".to_string(), wlan1)],
+            "test-secret".to_string(),
+        ));
+        let handler = CharacteristicHandler::new(service, Arc::new(RwLock::new(BleSession::new())));
+        handler.session.write().await.set_authorized(true).await;
+
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        let chunk = handler.handle_scan_results_read().await.unwrap();
+        assert_eq!(chunk.len(), MAX_CHUNK_SIZE);
+        *handler.dpp_uri_offset.write().await = 5;
+        *handler.survey_offset.write().await = 5;
+
+        handler
+            .handle_interface_write(b"wlan1".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(*handler.dpp_uri_offset.read().await, 0);
+        assert_eq!(*handler.survey_offset.read().await, 0);
+
+        // The next read starts over on the selected interface
+        handler.handle_scan_control_write(vec![1]).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        let mut json = Vec::new();
+        loop {
+            let chunk = handler.handle_scan_results_read().await.unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            json.extend(chunk);
+        }
+        let networks: Vec<WifiNetwork> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(networks.len(), 4);
+        assert!(
+            networks
+                .iter()
+                .all(|n| n.ssid.to_string().starts_with("Second"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_network_interfaces_read() {
+        let handler = create_test_handler().await;
+        assert!(handler.handle_network_interfaces_read().await.is_err());
+
+        handler.session.write().await.set_authorized(true).await;
+
+        let mut list = Vec::new();
+        loop {
+            let chunk = handler.handle_network_interfaces_read().await.unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            list.extend(chunk);
+        }
+        let list = String::from_utf8(list).unwrap();
+        assert!(!list.lines().any(|line| line.starts_with("lo ")));
+        assert!(list.lines().all(|line| line.split(' ').count() == 6));
+    }
 }
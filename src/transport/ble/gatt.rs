@@ -113,6 +113,116 @@ impl<B: WifiBackend> GattServer<B> {
                     notify: Some(Default::default()),
                     ..Default::default()
                 },
+                // Country code characteristic
+                Characteristic {
+                    uuid: SCAN_COUNTRY_CHAR_UUID,
+                    read: Some(CharacteristicRead {
+                        read: true,
+                        fun: {
+                            let handler = handler.clone();
+                            Box::new(move |_req| {
+                                let handler = handler.clone();
+                                Box::pin(async move { handler.handle_country_read().await })
+                            })
+                        },
+                        ..Default::default()
+                    }),
+                    write: Some(CharacteristicWrite {
+                        write: true,
+                        write_without_response: false,
+                        method: CharacteristicWriteMethod::Fun({
+                            let handler = handler.clone();
+                            Box::new(move |new_value, _req| {
+                                let handler = handler.clone();
+                                Box::pin(
+                                    async move { handler.handle_country_write(new_value).await },
+                                )
+                            })
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                // Capabilities characteristic
+                Characteristic {
+                    uuid: SCAN_CAPABILITIES_CHAR_UUID,
+                    read: Some(CharacteristicRead {
+                        read: true,
+                        fun: {
+                            let handler = handler.clone();
+                            Box::new(move |_req| {
+                                let handler = handler.clone();
+                                Box::pin(async move { handler.handle_capabilities_read().await })
+                            })
+                        },
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                // Interface selection characteristic
+                Characteristic {
+                    uuid: SCAN_INTERFACE_CHAR_UUID,
+                    read: Some(CharacteristicRead {
+                        read: true,
+                        fun: {
+                            let handler = handler.clone();
+                            Box::new(move |_req| {
+                                let handler = handler.clone();
+                                Box::pin(async move { handler.handle_interface_read().await })
+                            })
+                        },
+                        ..Default::default()
+                    }),
+                    write: Some(CharacteristicWrite {
+                        write: true,
+                        write_without_response: false,
+                        method: CharacteristicWriteMethod::Fun({
+                            let handler = handler.clone();
+                            Box::new(move |new_value, _req| {
+                                let handler = handler.clone();
+                                Box::pin(
+                                    async move { handler.handle_interface_write(new_value).await },
+                                )
+                            })
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                // Network interfaces characteristic
+                Characteristic {
+                    uuid: SCAN_NETWORK_INTERFACES_CHAR_UUID,
+                    read: Some(CharacteristicRead {
+                        read: true,
+                        fun: {
+                            let handler = handler.clone();
+                            Box::new(move |_req| {
+                                let handler = handler.clone();
+                                Box::pin(
+                                    async move { handler.handle_network_interfaces_read().await },
+                                )
+                            })
+                        },
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                // Site survey characteristic
+                Characteristic {
+                    uuid: SCAN_SURVEY_CHAR_UUID,
+                    read: Some(CharacteristicRead {
+                        read: true,
+                        fun: {
+                            let handler = handler.clone();
+                            Box::new(move |_req| {
+                                let handler = handler.clone();
+                                Box::pin(async move { handler.handle_survey_read().await })
+                            })
+                        },
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
                 // Scan results characteristic
                 Characteristic {
                     uuid: SCAN_RESULTS_CHAR_UUID,
@@ -127,6 +237,20 @@ impl<B: WifiBackend> GattServer<B> {
                         },
                         ..Default::default()
                     }),
+                    write: Some(CharacteristicWrite {
+                        write: true,
+                        write_without_response: false,
+                        method: CharacteristicWriteMethod::Fun({
+                            let handler = handler.clone();
+                            Box::new(move |new_value, _req| {
+                                let handler = handler.clone();
+                                Box::pin(async move {
+                                    handler.handle_scan_results_write(new_value).await
+                                })
+                            })
+                        }),
+                        ..Default::default()
+                    }),
                     ..Default::default()
                 },
             ],
@@ -174,6 +298,41 @@ impl<B: WifiBackend> GattServer<B> {
                     }),
                     ..Default::default()
                 },
+                // Connect options characteristic
+                Characteristic {
+                    uuid: CONNECT_OPTIONS_CHAR_UUID,
+                    write: Some(CharacteristicWrite {
+                        write: true,
+                        write_without_response: false,
+                        method: CharacteristicWriteMethod::Fun({
+                            let handler = handler.clone();
+                            Box::new(move |new_value, _req| {
+                                let handler = handler.clone();
+                                Box::pin(async move {
+                                    handler.handle_connect_options_write(new_value).await
+                                })
+                            })
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                // DPP bootstrap URI characteristic
+                Characteristic {
+                    uuid: CONNECT_DPP_URI_CHAR_UUID,
+                    read: Some(CharacteristicRead {
+                        read: true,
+                        fun: {
+                            let handler = handler.clone();
+                            Box::new(move |_req| {
+                                let handler = handler.clone();
+                                Box::pin(async move { handler.handle_dpp_uri_read().await })
+                            })
+                        },
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
                 // Control characteristic
                 Characteristic {
                     uuid: CONNECT_CONTROL_CHAR_UUID,
@@ -3,7 +3,7 @@
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::core::types::SessionId;
+use crate::core::types::{ConnectOptions, ScanResultsQuery, SessionId, Ssid};
 
 /// BLE client session state
 #[derive(Debug)]
@@ -12,6 +12,9 @@ pub struct BleSession {
     authorized: Arc<RwLock<bool>>,
     ssid_buffer: Arc<RwLock<Vec<u8>>>,
     psk_buffer: Arc<RwLock<Option<[u8; 32]>>>,
+    options_buffer: Arc<RwLock<Vec<u8>>>,
+    interface: Arc<RwLock<Option<String>>>,
+    results_query: Arc<RwLock<ScanResultsQuery>>,
 }
 
 impl BleSession {
@@ -22,6 +25,9 @@ impl BleSession {
             authorized: Arc::new(RwLock::new(false)),
             ssid_buffer: Arc::new(RwLock::new(Vec::new())),
             psk_buffer: Arc::new(RwLock::new(None)),
+            options_buffer: Arc::new(RwLock::new(Vec::new())),
+            interface: Arc::new(RwLock::new(None)),
+            results_query: Arc::new(RwLock::new(ScanResultsQuery::default())),
         }
     }
 
@@ -45,10 +51,9 @@ impl BleSession {
         self.ssid_buffer.write().await.extend_from_slice(data);
     }
 
-    /// Get accumulated SSID as UTF-8 string
-    pub async fn get_ssid(&self) -> Result<String, String> {
-        let buffer = self.ssid_buffer.read().await;
-        String::from_utf8(buffer.clone()).map_err(|e| format!("Invalid UTF-8 in SSID: {}", e))
+    /// Get accumulated SSID octets
+    pub async fn get_ssid(&self) -> Ssid {
+        Ssid::new(self.ssid_buffer.read().await.clone())
     }
 
     /// Clear SSID buffer
@@ -71,10 +76,64 @@ impl BleSession {
         *self.psk_buffer.write().await = None;
     }
 
-    /// Clear all buffers (SSID and PSK)
+    /// Append data to connect options buffer
+    pub async fn append_connect_options(&self, data: &[u8]) {
+        self.options_buffer.write().await.extend_from_slice(data);
+    }
+
+    /// Get accumulated connect options (defaults if none were written)
+    pub async fn get_connect_options(&self) -> Result<ConnectOptions, String> {
+        let buffer = self.options_buffer.read().await;
+        if buffer.is_empty() {
+            return Ok(ConnectOptions::default());
+        }
+        serde_json::from_slice(&buffer).map_err(|e| format!("Invalid connect options: {}", e))
+    }
+
+    /// Clear connect options buffer
+    pub async fn clear_connect_options(&self) {
+        self.options_buffer.write().await.clear();
+    }
+
+    /// Select the interface operations apply to (`None` for the default)
+    pub async fn set_interface(&self, interface: Option<String>) {
+        *self.interface.write().await = interface;
+    }
+
+    /// Get the selected interface
+    pub async fn interface(&self) -> Option<String> {
+        self.interface.read().await.clone()
+    }
+
+    /// Set how scan results are presented to this session
+    pub async fn set_results_query(&self, query: ScanResultsQuery) {
+        *self.results_query.write().await = query;
+    }
+
+    /// Get the scan results query
+    pub async fn results_query(&self) -> ScanResultsQuery {
+        self.results_query.read().await.clone()
+    }
+
+    /// Start over for a new client
+    ///
+    /// The link is shared by all clients over time, so each authorization
+    /// gets a new ID and fresh state. Returns the ID of the previous client.
+    pub async fn restart(&mut self) -> SessionId {
+        let previous = self.id;
+        self.id = SessionId::new();
+        self.set_authorized(false).await;
+        self.clear_buffers().await;
+        self.set_interface(None).await;
+        self.set_results_query(ScanResultsQuery::default()).await;
+        previous
+    }
+
+    /// Clear all buffers (SSID, PSK and connect options)
     pub async fn clear_buffers(&self) {
         self.clear_ssid().await;
         self.clear_psk().await;
+        self.clear_connect_options().await;
     }
 }
 
@@ -107,6 +166,21 @@ mod tests {
         assert!(!session.is_authorized().await);
     }
 
+    #[tokio::test]
+    async fn test_restart() {
+        let mut session = BleSession::new();
+        let first = session.id();
+        session.set_authorized(true).await;
+        session.append_ssid(b"TestSSID").await;
+        session.set_interface(Some("wlan1".to_string())).await;
+
+        assert_eq!(session.restart().await, first);
+        assert_ne!(session.id(), first);
+        assert!(!session.is_authorized().await);
+        assert_eq!(session.get_ssid().await, "");
+        assert_eq!(session.interface().await, None);
+    }
+
     #[tokio::test]
     async fn test_ssid_accumulation() {
         let session = BleSession::new();
@@ -114,20 +188,22 @@ mod tests {
         session.append_ssid(b"My").await;
         session.append_ssid(b"Network").await;
 
-        let ssid = session.get_ssid().await.unwrap();
+        let ssid = session.get_ssid().await;
         assert_eq!(ssid, "MyNetwork");
 
         session.clear_ssid().await;
-        let ssid = session.get_ssid().await.unwrap();
+        let ssid = session.get_ssid().await;
         assert_eq!(ssid, "");
     }
 
     #[tokio::test]
-    async fn test_ssid_invalid_utf8() {
+    async fn test_ssid_non_utf8() {
         let session = BleSession::new();
         session.append_ssid(&[0xFF, 0xFE]).await;
 
-        assert!(session.get_ssid().await.is_err());
+        let ssid = session.get_ssid().await;
+        assert_eq!(ssid.as_bytes(), &[0xFF, 0xFE]);
+        assert!(ssid.as_str().is_none());
     }
 
     #[tokio::test]
@@ -143,16 +219,46 @@ mod tests {
         assert!(session.get_psk().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_connect_options_accumulation() {
+        let session = BleSession::new();
+        assert_eq!(
+            session.get_connect_options().await.unwrap(),
+            ConnectOptions::default()
+        );
+
+        session.append_connect_options(br#"{"bssid":"aa:bb:"#).await;
+        session
+            .append_connect_options(br#"cc:dd:ee:ff","band":"5ghz"}"#)
+            .await;
+
+        let options = session.get_connect_options().await.unwrap();
+        assert_eq!(options.bssid.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+
+        session.clear_connect_options().await;
+        session.append_connect_options(b"{broken").await;
+        assert!(session.get_connect_options().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_clear_buffers() {
         let session = BleSession::new();
 
         session.append_ssid(b"TestSSID").await;
         session.set_psk([1u8; 32]).await;
+        session.append_connect_options(b"{}").await;
+        session.set_interface(Some("wlan1".to_string())).await;
 
         session.clear_buffers().await;
 
-        assert_eq!(session.get_ssid().await.unwrap(), "");
+        // The interface selection outlives a connection attempt
+        assert_eq!(session.interface().await.as_deref(), Some("wlan1"));
+
+        assert_eq!(session.get_ssid().await, "");
         assert!(session.get_psk().await.is_none());
+        assert_eq!(
+            session.get_connect_options().await.unwrap(),
+            ConnectOptions::default()
+        );
     }
 }
@@ -39,6 +39,31 @@ pub const SCAN_RESULTS_CHAR_UUID: Uuid = Uuid::from_bytes([
     0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x69,
 ]);
 
+/// Country code characteristic (read/write 2 ASCII characters, optional persist flag)
+pub const SCAN_COUNTRY_CHAR_UUID: Uuid = Uuid::from_bytes([
+    0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x70,
+]);
+
+/// Capabilities characteristic (read 2 bytes, little-endian feature bitmask)
+pub const SCAN_CAPABILITIES_CHAR_UUID: Uuid = Uuid::from_bytes([
+    0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x71,
+]);
+
+/// Interface characteristic (read managed names, write ASCII name to select)
+pub const SCAN_INTERFACE_CHAR_UUID: Uuid = Uuid::from_bytes([
+    0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x72,
+]);
+
+/// Network interfaces characteristic (read compact interface list, paginated)
+pub const SCAN_NETWORK_INTERFACES_CHAR_UUID: Uuid = Uuid::from_bytes([
+    0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x73,
+]);
+
+/// Site survey characteristic (read compact per-channel statistics, paginated)
+pub const SCAN_SURVEY_CHAR_UUID: Uuid = Uuid::from_bytes([
+    0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x74,
+]);
+
 // Connect service characteristics
 /// SSID write characteristic (accumulates partial writes)
 pub const CONNECT_SSID_CHAR_UUID: Uuid = Uuid::from_bytes([
@@ -60,6 +85,16 @@ pub const CONNECT_STATE_CHAR_UUID: Uuid = Uuid::from_bytes([
     0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x6d,
 ]);
 
+/// Connect options write characteristic (JSON, accumulates partial writes)
+pub const CONNECT_OPTIONS_CHAR_UUID: Uuid = Uuid::from_bytes([
+    0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x6e,
+]);
+
+/// DPP bootstrap URI characteristic (read in 100-byte chunks)
+pub const CONNECT_DPP_URI_CHAR_UUID: Uuid = Uuid::from_bytes([
+    0xd6, 0x9a, 0x37, 0xee, 0x1d, 0x8a, 0x43, 0x29, 0xbd, 0x24, 0x25, 0xdb, 0x4a, 0xf3, 0xc8, 0x6f,
+]);
+
 /// Maximum chunk size for BLE characteristics
 pub const MAX_CHUNK_SIZE: usize = 100;
 
@@ -96,6 +131,13 @@ mod tests {
             CONNECT_PSK_CHAR_UUID,
             CONNECT_CONTROL_CHAR_UUID,
             CONNECT_STATE_CHAR_UUID,
+            CONNECT_OPTIONS_CHAR_UUID,
+            CONNECT_DPP_URI_CHAR_UUID,
+            SCAN_COUNTRY_CHAR_UUID,
+            SCAN_CAPABILITIES_CHAR_UUID,
+            SCAN_INTERFACE_CHAR_UUID,
+            SCAN_NETWORK_INTERFACES_CHAR_UUID,
+            SCAN_SURVEY_CHAR_UUID,
         ];
 
         for (i, uuid1) in uuids.iter().enumerate() {
@@ -5,57 +5,141 @@ use std::sync::Arc;
 use crate::{
     backend::WifiBackend,
     core::{
-        authorization::AuthorizationService, connector::ConnectionService, scanner::ScanService,
+        authorization::AuthorizationService,
+        error::{ServiceError, WifiError},
+        netif,
+        scan_view::{self, ScanResultsView},
+        service::InterfaceService,
+        survey::{DEFAULT_SURVEY_SCANS, MAX_SURVEY_SCANS},
+        types::{
+            ScanOptions, ScanResultsQuery, SessionId, WpsMethod, normalize_bssid,
+            normalize_country_code,
+        },
     },
     protocol::{
-        ConnectResponse, DisconnectResponse, JsonRpcError, JsonRpcRequest, JsonRpcResponse,
-        Request, RequestId, Response, ScanResultsResponse, ScanStartedResponse, StatusResponse,
+        CapabilitiesResponse, ConnectResponse, CountryParams, CountryResponse, DisconnectResponse,
+        DppResponse, GroupedScanResultsResponse, InterfacesResponse, JsonRpcError, JsonRpcRequest,
+        JsonRpcResponse, NetworkDetailsParams, NetworkDetailsResponse, OkResponse, Request,
+        RequestId, Response, ScanResultsResponse, ScanStartedResponse, SiteSurveyResponse,
+        StatusResponse, SurveyParams, WpsResponse,
     },
 };
 
 /// JSON-RPC request handler
 pub struct RequestHandler<B: WifiBackend> {
-    scan_service: Arc<ScanService<B>>,
-    connect_service: Arc<ConnectionService<B>>,
+    interfaces: Vec<Arc<InterfaceService<B>>>,
     _auth_service: Arc<AuthorizationService>,
 }
 
 impl<B: WifiBackend> RequestHandler<B> {
     /// Create a new request handler
+    ///
+    /// Requests without an `interface` param go to the first interface.
     pub fn new(
-        scan_service: Arc<ScanService<B>>,
-        connect_service: Arc<ConnectionService<B>>,
+        interfaces: Vec<Arc<InterfaceService<B>>>,
         auth_service: Arc<AuthorizationService>,
     ) -> Self {
         Self {
-            scan_service,
-            connect_service,
+            interfaces,
             _auth_service: auth_service,
         }
     }
 
-    /// Handle a JSON-RPC request
+    /// Handle a JSON-RPC request outside of a client session
     pub async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+        self.handle_session_request(None, request).await
+    }
+
+    /// Handle a JSON-RPC request of a client session
+    ///
+    /// Scans the session starts count against its scan quota.
+    pub async fn handle_session_request(
+        &self,
+        session: Option<SessionId>,
+        request: JsonRpcRequest,
+    ) -> JsonRpcResponse {
+        let interface = match request.interface.as_deref() {
+            Some(name) => self.interfaces.iter().find(|iface| iface.name == name),
+            None => self.interfaces.first(),
+        };
+        let Some(iface) = interface else {
+            let name = request.interface.unwrap_or_default();
+            return JsonRpcResponse::error(
+                JsonRpcError::invalid_params(format!("Unknown interface: {}", name)),
+                request.id,
+            );
+        };
+        iface.scanner.client_active().await;
+
         match request.request {
-            Request::Scan => self.handle_scan(request.id).await,
-            Request::GetScanResults => self.handle_get_scan_results(request.id).await,
-            Request::Connect(params) => self.handle_connect(request.id, params).await,
-            Request::Disconnect => self.handle_disconnect(request.id).await,
-            Request::GetStatus => self.handle_get_status(request.id).await,
+            Request::Scan(options) => {
+                self.handle_scan(iface, request.id, session, options.unwrap_or_default())
+                    .await
+            }
+            Request::CancelScan => self.handle_cancel_scan(iface, request.id).await,
+            Request::GetScanResults(query) => {
+                self.handle_get_scan_results(iface, request.id, query.unwrap_or_default())
+                    .await
+            }
+            Request::GetNetworkDetails(params) => {
+                self.handle_get_network_details(iface, request.id, params)
+                    .await
+            }
+            Request::SiteSurvey(params) => {
+                self.handle_site_survey(iface, request.id, session, params.unwrap_or_default())
+                    .await
+            }
+            Request::Connect(params) => self.handle_connect(iface, request.id, params).await,
+            Request::WpsPbc(params) => {
+                let bssid = params.and_then(|p| p.bssid);
+                self.handle_wps(iface, request.id, WpsMethod::PushButton, bssid)
+                    .await
+            }
+            Request::WpsPin(params) => {
+                let params = params.unwrap_or_default();
+                self.handle_wps(iface, request.id, WpsMethod::Pin(params.pin), params.bssid)
+                    .await
+            }
+            Request::WpsCancel => self.handle_wps_cancel(iface, request.id).await,
+            Request::DppStart => self.handle_dpp_start(iface, request.id).await,
+            Request::DppStop => self.handle_dpp_stop(iface, request.id).await,
+            Request::GetCountry => self.handle_get_country(iface, request.id).await,
+            Request::SetCountry(params) => self.handle_set_country(iface, request.id, params).await,
+            Request::GetCapabilities => self.handle_get_capabilities(iface, request.id).await,
+            Request::ListInterfaces => self.handle_list_interfaces(request.id).await,
+            Request::Disconnect => self.handle_disconnect(iface, request.id).await,
+            Request::GetStatus => self.handle_get_status(iface, request.id).await,
         }
     }
 
-    async fn handle_scan(&self, id: RequestId) -> JsonRpcResponse {
-        match self.scan_service.start_scan().await {
-            Ok(()) => {
-                let state = self.scan_service.state().await;
-                JsonRpcResponse::success(Response::ScanStarted(ScanStartedResponse::ok(state)), id)
+    async fn handle_scan(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+        session: Option<SessionId>,
+        options: ScanOptions,
+    ) -> JsonRpcResponse {
+        let options = match options.normalized() {
+            Ok(options) => options,
+            Err(e) => {
+                return JsonRpcResponse::error(JsonRpcError::invalid_params(e), id);
+            }
+        };
+
+        match iface.scanner.start_session_scan(session, &options).await {
+            Ok(handle) => {
+                let state = iface.scanner.state().await;
+                let response = if handle.cached() {
+                    ScanStartedResponse::cached(state)
+                } else {
+                    ScanStartedResponse::ok(state, handle.joined())
+                };
+                JsonRpcResponse::success(Response::ScanStarted(response), id)
             }
             Err(e) => {
                 let error = match e {
-                    crate::core::error::ServiceError::OperationInProgress => {
-                        JsonRpcError::scan_in_progress()
-                    }
+                    ServiceError::OperationInProgress => JsonRpcError::scan_in_progress(),
+                    ServiceError::ScanRefused(refusal) => JsonRpcError::scan_refused(&refusal),
                     _ => JsonRpcError::backend_error(e.to_string()),
                 };
                 JsonRpcResponse::error(error, id)
@@ -63,12 +147,46 @@ impl<B: WifiBackend> RequestHandler<B> {
         }
     }
 
-    async fn handle_get_scan_results(&self, id: RequestId) -> JsonRpcResponse {
-        match self.scan_service.results().await {
-            Ok(networks) => JsonRpcResponse::success(
-                Response::ScanResults(ScanResultsResponse::ok(networks)),
-                id,
-            ),
+    /// Forget the scans counted for a closed session
+    pub async fn end_session(&self, session: SessionId) {
+        for iface in &self.interfaces {
+            iface.scanner.end_session(session).await;
+        }
+    }
+
+    async fn handle_cancel_scan(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+    ) -> JsonRpcResponse {
+        match iface.scanner.cancel_scan().await {
+            Ok(()) => JsonRpcResponse::success(Response::Ok(OkResponse::ok()), id),
+            Err(e @ ServiceError::InvalidStateTransition { .. }) => {
+                JsonRpcResponse::error(JsonRpcError::invalid_state(e.to_string()), id)
+            }
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
+    async fn handle_get_scan_results(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+        query: ScanResultsQuery,
+    ) -> JsonRpcResponse {
+        match iface.scanner.results().await {
+            Ok(networks) => {
+                let page = scan_view::apply(networks, &query);
+                let response = match page.view {
+                    ScanResultsView::Networks(networks) => {
+                        Response::ScanResults(ScanResultsResponse::ok(networks, page.total))
+                    }
+                    ScanResultsView::Groups(groups) => Response::GroupedScanResults(
+                        GroupedScanResultsResponse::ok(groups, page.total),
+                    ),
+                };
+                JsonRpcResponse::success(response, id)
+            }
             Err(e) => {
                 let error = JsonRpcError::invalid_state(e.to_string());
                 JsonRpcResponse::error(error, id)
@@ -76,8 +194,67 @@ impl<B: WifiBackend> RequestHandler<B> {
         }
     }
 
+    async fn handle_site_survey(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+        session: Option<SessionId>,
+        params: SurveyParams,
+    ) -> JsonRpcResponse {
+        let scans = params.scans.unwrap_or(DEFAULT_SURVEY_SCANS);
+        if !(1..=MAX_SURVEY_SCANS).contains(&scans) {
+            return JsonRpcResponse::error(
+                JsonRpcError::invalid_params(format!(
+                    "scans must be between 1 and {}",
+                    MAX_SURVEY_SCANS
+                )),
+                id,
+            );
+        }
+
+        match iface.scanner.survey(session, scans).await {
+            Ok(report) => {
+                JsonRpcResponse::success(Response::SiteSurvey(SiteSurveyResponse::ok(report)), id)
+            }
+            Err(e) => {
+                let error = match e {
+                    ServiceError::OperationInProgress => JsonRpcError::scan_in_progress(),
+                    ServiceError::ScanRefused(refusal) => JsonRpcError::scan_refused(&refusal),
+                    _ => JsonRpcError::backend_error(e.to_string()),
+                };
+                JsonRpcResponse::error(error, id)
+            }
+        }
+    }
+
+    async fn handle_get_network_details(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+        params: NetworkDetailsParams,
+    ) -> JsonRpcResponse {
+        let Some(bssid) = normalize_bssid(&params.bssid) else {
+            return JsonRpcResponse::error(
+                JsonRpcError::invalid_params(format!("Invalid BSSID: {}", params.bssid)),
+                id,
+            );
+        };
+
+        match iface.scanner.network_details(&bssid).await {
+            Ok(details) => JsonRpcResponse::success(
+                Response::NetworkDetails(NetworkDetailsResponse::ok(details)),
+                id,
+            ),
+            Err(ServiceError::Backend(e @ WifiError::BssNotFound(_))) => {
+                JsonRpcResponse::error(JsonRpcError::invalid_params(e.to_string()), id)
+            }
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
     async fn handle_connect(
         &self,
+        iface: &InterfaceService<B>,
         id: RequestId,
         params: crate::protocol::ConnectParams,
     ) -> JsonRpcResponse {
@@ -89,17 +266,92 @@ impl<B: WifiBackend> RequestHandler<B> {
             }
         };
 
+        let options = match params.options.normalized() {
+            Ok(options) => options,
+            Err(e) => {
+                return JsonRpcResponse::error(JsonRpcError::invalid_params(e), id);
+            }
+        };
+
         // Attempt connection
-        match self.connect_service.connect(&params.ssid, &psk).await {
+        match iface.connector.connect(&params.ssid, &psk, &options).await {
             Ok(()) => {
-                let state = self.connect_service.state().await;
+                let state = iface.connector.state().await;
                 JsonRpcResponse::success(Response::Connect(ConnectResponse::ok(state)), id)
             }
             Err(e) => {
                 let error = match e {
-                    crate::core::error::ServiceError::OperationInProgress => {
-                        JsonRpcError::scan_in_progress()
-                    }
+                    ServiceError::OperationInProgress => JsonRpcError::scan_in_progress(),
+                    _ => JsonRpcError::backend_error(e.to_string()),
+                };
+                JsonRpcResponse::error(error, id)
+            }
+        }
+    }
+
+    async fn handle_wps(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+        method: WpsMethod,
+        bssid: Option<String>,
+    ) -> JsonRpcResponse {
+        if let Err(e) = method.validate() {
+            return JsonRpcResponse::error(JsonRpcError::invalid_params(e), id);
+        }
+
+        let bssid = match bssid {
+            Some(bssid) => match normalize_bssid(&bssid) {
+                Some(bssid) => Some(bssid),
+                None => {
+                    return JsonRpcResponse::error(
+                        JsonRpcError::invalid_params(format!("Invalid BSSID: {}", bssid)),
+                        id,
+                    );
+                }
+            },
+            None => None,
+        };
+
+        match iface.connector.start_wps(&method, bssid.as_deref()).await {
+            Ok(pin) => {
+                let state = iface.connector.state().await;
+                JsonRpcResponse::success(Response::Wps(WpsResponse::ok(state, pin)), id)
+            }
+            Err(e) => {
+                let error = match e {
+                    ServiceError::OperationInProgress => JsonRpcError::scan_in_progress(),
+                    _ => JsonRpcError::backend_error(e.to_string()),
+                };
+                JsonRpcResponse::error(error, id)
+            }
+        }
+    }
+
+    async fn handle_wps_cancel(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+    ) -> JsonRpcResponse {
+        match iface.connector.cancel_wps().await {
+            Ok(()) => JsonRpcResponse::success(Response::Ok(OkResponse::ok()), id),
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
+    async fn handle_dpp_start(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+    ) -> JsonRpcResponse {
+        match iface.connector.start_dpp().await {
+            Ok(uri) => {
+                let state = iface.connector.state().await;
+                JsonRpcResponse::success(Response::Dpp(DppResponse::ok(state, uri)), id)
+            }
+            Err(e) => {
+                let error = match e {
+                    ServiceError::OperationInProgress => JsonRpcError::scan_in_progress(),
                     _ => JsonRpcError::backend_error(e.to_string()),
                 };
                 JsonRpcResponse::error(error, id)
@@ -107,15 +359,88 @@ impl<B: WifiBackend> RequestHandler<B> {
         }
     }
 
-    async fn handle_disconnect(&self, id: RequestId) -> JsonRpcResponse {
-        match self.connect_service.disconnect().await {
+    async fn handle_dpp_stop(&self, iface: &InterfaceService<B>, id: RequestId) -> JsonRpcResponse {
+        match iface.connector.stop_dpp().await {
+            Ok(()) => JsonRpcResponse::success(Response::Ok(OkResponse::ok()), id),
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
+    async fn handle_get_country(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+    ) -> JsonRpcResponse {
+        match iface.connector.country().await {
+            Ok(country) => {
+                JsonRpcResponse::success(Response::Country(CountryResponse::ok(country)), id)
+            }
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
+    async fn handle_set_country(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+        params: CountryParams,
+    ) -> JsonRpcResponse {
+        let Some(country) = normalize_country_code(&params.country) else {
+            return JsonRpcResponse::error(
+                JsonRpcError::invalid_params(format!("Invalid country code: {}", params.country)),
+                id,
+            );
+        };
+
+        match iface.connector.set_country(&country, params.persist).await {
+            Ok(()) => {
+                JsonRpcResponse::success(Response::Country(CountryResponse::ok(Some(country))), id)
+            }
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
+    async fn handle_get_capabilities(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+    ) -> JsonRpcResponse {
+        match iface.connector.capabilities().await {
+            Ok(capabilities) => JsonRpcResponse::success(
+                Response::Capabilities(CapabilitiesResponse::ok(capabilities)),
+                id,
+            ),
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
+    async fn handle_list_interfaces(&self, id: RequestId) -> JsonRpcResponse {
+        match netif::list_interfaces().await {
+            Ok(interfaces) => JsonRpcResponse::success(
+                Response::Interfaces(InterfacesResponse::ok(interfaces)),
+                id,
+            ),
+            Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
+        }
+    }
+
+    async fn handle_disconnect(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+    ) -> JsonRpcResponse {
+        match iface.connector.disconnect().await {
             Ok(()) => JsonRpcResponse::success(Response::Disconnect(DisconnectResponse::ok()), id),
             Err(e) => JsonRpcResponse::error(JsonRpcError::backend_error(e.to_string()), id),
         }
     }
 
-    async fn handle_get_status(&self, id: RequestId) -> JsonRpcResponse {
-        let status = self.connect_service.status().await;
+    async fn handle_get_status(
+        &self,
+        iface: &InterfaceService<B>,
+        id: RequestId,
+    ) -> JsonRpcResponse {
+        let status = iface.connector.status().await;
         JsonRpcResponse::success(Response::Status(StatusResponse::ok(status)), id)
     }
 }
@@ -123,26 +448,46 @@ impl<B: WifiBackend> RequestHandler<B> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{backend::MockWifiBackend, core::types::WifiNetwork};
+    use crate::{
+        backend::{MockWifiBackend, WifiBackend},
+        core::{
+            connector::ConnectionService,
+            scan_policy::ScanPolicy,
+            scanner::ScanService,
+            types::{Capabilities, ConnectOptions, ScanState, Ssid, WifiNetwork},
+        },
+    };
+
+    fn interfaces(
+        scan_service: Arc<ScanService<MockWifiBackend>>,
+        connect_service: Arc<ConnectionService<MockWifiBackend>>,
+    ) -> Vec<Arc<InterfaceService<MockWifiBackend>>> {
+        vec![Arc::new(InterfaceService::from_parts(
+            "wlan0".to_string(),
+            scan_service,
+            connect_service,
+        ))]
+    }
 
     #[tokio::test]
     async fn test_handle_scan_request() {
         let backend = Arc::new(MockWifiBackend::new());
         backend
             .set_scan_results(vec![WifiNetwork {
-                ssid: "TestNet".to_string(),
+                ssid: "TestNet".into(),
                 mac: "aa:bb:cc:dd:ee:ff".to_string(),
                 channel: 6,
                 rssi: -65,
+                ..Default::default()
             }])
             .await;
 
         let scan_service = Arc::new(ScanService::new(backend.clone()));
         let connect_service = Arc::new(ConnectionService::new(backend.clone()));
         let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
-        let handler = RequestHandler::new(scan_service, connect_service, auth_service);
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
 
-        let request = JsonRpcRequest::new(Request::Scan, RequestId::Number(1));
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(1));
         let response = handler.handle_request(request).await;
 
         assert!(response.result.is_some());
@@ -150,26 +495,272 @@ mod tests {
         assert_eq!(response.id, RequestId::Number(1));
     }
 
+    #[tokio::test]
+    async fn test_handle_scan_options() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"scan","params":{"ssids":["Hidden"],"passive":true},"id":1}"#,
+        )
+        .unwrap();
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_PARAMS);
+
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"scan","params":{"freq_list":[2412],"passive":true},"id":2}"#,
+        )
+        .unwrap();
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_none());
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        let options = backend.last_scan_options().await.unwrap();
+        assert_eq!(options.freq_list, Some(vec![2412]));
+        assert!(options.passive);
+    }
+
     #[tokio::test]
     async fn test_handle_scan_in_progress() {
         let backend = Arc::new(MockWifiBackend::new());
         let scan_service = Arc::new(ScanService::new(backend.clone()));
         let connect_service = Arc::new(ConnectionService::new(backend.clone()));
         let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
-        let handler = RequestHandler::new(scan_service.clone(), connect_service, auth_service);
+        let handler = RequestHandler::new(
+            interfaces(scan_service.clone(), connect_service),
+            auth_service,
+        );
 
         // Start first scan
-        scan_service.start_scan().await.unwrap();
+        backend
+            .set_scan_latency(tokio::time::Duration::from_secs(1))
+            .await;
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(1));
+        let response = handler.handle_request(request).await;
+        let Some(Response::ScanStarted(started)) = response.result else {
+            panic!("expected scan started");
+        };
+        assert!(!started.joined);
 
-        // Try to start second scan
-        let request = JsonRpcRequest::new(Request::Scan, RequestId::Number(2));
+        // A second scan with the same options joins the first
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(2));
         let response = handler.handle_request(request).await;
+        let Some(Response::ScanStarted(started)) = response.result else {
+            panic!("expected scan started");
+        };
+        assert!(started.joined);
+        assert_eq!(started.state, ScanState::Scanning);
 
-        assert!(response.result.is_none());
-        assert!(response.error.is_some());
+        // One with other options is refused
+        let options = ScanOptions {
+            passive: true,
+            ..Default::default()
+        };
+        let request = JsonRpcRequest::new(Request::Scan(Some(options)), RequestId::Number(3));
+        let response = handler.handle_request(request).await;
 
+        assert!(response.result.is_none());
         let error = response.error.unwrap();
         assert_eq!(error.code, JsonRpcError::SCAN_IN_PROGRESS);
+        assert!(scan_service.results().await.is_err());
+
+        // Cancelling returns to idle, a second cancel has nothing to stop
+        let request = JsonRpcRequest::new(Request::CancelScan, RequestId::Number(4));
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_none());
+        assert_eq!(scan_service.state().await, ScanState::Idle);
+
+        let request = JsonRpcRequest::new(Request::CancelScan, RequestId::Number(5));
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_STATE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handle_scan_policy() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(
+            interfaces(scan_service.clone(), connect_service),
+            auth_service,
+        );
+        scan_service
+            .set_policy(ScanPolicy {
+                min_interval: tokio::time::Duration::from_secs(60),
+                session_quota: Some(1),
+                defer_while_connected: false,
+            })
+            .await;
+        let session = SessionId::new();
+
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(1));
+        let response = handler.handle_session_request(Some(session), request).await;
+        let Some(Response::ScanStarted(started)) = response.result else {
+            panic!("expected scan started");
+        };
+        assert!(!started.cached);
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        // Another session inside the interval gets the cached results
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(2));
+        let response = handler.handle_request(request).await;
+        let Some(Response::ScanStarted(started)) = response.result else {
+            panic!("expected scan started");
+        };
+        assert!(started.cached);
+        assert_eq!(started.state, ScanState::Finished);
+
+        // The first session has used up its quota, the cache still answers
+        tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(3));
+        let response = handler.handle_session_request(Some(session), request).await;
+        let Some(Response::ScanStarted(started)) = response.result else {
+            panic!("expected cached results");
+        };
+        assert!(started.cached);
+
+        handler.end_session(session).await;
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(4));
+        let response = handler.handle_session_request(Some(session), request).await;
+        let Some(Response::ScanStarted(started)) = response.result else {
+            panic!("expected scan started");
+        };
+        assert!(!started.cached);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handle_scan_refused() {
+        let backend = Arc::new(MockWifiBackend::new());
+        // Failed scans leave no results to answer refused scans from
+        backend.set_scan_failure(true).await;
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(
+            interfaces(scan_service.clone(), connect_service),
+            auth_service,
+        );
+        scan_service
+            .set_policy(ScanPolicy {
+                session_quota: Some(1),
+                ..Default::default()
+            })
+            .await;
+        let session = SessionId::new();
+
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(1));
+        let response = handler.handle_session_request(Some(session), request).await;
+        assert!(response.error.is_none());
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(2));
+        let response = handler.handle_session_request(Some(session), request).await;
+        let error = response.error.unwrap();
+        assert_eq!(error.code, JsonRpcError::SCAN_REFUSED);
+        assert_eq!(
+            error.data,
+            Some(serde_json::json!({"reason": "quota", "quota": 1}))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handle_scan_result_markers() {
+        let network = |ssid: &str, mac: &str| WifiNetwork {
+            ssid: ssid.into(),
+            mac: mac.to_string(),
+            ..Default::default()
+        };
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![
+                network("Home", "aa:00:00:00:00:01"),
+                network("Office", "aa:00:00:00:00:02"),
+                network("Cafe", "aa:00:00:00:00:03"),
+            ])
+            .await;
+        backend.set_saved_networks(vec![Ssid::from("Office")]).await;
+        backend
+            .connect(&"Home".into(), &[0; 32], &ConnectOptions::default())
+            .await
+            .unwrap();
+        backend.complete_connection("192.168.1.100").await;
+
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(
+            interfaces(scan_service.clone(), connect_service),
+            auth_service,
+        );
+        scan_service
+            .start_scan(&ScanOptions::default())
+            .await
+            .unwrap()
+            .wait()
+            .await
+            .unwrap();
+
+        let request = JsonRpcRequest::new(Request::GetScanResults(None), RequestId::Number(1));
+        let response = handler.handle_request(request).await;
+        let Some(Response::ScanResults(result)) = response.result else {
+            panic!("expected results");
+        };
+        let markers: Vec<_> = result
+            .networks
+            .iter()
+            .map(|n| (n.ssid.to_string(), n.connected, n.saved))
+            .collect();
+        assert_eq!(
+            markers,
+            [
+                ("Home".to_string(), true, true),
+                ("Office".to_string(), false, true),
+                ("Cafe".to_string(), false, false),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handle_site_survey() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![WifiNetwork {
+                ssid: "TestNet".into(),
+                mac: "aa:bb:cc:dd:ee:ff".to_string(),
+                channel: 36,
+                frequency: 5180,
+                rssi: -60,
+                ..Default::default()
+            }])
+            .await;
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"site_survey","params":{"scans":2},"id":1}"#,
+        )
+        .unwrap();
+        let response = handler.handle_request(request).await;
+        let Some(Response::SiteSurvey(result)) = response.result else {
+            panic!("expected survey");
+        };
+        assert_eq!(result.survey.scans, 2);
+        assert_eq!(result.survey.channels.len(), 1);
+        assert_eq!(result.survey.channels[0].channel, 36);
+        assert_eq!(result.survey.channels[0].bss_count, 1);
+
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"site_survey","params":{"scans":0},"id":2}"#,
+        )
+        .unwrap();
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_PARAMS);
     }
 
     #[tokio::test]
@@ -177,30 +768,364 @@ mod tests {
         let backend = Arc::new(MockWifiBackend::new());
         backend
             .set_scan_results(vec![WifiNetwork {
-                ssid: "TestNet".to_string(),
+                ssid: "TestNet".into(),
                 mac: "aa:bb:cc:dd:ee:ff".to_string(),
                 channel: 6,
                 rssi: -65,
+                ..Default::default()
             }])
             .await;
 
         let scan_service = Arc::new(ScanService::new(backend.clone()));
         let connect_service = Arc::new(ConnectionService::new(backend.clone()));
         let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
-        let handler = RequestHandler::new(scan_service.clone(), connect_service, auth_service);
+        let handler = RequestHandler::new(
+            interfaces(scan_service.clone(), connect_service),
+            auth_service,
+        );
 
         // Start and complete scan
-        scan_service.start_scan().await.unwrap();
+        scan_service
+            .start_scan(&ScanOptions::default())
+            .await
+            .unwrap();
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
 
         // Get results
         let request = JsonRpcRequest::new(
-            Request::GetScanResults,
+            Request::GetScanResults(None),
             RequestId::String("abc".to_string()),
         );
         let response = handler.handle_request(request).await;
 
         assert!(response.result.is_some());
         assert!(response.error.is_none());
+
+        // Grouped by SSID
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"get_scan_results","params":{"group":true},"id":1}"#,
+        )
+        .unwrap();
+        let response = handler.handle_request(request).await;
+
+        let Some(Response::GroupedScanResults(result)) = response.result else {
+            panic!("expected grouped results");
+        };
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.total, 1);
+        assert_eq!(result.groups[0].ssid, "TestNet");
+        assert_eq!(result.groups[0].bssids[0].mac, "aa:bb:cc:dd:ee:ff");
+
+        // Filtered out
+        let request: JsonRpcRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"get_scan_results","params":{"min_rssi":-50},"id":2}"#,
+        )
+        .unwrap();
+        let response = handler.handle_request(request).await;
+
+        let Some(Response::ScanResults(result)) = response.result else {
+            panic!("expected results");
+        };
+        assert!(result.networks.is_empty());
+        assert_eq!(result.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_handle_connect_with_options() {
+        use crate::{
+            core::types::{Band, ConnectOptions},
+            protocol::ConnectParams,
+        };
+
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        let request = JsonRpcRequest::new(
+            Request::Connect(ConnectParams {
+                ssid: "Warehouse".into(),
+                psk: "a".repeat(64),
+                options: ConnectOptions {
+                    bssid: Some("AA:BB:CC:DD:EE:FF".to_string()),
+                    freq_list: None,
+                    band: Some(Band::Band5Ghz),
+                },
+            }),
+            RequestId::Number(1),
+        );
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_none());
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        let options = backend.last_connect_options().await.unwrap();
+        assert_eq!(options.bssid.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(options.freq_list, Some(Band::Band5Ghz.frequencies()));
+
+        // Invalid options are rejected before connecting
+        let request = JsonRpcRequest::new(
+            Request::Connect(ConnectParams {
+                ssid: "Warehouse".into(),
+                psk: "a".repeat(64),
+                options: ConnectOptions {
+                    freq_list: Some(vec![1234]),
+                    ..Default::default()
+                },
+            }),
+            RequestId::Number(2),
+        );
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_handle_get_network_details() {
+        let backend = Arc::new(MockWifiBackend::new());
+        backend
+            .set_scan_results(vec![WifiNetwork {
+                ssid: "TestNet".into(),
+                mac: "aa:bb:cc:dd:ee:ff".to_string(),
+                channel: 6,
+                rssi: -65,
+                ..Default::default()
+            }])
+            .await;
+
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        // BSSID lookup is case-insensitive
+        let request = JsonRpcRequest::new(
+            Request::GetNetworkDetails(NetworkDetailsParams {
+                bssid: "AA:BB:CC:DD:EE:FF".to_string(),
+            }),
+            RequestId::Number(1),
+        );
+        let response = handler.handle_request(request).await;
+        let Some(Response::NetworkDetails(details)) = response.result else {
+            panic!("expected network details, got {:?}", response);
+        };
+        assert_eq!(details.details.ssid, "TestNet");
+
+        // Unknown BSS
+        let request = JsonRpcRequest::new(
+            Request::GetNetworkDetails(NetworkDetailsParams {
+                bssid: "00:11:22:33:44:55".to_string(),
+            }),
+            RequestId::Number(2),
+        );
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_PARAMS);
+
+        // Malformed BSSID
+        let request = JsonRpcRequest::new(
+            Request::GetNetworkDetails(NetworkDetailsParams {
+                bssid: "not-a-mac".to_string(),
+            }),
+            RequestId::Number(3),
+        );
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_handle_wps() {
+        use crate::{core::types::ConnectionState, protocol::WpsPinParams};
+
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        // Bad checksum is rejected before reaching the backend
+        let request = JsonRpcRequest::new(
+            Request::WpsPin(Some(WpsPinParams {
+                bssid: None,
+                pin: Some("12345678".to_string()),
+            })),
+            RequestId::Number(1),
+        );
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_PARAMS);
+        assert_eq!(backend.wps_method().await, None);
+
+        let request = JsonRpcRequest::new(Request::WpsPin(None), RequestId::Number(2));
+        let response = handler.handle_request(request).await;
+        let Some(Response::Wps(result)) = response.result else {
+            panic!("Expected WPS response");
+        };
+        assert_eq!(result.pin, Some("12345670".to_string()));
+        assert_eq!(result.state, ConnectionState::WpsActive);
+
+        // Only one registration at a time
+        let request = JsonRpcRequest::new(Request::WpsPbc(None), RequestId::Number(3));
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_some());
+
+        let request = JsonRpcRequest::new(Request::WpsCancel, RequestId::Number(4));
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.result, Some(Response::Ok(OkResponse::ok())));
+        assert_eq!(backend.wps_method().await, None);
+
+        let request = JsonRpcRequest::new(Request::GetStatus, RequestId::Number(5));
+        let response = handler.handle_request(request).await;
+        let Some(Response::Status(result)) = response.result else {
+            panic!("Expected status response");
+        };
+        assert_eq!(result.connection.state, ConnectionState::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_handle_dpp() {
+        use crate::core::types::ConnectionState;
+
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        let request = JsonRpcRequest::new(Request::DppStart, RequestId::Number(1));
+        let response = handler.handle_request(request).await;
+        let Some(Response::Dpp(result)) = response.result else {
+            panic!("Expected DPP response");
+        };
+        assert!(result.uri.starts_with("DPP:"));
+        assert_eq!(result.state, ConnectionState::DppActive);
+
+        let request = JsonRpcRequest::new(Request::DppStop, RequestId::Number(2));
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_none());
+        assert!(!backend.dpp_listening().await);
+    }
+
+    #[tokio::test]
+    async fn test_handle_set_country() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        let request = JsonRpcRequest::new(
+            Request::SetCountry(CountryParams {
+                country: "Germany".to_string(),
+                persist: false,
+            }),
+            RequestId::Number(1),
+        );
+        let response = handler.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, JsonRpcError::INVALID_PARAMS);
+
+        let request = JsonRpcRequest::new(
+            Request::SetCountry(CountryParams {
+                country: "de".to_string(),
+                persist: true,
+            }),
+            RequestId::Number(2),
+        );
+        let response = handler.handle_request(request).await;
+        assert!(response.error.is_none());
+        assert!(backend.country_persisted().await);
+
+        let request = JsonRpcRequest::new(Request::GetCountry, RequestId::Number(3));
+        let response = handler.handle_request(request).await;
+        assert_eq!(
+            response.result,
+            Some(Response::Country(CountryResponse::ok(Some(
+                "DE".to_string()
+            ))))
+        );
+
+        // The active domain is part of the status
+        let request = JsonRpcRequest::new(Request::GetStatus, RequestId::Number(4));
+        let response = handler.handle_request(request).await;
+        let Some(Response::Status(result)) = response.result else {
+            panic!("Expected status response");
+        };
+        assert_eq!(result.connection.country, Some("DE".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_handle_get_capabilities() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend.clone()));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        let capabilities = Capabilities {
+            sae: true,
+            band_6ghz: true,
+            ..Default::default()
+        };
+        backend.set_capabilities(capabilities.clone()).await;
+
+        let request = JsonRpcRequest::new(Request::GetCapabilities, RequestId::Number(1));
+        let response = handler.handle_request(request).await;
+        assert_eq!(
+            response.result,
+            Some(Response::Capabilities(CapabilitiesResponse::ok(
+                capabilities
+            )))
+        );
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains(r#""sae":true"#));
+        assert!(json.contains(r#""wps":false"#));
+    }
+
+    #[tokio::test]
+    async fn test_handle_interface_selection() {
+        let wlan0 = Arc::new(MockWifiBackend::new());
+        let wlan1 = Arc::new(MockWifiBackend::new());
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(
+            vec![
+                Arc::new(InterfaceService::new("wlan0".to_string(), wlan0)),
+                Arc::new(InterfaceService::new("wlan1".to_string(), wlan1.clone())),
+            ],
+            auth_service,
+        );
+
+        wlan1.set_country("DE", false).await.unwrap();
+        let request = JsonRpcRequest::new(Request::GetCountry, RequestId::Number(1));
+        let response = handler.handle_request(request).await;
+        assert!(matches!(response.result, Some(Response::Country(ref r)) if r.country.is_none()));
+
+        let request =
+            JsonRpcRequest::new(Request::GetCountry, RequestId::Number(2)).with_interface("wlan1");
+        let response = handler.handle_request(request).await;
+        assert!(
+            matches!(response.result, Some(Response::Country(ref r)) if r.country.as_deref() == Some("DE"))
+        );
+
+        let request =
+            JsonRpcRequest::new(Request::GetStatus, RequestId::Number(3)).with_interface("eth0");
+        let response = handler.handle_request(request).await;
+        let error = response.error.unwrap();
+        assert_eq!(error.code, JsonRpcError::INVALID_PARAMS);
+        assert_eq!(error.message, "Unknown interface: eth0");
+    }
+
+    #[tokio::test]
+    async fn test_handle_list_interfaces() {
+        let backend = Arc::new(MockWifiBackend::new());
+        let scan_service = Arc::new(ScanService::new(backend.clone()));
+        let connect_service = Arc::new(ConnectionService::new(backend));
+        let auth_service = Arc::new(AuthorizationService::new("test-device".to_string()));
+        let handler = RequestHandler::new(interfaces(scan_service, connect_service), auth_service);
+
+        // Not limited to the managed interfaces
+        let request = JsonRpcRequest::new(Request::ListInterfaces, RequestId::Number(1));
+        let response = handler.handle_request(request).await;
+        let Some(Response::Interfaces(response)) = response.result else {
+            panic!("unexpected response: {:?}", response);
+        };
+        assert!(response.interfaces.iter().any(|i| i.name == "lo"));
     }
 }
@@ -10,9 +10,7 @@ use tracing::{error, info, warn};
 
 use crate::{
     backend::WifiBackend,
-    core::{
-        authorization::AuthorizationService, connector::ConnectionService, scanner::ScanService,
-    },
+    core::{authorization::AuthorizationService, service::InterfaceService},
     protocol::{JsonRpcNotification, JsonRpcRequest},
     transport::unix_socket::{
         handler::RequestHandler,
@@ -31,15 +29,10 @@ impl<B: WifiBackend> UnixSocketServer<B> {
     /// Create a new Unix socket server
     pub fn new(
         socket_path: String,
-        scan_service: Arc<ScanService<B>>,
-        connect_service: Arc<ConnectionService<B>>,
+        interfaces: Vec<Arc<InterfaceService<B>>>,
         auth_service: Arc<AuthorizationService>,
     ) -> Self {
-        let handler = Arc::new(RequestHandler::new(
-            scan_service,
-            connect_service,
-            auth_service,
-        ));
+        let handler = Arc::new(RequestHandler::new(interfaces, auth_service));
         let (notification_tx, _) = broadcast::channel(100);
 
         Self {
@@ -114,6 +107,16 @@ impl<B: WifiBackend> UnixSocketServer<B> {
 
         info!("New client connected: {}", session.id());
 
+        let result = Self::serve_client(&session, &mut reader, &handler).await;
+        handler.end_session(session.id()).await;
+        result
+    }
+
+    async fn serve_client(
+        session: &UnixSocketSession,
+        reader: &mut SessionReader,
+        handler: &RequestHandler<B>,
+    ) -> std::io::Result<()> {
         loop {
             match reader.read_line().await? {
                 Some(line) => {
@@ -123,7 +126,9 @@ impl<B: WifiBackend> UnixSocketServer<B> {
 
                     match serde_json::from_str::<JsonRpcRequest>(&line) {
                         Ok(request) => {
-                            let response = handler.handle_request(request).await;
+                            let response = handler
+                                .handle_session_request(Some(session.id()), request)
+                                .await;
                             if let Err(e) = session.send_response(&response).await {
                                 error!("Error sending response: {}", e);
                                 break;
@@ -160,14 +165,12 @@ mod tests {
         let socket_path = dir.path().join("test.sock");
 
         let backend = Arc::new(MockWifiBackend::new());
-        let scan_service = Arc::new(ScanService::new(backend.clone()));
-        let connect_service = Arc::new(ConnectionService::new(backend));
+        let interface = Arc::new(InterfaceService::new("wlan0".to_string(), backend));
         let auth_service = Arc::new(AuthorizationService::new("test".to_string()));
 
         let _server = UnixSocketServer::new(
             socket_path.to_str().unwrap().to_string(),
-            scan_service,
-            connect_service,
+            vec![interface],
             auth_service,
         );
 
@@ -184,21 +187,20 @@ mod tests {
         let backend = Arc::new(MockWifiBackend::new());
         backend
             .set_scan_results(vec![WifiNetwork {
-                ssid: "TestNet".to_string(),
+                ssid: "TestNet".into(),
                 mac: "aa:bb:cc:dd:ee:ff".to_string(),
                 channel: 6,
                 rssi: -65,
+                ..Default::default()
             }])
             .await;
 
-        let scan_service = Arc::new(ScanService::new(backend.clone()));
-        let connect_service = Arc::new(ConnectionService::new(backend));
+        let interface = Arc::new(InterfaceService::new("wlan0".to_string(), backend));
         let auth_service = Arc::new(AuthorizationService::new("test".to_string()));
 
         let server = UnixSocketServer::new(
             socket_path.to_str().unwrap().to_string(),
-            scan_service,
-            connect_service,
+            vec![interface],
             auth_service,
         );
 
@@ -214,7 +216,8 @@ mod tests {
         // Connect and send request
         let mut client = UnixStream::connect(&socket_path_clone).await.unwrap();
 
-        let request = JsonRpcRequest::new(crate::protocol::Request::Scan, RequestId::Number(1));
+        let request =
+            JsonRpcRequest::new(crate::protocol::Request::Scan(None), RequestId::Number(1));
         let json = serde_json::to_string(&request).unwrap();
 
         client.write_all(json.as_bytes()).await.unwrap();
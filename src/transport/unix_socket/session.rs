@@ -128,7 +128,7 @@ mod tests {
         let mut reader = SessionReader::new(read_half);
 
         // Write a request from client
-        let request = JsonRpcRequest::new(Request::Scan, RequestId::Number(1));
+        let request = JsonRpcRequest::new(Request::Scan(None), RequestId::Number(1));
         let json = serde_json::to_string(&request).unwrap();
         let (_client_read, mut client_write) = client.into_split();
 